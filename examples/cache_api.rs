@@ -0,0 +1,67 @@
+//! Lookup API backed by shared cache. Products are loaded from slow source only on cache miss,
+//! so other processes serving the same API reuse loaded values until they expire.
+//!
+//! Redis address is read from `REDIS_URL` env variable, `redis://127.0.0.1/` is used by default.
+
+use redis_ipc::error::IpcError;
+use redis_ipc::{helpers, Cache};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Cached product details.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Product {
+    id: String,
+    name: String,
+    price: u32,
+}
+
+/// Loads product from slow source, e.g. database.
+fn load_product(id: &str) -> Product {
+    thread::sleep(Duration::from_millis(200));
+
+    Product {
+        id: id.to_string(),
+        name: format!("Product {id}"),
+        price: 100,
+    }
+}
+
+/// Returns product from cache or loads it and caches it.
+fn get_product(cache: &Cache<Product>, id: &str) -> Result<Product, IpcError> {
+    if let Some(element) = cache.get(id)? {
+        return Ok(element.into_content());
+    }
+
+    let product = load_product(id);
+    cache.set(id, &product)?;
+
+    Ok(product)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let url = env::var("REDIS_URL").unwrap_or_else(|_| String::from("redis://127.0.0.1/"));
+    let pool = helpers::connect(url)?;
+
+    let cache = Cache::<Product>::new(
+        pool,
+        "examples:products",
+        Some(Duration::from_secs(60)),
+        None,
+    );
+
+    // the second round is served from cache
+    for _ in 0..2 {
+        for id in ["a1", "b2", "c3"] {
+            let start = Instant::now();
+            let product = get_product(&cache, id)?;
+
+            println!("{} ({}) in {:?}", product.name, product.id, start.elapsed());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+//! Reads order events published by `producer` and `worker` examples as a member of consumer
+//! group, so several instances share events. Stops when no event arrives for 5 seconds.
+//!
+//! Redis address is read from `REDIS_URL` env variable, `redis://127.0.0.1/` is used by default.
+
+use redis_ipc::{helpers, GroupReadStream};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::error::Error;
+use std::process;
+use std::time::Duration;
+
+/// Event published for every order.
+#[derive(Debug, Serialize, Deserialize)]
+struct OrderEvent {
+    order_id: u32,
+    status: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let url = env::var("REDIS_URL").unwrap_or_else(|_| String::from("redis://127.0.0.1/"));
+    let pool = helpers::connect(url)?;
+
+    let consumer = format!("consumer-{}", process::id());
+    let events = GroupReadStream::<OrderEvent>::new(
+        pool,
+        "examples:order-events",
+        "examples",
+        &consumer,
+        Some(Duration::from_secs(5)),
+    );
+
+    // reading ends on timeout
+    while let Some(event) = events.b_next()? {
+        let content = event.get_content();
+        println!("order {} is {}", content.order_id, content.status);
+
+        events.ack(event.get_id())?;
+    }
+
+    let lag = events.lag()?;
+    println!("group is {} events behind", lag.get_messages());
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Publishes orders to task queue and order events to stream.
+//!
+//! Run together with consumers:
+//! ```text
+//! cargo run --example worker
+//! cargo run --example events
+//! cargo run --example producer
+//! ```
+//!
+//! Redis address is read from `REDIS_URL` env variable, `redis://127.0.0.1/` is used by default.
+
+use redis_ipc::{helpers, WriteQueue, WriteStream};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::error::Error;
+
+/// Task processed by workers.
+#[derive(Debug, Serialize, Deserialize)]
+struct Order {
+    id: u32,
+    product: String,
+    quantity: u32,
+}
+
+/// Event published for every order.
+#[derive(Debug, Serialize, Deserialize)]
+struct OrderEvent {
+    order_id: u32,
+    status: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let url = env::var("REDIS_URL").unwrap_or_else(|_| String::from("redis://127.0.0.1/"));
+    let pool = helpers::connect(url)?;
+
+    let mut orders = WriteQueue::<Order>::new(pool.clone(), "examples:orders");
+    let events = WriteStream::<OrderEvent>::new(pool, "examples:order-events", 1000);
+
+    for id in 1..=10 {
+        let order = Order {
+            id,
+            product: format!("product-{}", id % 3),
+            quantity: id,
+        };
+        orders.publish(&order)?;
+
+        let event_id = events.publish(&OrderEvent {
+            order_id: id,
+            status: String::from("placed"),
+        })?;
+
+        println!("published order {id}, event {event_id}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Request/reply on top of task queues. Server reads requests from shared queue and publishes
+//! replies to queue named in the request, which is unique for every client. Both sides run in
+//! this process.
+//!
+//! Redis address is read from `REDIS_URL` env variable, `redis://127.0.0.1/` is used by default.
+
+use redis_ipc::scope::IpcScope;
+use redis_ipc::{helpers, ReadQueue, RedisPool, WriteQueue};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::error::Error;
+use std::process;
+use std::time::Duration;
+
+/// Name of the queue of requests.
+const REQUESTS: &str = "examples:rpc:requests";
+
+/// Request of price of the product.
+#[derive(Debug, Serialize, Deserialize)]
+struct PriceRequest {
+    product: String,
+    /// Queue, which reply is published to
+    reply_to: String,
+}
+
+/// Reply to [`PriceRequest`].
+#[derive(Debug, Serialize, Deserialize)]
+struct PriceReply {
+    product: String,
+    price: u32,
+}
+
+/// Serves requests until scope is cancelled.
+fn serve(pool: RedisPool, requests: ReadQueue<PriceRequest>) {
+    // iteration ends when scope is cancelled
+    for request in requests {
+        let request = request.get_content();
+
+        let reply = PriceReply {
+            product: request.product.clone(),
+            price: request.product.len() as u32 * 100,
+        };
+
+        let mut replies = WriteQueue::<PriceReply>::new(pool.clone(), &request.reply_to);
+        if let Err(error) = replies.publish(&reply) {
+            eprintln!("server: cannot reply: {error}");
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let url = env::var("REDIS_URL").unwrap_or_else(|_| String::from("redis://127.0.0.1/"));
+    let pool = helpers::connect(url)?;
+
+    let mut scope = IpcScope::new();
+
+    let requests =
+        ReadQueue::new(pool.clone(), REQUESTS, None).with_cancellation(scope.get_token());
+    let server_pool = pool.clone();
+    scope.spawn(move |_| serve(server_pool, requests));
+
+    // client
+    let reply_to = format!("examples:rpc:replies:{}", process::id());
+    let mut requests = WriteQueue::<PriceRequest>::new(pool.clone(), REQUESTS);
+    let mut replies = ReadQueue::<PriceReply>::new(pool, &reply_to, Some(Duration::from_secs(5)));
+
+    for product in ["apple", "banana", "cherry"] {
+        requests.publish(&PriceRequest {
+            product: product.to_string(),
+            reply_to: reply_to.clone(),
+        })?;
+
+        let Some(reply) = replies.b_next()? else {
+            eprintln!("no reply for {product}");
+            continue;
+        };
+        println!("{} costs {}", reply.get_content().product, reply.get_content().price);
+    }
+
+    scope.shutdown();
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+//! Processes orders published by `producer` example with several worker threads owned by one
+//! scope. Workers stop when no order arrives for 5 seconds.
+//!
+//! Redis address is read from `REDIS_URL` env variable, `redis://127.0.0.1/` is used by default.
+
+use redis_ipc::scope::IpcScope;
+use redis_ipc::{helpers, ReadQueue, WriteStream};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+/// Task published by producer.
+#[derive(Debug, Serialize, Deserialize)]
+struct Order {
+    id: u32,
+    product: String,
+    quantity: u32,
+}
+
+/// Event published for every order.
+#[derive(Debug, Serialize, Deserialize)]
+struct OrderEvent {
+    order_id: u32,
+    status: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let url = env::var("REDIS_URL").unwrap_or_else(|_| String::from("redis://127.0.0.1/"));
+    let pool = helpers::connect(url)?;
+
+    let mut scope = IpcScope::new();
+
+    for worker in 0..3 {
+        let mut orders = ReadQueue::<Order>::new(
+            pool.clone(),
+            "examples:orders",
+            Some(Duration::from_secs(5)),
+        )
+        .with_cancellation(scope.get_token());
+        let events = WriteStream::<OrderEvent>::new(pool.clone(), "examples:order-events", 1000);
+
+        scope.spawn(move |_| {
+            // reading ends on timeout, error or cancellation
+            while let Ok(Some(message)) = orders.b_next() {
+                let order = message.get_content();
+                println!("worker {worker}: {} x {}", order.quantity, order.product);
+
+                let event = OrderEvent {
+                    order_id: order.id,
+                    status: String::from("processed"),
+                };
+                if let Err(error) = events.publish(&event) {
+                    eprintln!("worker {worker}: cannot publish event: {error}");
+                }
+            }
+        });
+    }
+
+    // waits until all workers are done
+    scope.shutdown();
+
+    Ok(())
+}
@@ -0,0 +1,19 @@
+//! Queue payloads are pushed to redis lists by other processes, possibly by other clients.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use redis_ipc::codec::{decode_queue_message_with, id_timestamp, DecodeMode};
+use serde_json::Value;
+
+fuzz_target!(|payload: &str| {
+    for mode in [DecodeMode::Lenient, DecodeMode::Strict] {
+        if let Ok(message) = decode_queue_message_with::<Value>(payload, mode) {
+            let _ = message.sent_at();
+            let _ = message.get_unique_key();
+            let _ = message.is_redelivered();
+        }
+    }
+
+    let _ = id_timestamp(payload);
+});
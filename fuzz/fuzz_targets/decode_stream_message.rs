@@ -0,0 +1,27 @@
+//! Stream entries are added by other processes, fields other than content are kept as extra.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use redis_ipc::codec::{decode_stream_message_with, DecodeMode};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Flat encoded content of known shape, so its fields are decoded from strings.
+#[derive(serde::Deserialize)]
+#[allow(dead_code)]
+struct Flat {
+    name: String,
+    count: u32,
+    enabled: Option<bool>,
+}
+
+fuzz_target!(|entry: (&str, Vec<(String, String)>)| {
+    let (id, fields) = entry;
+    let fields: HashMap<String, String> = fields.into_iter().collect();
+
+    for mode in [DecodeMode::Lenient, DecodeMode::Strict] {
+        let _ = decode_stream_message_with::<Value>(id, &fields, mode);
+        let _ = decode_stream_message_with::<Flat>(id, &fields, mode);
+    }
+});
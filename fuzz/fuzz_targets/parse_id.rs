@@ -0,0 +1,15 @@
+//! Stream ids are read from redis replies and checkpoints written by other processes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use redis_ipc::codec::{parse_id, stringify_id};
+
+fuzz_target!(|id: &str| {
+    let Ok(parsed) = parse_id(id) else {
+        return;
+    };
+
+    // formatted id is parsed back to the same id
+    assert_eq!(parse_id(&stringify_id(&parsed)).unwrap(), parsed);
+});
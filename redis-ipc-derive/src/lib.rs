@@ -0,0 +1,98 @@
+//! # Introduction
+//! Derive macros for `redis_ipc` crate. Please use them through `redis_ipc` with `derive` feature
+//! enabled, instead of depending on this crate directly.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, LitInt, LitStr};
+
+/// Implements `redis_ipc::message::Message` trait.
+///
+/// # Attributes
+///
+/// All attributes are optional and are passed in `#[ipc(...)]`:
+///
+/// * name - stable type name, defaults to type identifier
+/// * version - schema version, defaults to `1`
+/// * channel - default channel name, defaults to type identifier in snake case
+///
+/// # Examples
+/// ```ignored
+/// #[derive(Serialize, Deserialize, IpcMessage)]
+/// #[ipc(name = "order.created", version = 2, channel = "orders")]
+/// struct OrderCreated {
+///     id: u64,
+/// }
+/// ```
+#[proc_macro_derive(IpcMessage, attributes(ipc))]
+pub fn derive_ipc_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_ipc_message(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_ipc_message(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+
+    let mut name = ident.to_string();
+    let mut version = 1_u32;
+    let mut channel = to_snake_case(&ident.to_string());
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("ipc")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("version") {
+                version = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+            } else if meta.path.is_ident("channel") {
+                channel = meta.value()?.parse::<LitStr>()?.value();
+            } else {
+                return Err(meta.error("unsupported ipc attribute, expected name, version or channel"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::redis_ipc::message::Message for #ident #ty_generics #where_clause {
+            const TYPE_NAME: &'static str = #name;
+            const SCHEMA_VERSION: u32 = #version;
+            const CHANNEL: &'static str = #channel;
+        }
+    })
+}
+
+/// Converts `CamelCase` identifier into `snake_case`.
+fn to_snake_case(ident: &str) -> String {
+    let mut result = String::with_capacity(ident.len() + 4);
+
+    for (index, c) in ident.chars().enumerate() {
+        if c.is_uppercase() {
+            if index != 0 {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_case_conversion() {
+        assert_eq!(to_snake_case("OrderCreated"), "order_created");
+        assert_eq!(to_snake_case("Order"), "order");
+    }
+}
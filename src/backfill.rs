@@ -0,0 +1,181 @@
+//! Backfill of historical data (e.g. read with database cursor) into queues and streams, which
+//! bootstraps new consumers.
+//!
+//! Source yields messages together with their position in source (e.g. primary key). Position of
+//! the last published message is saved in redis as checkpoint, so backfill interrupted by crash
+//! is resumed by reading source after [`Backfill::checkpoint()`](Backfill::checkpoint).
+//!
+//! # Examples
+//! ```ignored
+//! let backfill = Backfill::new(pool.clone(), "orders:backfill").with_rate_limit(500);
+//! let mut stream = WriteStream::new(pool, "orders", 1_000_000);
+//!
+//! let after = backfill.checkpoint()?;
+//! let rows = db.orders_after(after).map(|order| (order.id.to_string(), order));
+//! backfill.run(rows, &mut stream)?;
+//! ```
+
+use crate::error::IpcError;
+use crate::queue::WriteQueue;
+use crate::stream::WriteStream;
+use crate::RedisPool;
+use redis::Commands;
+use serde::Serialize;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default number of published messages, after which checkpoint is saved.
+const DEFAULT_CHECKPOINT_EVERY: usize = 100;
+
+/// Target of [`Backfill`], implemented for [`WriteStream`] and [`WriteQueue`].
+pub trait BackfillTarget<MessageContent> {
+    /// Publishes single message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when message can't be published.
+    fn publish_message(&mut self, message: &MessageContent) -> Result<(), IpcError>;
+}
+
+impl<MessageContent: Serialize> BackfillTarget<MessageContent> for WriteStream<MessageContent> {
+    fn publish_message(&mut self, message: &MessageContent) -> Result<(), IpcError> {
+        self.publish(message).map(|_| ())
+    }
+}
+
+impl<MessageContent: Serialize> BackfillTarget<MessageContent> for WriteQueue<MessageContent> {
+    fn publish_message(&mut self, message: &MessageContent) -> Result<(), IpcError> {
+        self.publish(message)
+    }
+}
+
+/// Publishes messages read from source to queue or stream with optional rate limit and saves
+/// checkpoint of the progress in redis. See [module docs](self) for details.
+///
+/// Message published just before crash may be published again after resume, when checkpoint
+/// wasn't saved yet, so consumers should be idempotent.
+#[derive(Clone)]
+pub struct Backfill {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Redis key, which stores checkpoint
+    key: Arc<String>,
+    /// Max number of published messages per second, [`None`] if not limited
+    rate_limit: Option<u32>,
+    /// Number of published messages, after which checkpoint is saved
+    checkpoint_every: usize,
+}
+
+impl Backfill {
+    /// Creates backfill, which stores checkpoint in redis key with given name.
+    pub fn new(pool: RedisPool, key: &str) -> Self {
+        Self {
+            pool,
+            key: Arc::new(key.to_string()),
+            rate_limit: None,
+            checkpoint_every: DEFAULT_CHECKPOINT_EVERY,
+        }
+    }
+
+    /// Limits number of messages published per second, so backfill doesn't starve live traffic.
+    pub fn with_rate_limit(mut self, per_second: u32) -> Self {
+        self.rate_limit = Some(per_second.max(1));
+        self
+    }
+
+    /// Sets number of published messages (at least one), after which checkpoint is saved. Less
+    /// frequent checkpoints are cheaper, but more messages are published again after crash.
+    pub fn with_checkpoint_every(mut self, count: usize) -> Self {
+        self.checkpoint_every = count.max(1);
+        self
+    }
+
+    /// Returns position of the last published message saved by previous run or [`None`] if
+    /// backfill wasn't started yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn checkpoint(&self) -> Result<Option<String>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        Ok(conn.get::<&str, Option<String>>(&self.key)?)
+    }
+
+    /// Removes saved checkpoint, so backfill is started from the beginning.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn reset(&self) -> Result<(), IpcError> {
+        let mut conn = self.pool.get()?;
+
+        conn.del::<&str, ()>(&self.key)?;
+
+        Ok(())
+    }
+
+    /// Publishes all messages of `source`, given together with their positions, to `target`.
+    /// Returns number of published messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure. Checkpoint of messages
+    /// published before the failure is saved when possible.
+    pub fn run<MessageContent, I, T>(&self, source: I, target: &mut T) -> Result<usize, IpcError>
+    where
+        I: IntoIterator<Item = (String, MessageContent)>,
+        T: BackfillTarget<MessageContent>,
+    {
+        let started = Instant::now();
+        let mut published = 0;
+        let mut position: Option<String> = None;
+
+        for (next_position, message) in source {
+            if let Err(error) = target.publish_message(&message) {
+                if let Some(position) = &position {
+                    let _ = self.save(position);
+                }
+                return Err(error);
+            }
+
+            published += 1;
+
+            if published % self.checkpoint_every == 0 {
+                self.save(&next_position)?;
+            }
+            position = Some(next_position);
+
+            self.throttle(started, published);
+        }
+
+        if let Some(position) = &position {
+            self.save(position)?;
+        }
+
+        Ok(published)
+    }
+
+    /// Saves checkpoint.
+    fn save(&self, position: &str) -> Result<(), IpcError> {
+        let mut conn = self.pool.get()?;
+
+        conn.set::<&str, &str, ()>(&self.key, position)?;
+
+        Ok(())
+    }
+
+    /// Sleeps until publishing `published` messages since `started` doesn't exceed rate limit.
+    fn throttle(&self, started: Instant, published: usize) {
+        let Some(rate_limit) = self.rate_limit else {
+            return;
+        };
+
+        let expected = Duration::from_secs_f64(published as f64 / f64::from(rate_limit));
+
+        if let Some(ahead) = expected.checked_sub(started.elapsed()) {
+            thread::sleep(ahead);
+        }
+    }
+}
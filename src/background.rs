@@ -0,0 +1,54 @@
+//! Background threads of this crate, e.g. cache refresh, queue reaping or status reports, which
+//! run their task periodically until their [`BackgroundHandle`] is dropped.
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Handle of background thread. Thread is stopped when handle is dropped.
+pub struct BackgroundHandle {
+    /// Dropping sender wakes up and stops background thread
+    stop: Option<Sender<()>>,
+    /// Background thread
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BackgroundHandle {
+    /// Stops background task and waits until its thread finishes.
+    pub fn stop(self) {
+        // done by drop
+    }
+}
+
+impl Drop for BackgroundHandle {
+    fn drop(&mut self) {
+        self.stop.take();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Runs `task` every `interval` in background thread, first time immediately. Task is dropped
+/// in background thread, when handle is dropped, so it may clean up in its drop.
+pub(crate) fn spawn_periodic<F>(interval: Duration, mut task: F) -> BackgroundHandle
+where
+    F: FnMut() + Send + 'static,
+{
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    let thread = thread::spawn(move || loop {
+        task();
+
+        match stopped.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => {}
+            _ => break,
+        }
+    });
+
+    BackgroundHandle {
+        stop: Some(stop),
+        thread: Some(thread),
+    }
+}
@@ -0,0 +1,116 @@
+//! Results of batch operations, see [`BatchResult`].
+
+use crate::error::IpcError;
+
+/// Result of batch operation, e.g.
+/// [`WriteStream::publish_many()`](crate::WriteStream::publish_many), with outcome of every item.
+/// Items are identified with their indices in the batch, so only failed ones may be retried.
+#[derive(Debug)]
+pub struct BatchResult<T> {
+    /// Results of successful items with their indices
+    successes: Vec<(usize, T)>,
+    /// Errors of failed items with their indices
+    failures: Vec<(usize, IpcError)>,
+}
+
+impl<T> Default for BatchResult<T> {
+    // Implemented manually, because derive would require `T: Default`.
+    fn default() -> Self {
+        Self {
+            successes: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl<T> BatchResult<T> {
+    /// Creates empty result.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records success of item with given index.
+    pub fn push_success(&mut self, index: usize, value: T) {
+        self.successes.push((index, value));
+    }
+
+    /// Records failure of item with given index.
+    pub fn push_failure(&mut self, index: usize, error: IpcError) {
+        self.failures.push((index, error));
+    }
+
+    /// Returns results of successful items with their indices, in order of the batch.
+    pub fn get_successes(&self) -> &[(usize, T)] {
+        &self.successes
+    }
+
+    /// Returns errors of failed items with their indices, in order of the batch.
+    pub fn get_failures(&self) -> &[(usize, IpcError)] {
+        &self.failures
+    }
+
+    /// Returns indices of failed items, e.g. to pick items for retry.
+    pub fn failed_indices(&self) -> Vec<usize> {
+        self.failures.iter().map(|(index, _)| *index).collect()
+    }
+
+    /// Checks if all items succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns number of items in the batch.
+    pub fn len(&self) -> usize {
+        self.successes.len() + self.failures.len()
+    }
+
+    /// Checks if the batch had no items.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Converts result into values of all items in order of the batch or the first error, if
+    /// any item failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error of the first failed item.
+    pub fn into_result(mut self) -> Result<Vec<T>, IpcError> {
+        self.failures.sort_by_key(|(index, _)| *index);
+
+        match self.failures.into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => {
+                self.successes.sort_by_key(|(index, _)| *index);
+                Ok(self.successes.into_iter().map(|(_, value)| value).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::IpcErrorKind;
+
+    #[test]
+    fn collects_successes_and_failures() {
+        let mut result = BatchResult::new();
+        result.push_success(0, "a");
+        result.push_failure(1, IpcError::new(IpcErrorKind::InvalidData, "invalid"));
+        result.push_success(2, "c");
+
+        assert_eq!(result.len(), 3);
+        assert!(!result.is_complete());
+        assert_eq!(result.failed_indices(), vec![1]);
+        assert_eq!(result.get_successes(), &[(0, "a"), (2, "c")]);
+        assert!(result.into_result().is_err());
+
+        let mut result = BatchResult::new();
+        result.push_success(1, "b");
+        result.push_success(0, "a");
+
+        assert!(result.is_complete());
+        assert_eq!(result.into_result().unwrap(), vec!["a", "b"]);
+    }
+}
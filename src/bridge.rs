@@ -0,0 +1,260 @@
+//! HTTP/JSON bridge, which exposes named queues and streams to systems without redis client.
+//! Messages use the same envelope as [`WriteQueue`](crate::WriteQueue) and
+//! [`WriteStream`](crate::WriteStream), so bridge clients and native clients may communicate with
+//! each other.
+//!
+//! Available routes:
+//!
+//! * `POST /queues/{name}` - publishes JSON body as queue task, returns `{"uuid": "..."}`
+//! * `GET /queues/{name}?timeout_ms=<ms>` - long-polls next task, returns queue envelope or
+//!   `204 No Content` on timeout
+//! * `POST /streams/{name}` - publishes JSON body on stream, returns `{"id": "<ms>-<seq>"}`
+//! * `GET /streams/{name}?after=<id>&timeout_ms=<ms>` - long-polls next stream message after
+//!   given id (or the first new one), returns `{"id": "...", "content": ...}` or
+//!   `204 No Content` on timeout
+//!
+//! # Examples
+//! ```ignored
+//! let bridge = HttpBridge::new(pool);
+//!
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
+//! axum::serve(listener, bridge.router()).await?;
+//! ```
+
+use crate::error::{IpcError, IpcErrorKind};
+use crate::codec::{parse_id, DecodeMode, WriteQueueMessage, CONTENT_FIELD};
+use crate::stream::parse_first_read_reply;
+use crate::RedisPool;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use redis::streams::{StreamMaxlen, StreamReadOptions, StreamReadReply};
+use redis::Commands;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task;
+use uuid::Uuid;
+
+/// Default maximum time of single long-poll request.
+const DEFAULT_MAX_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default maximum size of streams written by the bridge.
+const DEFAULT_STREAM_MAX_SIZE: usize = 1024;
+
+/// HTTP bridge configuration. Use [`HttpBridge::router()`](HttpBridge::router) to build
+/// [`Router`], which may be served with axum.
+#[derive(Clone)]
+pub struct HttpBridge {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Pool used for long-poll requests, if it's [`None`] `pool` is used
+    blocking_pool: Option<RedisPool>,
+    /// Maximum time of single long-poll request
+    max_timeout: Duration,
+    /// Max size of streams written by the bridge
+    stream_max_size: usize,
+}
+
+impl HttpBridge {
+    /// Creates bridge with default limits: 30s long-poll timeout and 1024 stream size.
+    pub fn new(pool: RedisPool) -> Self {
+        Self {
+            pool,
+            blocking_pool: None,
+            max_timeout: DEFAULT_MAX_TIMEOUT,
+            stream_max_size: DEFAULT_STREAM_MAX_SIZE,
+        }
+    }
+
+    /// Sets separate pool for long-poll requests, so they can't starve publishing requests.
+    pub fn with_blocking_pool(mut self, blocking_pool: RedisPool) -> Self {
+        self.blocking_pool = Some(blocking_pool);
+        self
+    }
+
+    /// Sets maximum time of single long-poll request. Longer timeouts requested by clients are
+    /// shortened to this value.
+    pub fn with_max_timeout(mut self, max_timeout: Duration) -> Self {
+        self.max_timeout = max_timeout;
+        self
+    }
+
+    /// Sets max size of streams written by the bridge, see [`WriteStream`](crate::WriteStream).
+    pub fn with_stream_max_size(mut self, stream_max_size: u32) -> Self {
+        self.stream_max_size = stream_max_size as usize;
+        self
+    }
+
+    /// Builds router with bridge routes.
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/queues/{name}", get(queue_next).post(queue_publish))
+            .route("/streams/{name}", get(stream_next).post(stream_publish))
+            .with_state(Arc::new(self))
+    }
+
+    /// Returns requested timeout limited by max timeout. Missing or zero timeout means max one.
+    fn timeout(&self, timeout_ms: Option<u64>) -> Duration {
+        timeout_ms
+            .map(Duration::from_millis)
+            .filter(|timeout| !timeout.is_zero())
+            .map_or(self.max_timeout, |timeout| timeout.min(self.max_timeout))
+    }
+
+    /// Returns pool for long-poll requests.
+    fn blocking_pool(&self) -> &RedisPool {
+        self.blocking_pool.as_ref().unwrap_or(&self.pool)
+    }
+}
+
+/// Query of long-poll requests.
+#[derive(Deserialize)]
+struct PollQuery {
+    /// Long-poll timeout
+    timeout_ms: Option<u64>,
+    /// Id of the last read stream message
+    after: Option<String>,
+}
+
+/// [`IpcError`] wrapper, which maps error kind to HTTP status.
+struct BridgeError(IpcError);
+
+impl From<IpcError> for BridgeError {
+    fn from(error: IpcError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for BridgeError {
+    fn into_response(self) -> Response {
+        let status = match self.0.kind() {
+            IpcErrorKind::InvalidData => StatusCode::BAD_REQUEST,
+            IpcErrorKind::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            IpcErrorKind::ConnectionFailure => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        (status, Json(json!({ "error": self.0.to_string() }))).into_response()
+    }
+}
+
+/// Runs blocking redis operation outside of async runtime.
+async fn blocking<T, F>(operation: F) -> Result<T, BridgeError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, IpcError> + Send + 'static,
+{
+    task::spawn_blocking(operation)
+        .await
+        .map_err(|error| IpcError::new(IpcErrorKind::Other, error))?
+        .map_err(BridgeError)
+}
+
+async fn queue_publish(
+    State(bridge): State<Arc<HttpBridge>>,
+    Path(name): Path<String>,
+    Json(content): Json<Value>,
+) -> Result<Response, BridgeError> {
+    let uuid = Uuid::new_v4().to_string();
+    let message = WriteQueueMessage::new(uuid.clone(), content);
+    let json = serde_json::to_string(&message).map_err(IpcError::from)?;
+
+    blocking(move || {
+        let mut conn = bridge.pool.get()?;
+        conn.lpush::<&str, &str, ()>(&name, &json)?;
+        Ok(())
+    })
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "uuid": uuid }))).into_response())
+}
+
+async fn queue_next(
+    State(bridge): State<Arc<HttpBridge>>,
+    Path(name): Path<String>,
+    Query(query): Query<PollQuery>,
+) -> Result<Response, BridgeError> {
+    let timeout = bridge.timeout(query.timeout_ms);
+
+    let message = blocking(move || {
+        let mut conn = bridge.blocking_pool().get()?;
+        // reply is ["queue_name", "queue_elem"] or nil on timeout
+        let res = conn.brpop::<&str, Option<(String, String)>>(&name, timeout.as_secs_f64())?;
+        Ok(res.map(|(_, message)| message))
+    })
+    .await?;
+
+    let Some(message) = message else {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+
+    let envelope = serde_json::from_str::<Value>(&message).map_err(IpcError::from)?;
+
+    Ok(Json(envelope).into_response())
+}
+
+async fn stream_publish(
+    State(bridge): State<Arc<HttpBridge>>,
+    Path(name): Path<String>,
+    Json(content): Json<Value>,
+) -> Result<Response, BridgeError> {
+    let json = serde_json::to_string(&content).map_err(IpcError::from)?;
+
+    let id = blocking(move || {
+        let mut conn = bridge.pool.get()?;
+        let id = conn.xadd_maxlen::<&str, &str, &str, &str, String>(
+            &name,
+            StreamMaxlen::Approx(bridge.stream_max_size),
+            "*",
+            &[(CONTENT_FIELD, &json)],
+        )?;
+        Ok(id)
+    })
+    .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "id": id }))).into_response())
+}
+
+async fn stream_next(
+    State(bridge): State<Arc<HttpBridge>>,
+    Path(name): Path<String>,
+    Query(query): Query<PollQuery>,
+) -> Result<Response, BridgeError> {
+    let timeout = bridge.timeout(query.timeout_ms);
+
+    let after = match query.after {
+        Some(after) => {
+            parse_id(&after).map_err(IpcError::from)?.to_string()
+        }
+        // "$" is redis symbol, for first message after xread()
+        None => String::from("$"),
+    };
+
+    let reply = blocking(move || {
+        let mut conn = bridge.blocking_pool().get()?;
+        let timeout = usize::try_from(timeout.as_millis()).unwrap_or(usize::MAX);
+        let opts = StreamReadOptions::default().count(1).block(timeout);
+        let reply =
+            conn.xread_options::<&str, &str, Option<StreamReadReply>>(&[&name], &[&after], &opts)?;
+        Ok(reply)
+    })
+    .await?;
+
+    let message = reply
+        .map(|reply| parse_first_read_reply::<Value>(&reply, DecodeMode::default()))
+        .transpose()?
+        .flatten();
+
+    let Some(message) = message else {
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    };
+
+    Ok(Json(json!({
+        "id": message.get_id().to_string(),
+        "content": message.get_content(),
+    }))
+    .into_response())
+}
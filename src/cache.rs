@@ -1,40 +1,127 @@
+use crate::cancel::CancellationToken;
+use crate::channel::Channel;
 use crate::error::{IpcError, IpcErrorKind};
-use crate::{ OptionalTimeout, OptionalTtl, RedisPool, Timeout};
+use crate::helpers::{jittered_ttl, stable_hash, timestamp_u128_now};
+use crate::{ OptionalTimeout, OptionalTtl, RedisConnection, RedisPool, Timeout};
 use redis::{Commands, ExpireOption};
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use std::thread;
 use std::time;
 
-/// Wrapper struct for elements in cache. 
-#[derive(Serialize, Deserialize)]
-pub struct CacheElement<ElementContent> {
-    timestamp: u128,
-    content: ElementContent,
-}
+#[cfg(feature = "aio")]
+mod aio;
+mod chain;
+mod guarded;
+mod memory;
+mod refresh;
 
-impl<ElementContent> CacheElement<ElementContent> {
-    /// Creates a new `CacheElement`. `timestamp` param should be unix timestamp.
-    pub fn new(timestamp: u128, content: ElementContent) -> Self {
-        Self { timestamp, content }
-    }
+#[cfg(feature = "aio")]
+pub use aio::AsyncCache;
+pub use chain::{CacheChain, CacheLevel};
+pub use guarded::{EntryGuard, GuardedMap};
+pub use memory::MemoryCache;
+pub use refresh::RefreshHandle;
+use crate::codec::decode_cache_element_with;
+pub use crate::codec::{CacheElement, DecodeMode};
 
-    /// Getter for timestamp field
-    pub fn get_timestamp_128(&self) -> u128 {
-        self.timestamp
-    }
+/// Suffix of the redis hash, which stores versions of cache elements.
+const VERSIONS_SUFFIX: &str = ":versions";
+/// Suffix of the redis hash, which stores quarantined elements.
+const QUARANTINE_SUFFIX: &str = ":quarantine";
 
-    /// Getter for content field
-    pub fn get_content(&self) -> &ElementContent {
-        &self.content
-    }
+/// Sets element only if its current version (missing version means `0`) is equal to `ARGV[2]`.
+/// Returns `-1` on success or current version on conflict.
+const SET_IF_VERSION_SCRIPT: &str = r"
+local current = tonumber(redis.call('HGET', KEYS[2], ARGV[1]) or '0')
+if current ~= tonumber(ARGV[2]) then
+    return current
+end
+redis.call('HSET', KEYS[2], ARGV[1], current + 1)
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[3])
+return -1
+";
+
+/// Bumps version of element `ARGV[1]` and sets element with content `ARGV[3]` and timestamp
+/// `ARGV[2]` in one step, so element version can't diverge from the counter. Element is built
+/// in the same shape as serialized [`CacheElement`]. Returns new version.
+const SET_SCRIPT: &str = r#"
+local version = redis.call('HINCRBY', KEYS[2], ARGV[1], 1)
+local element = '{"timestamp":' .. ARGV[2] .. ',"version":' .. version
+    .. ',"content":' .. ARGV[3] .. '}'
+redis.call('HSET', KEYS[1], ARGV[1], element)
+return version
+"#;
+
+/// Removes element `ARGV[1]` only if it still holds corrupted payload `ARGV[2]`, so element set
+/// meanwhile by other client isn't lost. Removed payload is moved to quarantine hash `KEYS[2]`,
+/// when `ARGV[3]` is `1`. Returns `1` if element was removed.
+const REPAIR_SCRIPT: &str = r"
+if redis.call('HGET', KEYS[1], ARGV[1]) ~= ARGV[2] then
+    return 0
+end
+if ARGV[3] == '1' then
+    redis.call('HSET', KEYS[2], ARGV[1], ARGV[2])
+end
+return redis.call('HDEL', KEYS[1], ARGV[1])
+";
+
+/// Returns version of element `ARGV[1]` (missing version means `0`) or `nil` if element doesn't
+/// exist, e.g. it expired before its version.
+const GET_VERSION_SCRIPT: &str = r"
+if redis.call('HEXISTS', KEYS[1], ARGV[1]) == 0 then
+    return false
+end
+return tonumber(redis.call('HGET', KEYS[2], ARGV[1]) or '0')
+";
+
+/// Returns `nil` if element `ARGV[1]` doesn't exist, `1` if its version is equal to `ARGV[2]`
+/// or element payload otherwise.
+const GET_IF_MODIFIED_SCRIPT: &str = r"
+local element = redis.call('HGET', KEYS[1], ARGV[1])
+if not element then
+    return false
+end
+if tonumber(redis.call('HGET', KEYS[2], ARGV[1]) or '0') == tonumber(ARGV[2]) then
+    return 1
+end
+return element
+";
+
+/// Hook called with field name and raw payload of cache element, which can't be deserialized.
+pub type CorruptionHook = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Handling of cache elements, which can't be deserialized. See
+/// [`Cache::with_read_repair()`](Cache::with_read_repair).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadRepair {
+    /// Read fails with [`InvalidData`](IpcErrorKind::InvalidData) error.
+    #[default]
+    Disabled,
+    /// Element is deleted and treated as missing.
+    Delete,
+    /// Element is moved to `<name>:quarantine` hash and treated as missing.
+    Quarantine,
+}
+
+/// Result of conditional read, see [`Cache::get_if_modified()`](Cache::get_if_modified).
+pub enum ConditionalGet<ElementContent> {
+    /// Element version is equal to the known one, content wasn't transferred.
+    NotModified,
+    /// Element has changed since known version.
+    Modified(CacheElement<ElementContent>),
+    /// Element doesn't exist.
+    NotFound,
 }
 
 /// Shared cache based on redis hash.
-#[derive(Clone)]
-pub struct Cache<ElementContent: Serialize + DeserializeOwned> {
+///
+/// For read only access use [`ReadOnlyCache`], for write only access use [`WriteCache`]. Both
+/// may be obtained with [`Cache::split()`](Cache::split).
+pub struct Cache<ElementContent> {
     /// Configured [`Pool`](r2d2::Pool) with [`Client`](redis::Client)
     pool: RedisPool,
     /// Cache name
@@ -45,9 +132,40 @@ pub struct Cache<ElementContent: Serialize + DeserializeOwned> {
     phantom: PhantomData<ElementContent>,
     /// timeout for reading operation in milliseconds
     read_timeout: Timeout,
+    /// Number of redis hashes, which fields are split across. `1` means no sharding.
+    shards: u32,
+    /// Handling of elements, which can't be deserialized
+    read_repair: ReadRepair,
+    /// Hook called with raw payload of elements, which can't be deserialized
+    corruption_hook: Option<CorruptionHook>,
+    /// Max random change of ttl in percents, `0` disables jitter
+    ttl_jitter: u8,
+    /// Token, which interrupts blocking reads
+    cancellation: Option<CancellationToken>,
+    /// Handling of unknown content fields
+    decode_mode: DecodeMode,
+}
+
+// Implemented manually, because derive would require `ElementContent: Clone`.
+impl<ElementContent> Clone for Cache<ElementContent> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            name: Arc::clone(&self.name),
+            ttl: self.ttl,
+            phantom: PhantomData,
+            read_timeout: self.read_timeout,
+            shards: self.shards,
+            read_repair: self.read_repair,
+            corruption_hook: self.corruption_hook.clone(),
+            ttl_jitter: self.ttl_jitter,
+            cancellation: self.cancellation.clone(),
+            decode_mode: self.decode_mode,
+        }
+    }
 }
 
-impl<ElementContent: Serialize + DeserializeOwned> Cache<ElementContent> {
+impl<ElementContent> Cache<ElementContent> {
     /// Creates new cache, using existing pool.
     ///
     /// # Arguments
@@ -71,31 +189,252 @@ impl<ElementContent: Serialize + DeserializeOwned> Cache<ElementContent> {
             ttl,
             read_timeout,
             phantom: PhantomData,
+            shards: 1,
+            read_repair: ReadRepair::Disabled,
+            corruption_hook: None,
+            ttl_jitter: 0,
+            cancellation: None,
+            decode_mode: DecodeMode::default(),
+        }
+    }
+
+    /// Creates cache for given [`Channel`]. Channel name is used as cache name.
+    pub fn for_channel<C: Channel<Message = ElementContent>>(
+        pool: RedisPool,
+        ttl: OptionalTtl,
+        read_timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(pool, C::NAME, ttl, read_timeout)
+    }
+
+    /// Splits cache fields across `shards` redis hashes (by field hash), which avoids single-key
+    /// hot spots and very large hashes. Shards are stored under `<name>:<shard>` keys. `0` and `1`
+    /// disable sharding.
+    ///
+    /// All clients of the same cache must use the same number of shards.
+    pub fn with_shards(mut self, shards: u32) -> Self {
+        self.shards = shards.max(1);
+        self
+    }
+
+    /// Applies random jitter of up to ± `percent` % (limited to 100) to ttl of every written
+    /// element, so elements written at the same time don't expire simultaneously.
+    pub fn with_ttl_jitter(mut self, percent: u8) -> Self {
+        self.ttl_jitter = percent;
+        self
+    }
+
+    /// Sets token, which interrupts blocking reads. When it is cancelled,
+    /// [`Cache::b_get()`](Cache::b_get) returns error with [`Cancelled`](IpcErrorKind::Cancelled)
+    /// kind.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets handling of elements, which can't be deserialized. By default reads of such element
+    /// fail until it is overwritten. With [`ReadRepair::Delete`] or [`ReadRepair::Quarantine`]
+    /// bad element is removed and treated as missing.
+    pub fn with_read_repair(mut self, read_repair: ReadRepair) -> Self {
+        self.read_repair = read_repair;
+        self
+    }
+
+    /// Sets handling of element content fields unknown to `ElementContent`. They are ignored by
+    /// default. Elements rejected in strict mode aren't treated as corrupted, so they aren't
+    /// repaired.
+    pub fn with_decode_mode(mut self, decode_mode: DecodeMode) -> Self {
+        self.decode_mode = decode_mode;
+        self
+    }
+
+    /// Sets hook called with field name and raw payload of elements, which can't be
+    /// deserialized, e.g. for logging.
+    pub fn with_corruption_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.corruption_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Returns current version of cache element or [`None`] if it doesn't exist. Only version is
+    /// transferred, so it is cheap even for large elements.
+    pub fn get_version(&self, field: &str) -> Result<Option<u64>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let version = redis::Script::new(GET_VERSION_SCRIPT)
+            .key(self.shard_key(field))
+            .key(self.versions_key(field))
+            .arg(field)
+            .invoke::<Option<u64>>(&mut *conn)?;
+
+        Ok(version)
+    }
+
+    /// Checks if cache element with given name exists. Returns error on failure.
+    pub fn exists(&self, field: &str) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let result = conn.hexists::<&str, &str, u8>(&self.shard_key(field), field)?;
+
+        Ok(result != 0)
+    }
+
+    /// Deletes cache field by given key. Returns error on failure.
+    ///
+    /// Element version is deleted with it, so element set again starts from version `1`.
+    pub fn delete(&self, field: &str) -> Result<(), IpcError> {
+        let mut conn = self.pool.get()?;
+
+        redis::pipe()
+            .atomic()
+            .hdel(self.shard_key(field), field)
+            .hdel(self.versions_key(field), field)
+            .exec(&mut *conn)?;
+
+        Ok(())
+    }
+
+    /// Returns read only handle of this cache, which may be handed to consumer components.
+    pub fn read_only(&self) -> ReadOnlyCache<ElementContent> {
+        ReadOnlyCache::from(self.clone())
+    }
+
+    /// Splits cache into read only and write only handles, so reading and writing
+    /// responsibilities may be owned by different parts of a codebase.
+    pub fn split(self) -> (ReadOnlyCache<ElementContent>, WriteCache<ElementContent>) {
+        (ReadOnlyCache::from(self.clone()), WriteCache::from(self))
+    }
+
+    /// Name of redis hash, which stores given field.
+    fn shard_key(&self, field: &str) -> String {
+        if self.shards <= 1 {
+            return self.name.to_string();
+        }
+
+        format!("{}:{}", self.name, shard_index(field, self.shards))
+    }
+
+    /// Names of all redis hashes used by this cache.
+    fn shard_keys(&self) -> Vec<String> {
+        if self.shards <= 1 {
+            return vec![self.name.to_string()];
         }
+
+        (0..self.shards)
+            .map(|shard| format!("{}:{}", self.name, shard))
+            .collect()
     }
 
+    /// Name of redis hash, which stores version of given field.
+    fn versions_key(&self, field: &str) -> String {
+        format!("{}{}", self.shard_key(field), VERSIONS_SUFFIX)
+    }
+
+    /// Name of redis hash, which stores quarantined elements.
+    fn quarantine_key(&self) -> String {
+        format!("{}{}", self.name, QUARANTINE_SUFFIX)
+    }
+}
+
+impl<ElementContent: DeserializeOwned> Cache<ElementContent> {
     /// Returns a cache element or error if not exists
     pub fn get(&self, field: &str) -> Result<Option<CacheElement<ElementContent>>, IpcError> {
         let mut conn = self.pool.get()?;
 
-        let element = conn.hget::<&str, &str, Option<String>>(&self.name, field)?;
+        let element = conn.hget::<&str, &str, Option<String>>(&self.shard_key(field), field)?;
         
         Ok(
             if let Some(element) = element {
-                let parsed = serde_json::from_str::<CacheElement<ElementContent>>(&element)?;
-                Some(parsed)
+                self.parse(&mut conn, field, &element)?
             } else {
                 None
             }
         )
     }
 
+    /// Returns cache element only if its version differs from `known_version`. Otherwise
+    /// [`ConditionalGet::NotModified`] is returned and element content isn't transferred nor
+    /// deserialized.
+    pub fn get_if_modified(
+        &self,
+        field: &str,
+        known_version: u64,
+    ) -> Result<ConditionalGet<ElementContent>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let res = redis::Script::new(GET_IF_MODIFIED_SCRIPT)
+            .key(self.shard_key(field))
+            .key(self.versions_key(field))
+            .arg(field)
+            .arg(known_version)
+            .invoke::<redis::Value>(&mut *conn)?;
+
+        let element = match res {
+            redis::Value::Nil => return Ok(ConditionalGet::NotFound),
+            redis::Value::Int(_) => return Ok(ConditionalGet::NotModified),
+            element => redis::from_redis_value::<String>(&element)?,
+        };
+
+        Ok(match self.parse(&mut conn, field, &element)? {
+            Some(element) => ConditionalGet::Modified(element),
+            None => ConditionalGet::NotFound,
+        })
+    }
+
+    /// Returns all elements in cache, mapped by field name.
+    pub fn get_all(&self) -> Result<HashMap<String, CacheElement<ElementContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let mut elements = HashMap::new();
+
+        for key in self.shard_keys() {
+            let shard = conn.hgetall::<&str, HashMap<String, String>>(&key)?;
+
+            for (field, element) in shard {
+                if let Some(parsed) = self.parse(&mut conn, &field, &element)? {
+                    elements.insert(field, parsed);
+                }
+            }
+        }
+
+        Ok(elements)
+    }
+
+    /// Returns elements, which field names match given glob-style `pattern` (see redis `HSCAN`).
+    pub fn scan(
+        &self,
+        pattern: &str,
+    ) -> Result<Vec<(String, CacheElement<ElementContent>)>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let mut elements = Vec::new();
+
+        for key in self.shard_keys() {
+            let shard: Vec<(String, String)> =
+                conn.hscan_match::<&str, &str, (String, String)>(&key, pattern)?.collect();
+
+            for (field, element) in shard {
+                if let Some(parsed) = self.parse(&mut conn, &field, &element)? {
+                    elements.push((field, parsed));
+                }
+            }
+        }
+
+        Ok(elements)
+    }
+
     /// Returns (blocking) a cache element with given name, or error if timeouts.
     pub fn b_get(&self, field: &str) -> Result<CacheElement<ElementContent>, IpcError> {
         let start_time = time::Instant::now();
         let sleep_duration = time::Duration::from_millis(50);
 
         loop {
+            if let Some(token) = &self.cancellation {
+                token.check()?;
+            }
+
             let elem = self.get(field);
 
             if let Ok(Some(elem)) = elem {
@@ -110,50 +449,404 @@ impl<ElementContent: Serialize + DeserializeOwned> Cache<ElementContent> {
         }
     }
 
-    /// Sets given cache field to the element or returns error on failure.
+    /// Deserializes raw element. Elements, which can't be deserialized, are handled according to
+    /// [`ReadRepair`] policy and [`None`] is returned when they were repaired.
+    fn parse(
+        &self,
+        conn: &mut RedisConnection,
+        field: &str,
+        element: &str,
+    ) -> Result<Option<CacheElement<ElementContent>>, IpcError> {
+        let error = match serde_json::from_str::<CacheElement<ElementContent>>(element) {
+            Ok(parsed) if self.decode_mode == DecodeMode::Lenient => return Ok(Some(parsed)),
+            // element with unknown fields isn't corrupted, so it's not repaired
+            Ok(_) => return decode_cache_element_with(element, self.decode_mode).map(Some),
+            Err(error) => error,
+        };
+
+        if let Some(hook) = &self.corruption_hook {
+            hook(field, element);
+        }
+
+        let quarantine = match self.read_repair {
+            ReadRepair::Disabled => return Err(error.into()),
+            ReadRepair::Delete => false,
+            ReadRepair::Quarantine => true,
+        };
+
+        redis::Script::new(REPAIR_SCRIPT)
+            .key(self.shard_key(field))
+            .key(self.quarantine_key())
+            .arg(field)
+            .arg(element)
+            .arg(if quarantine { "1" } else { "0" })
+            .invoke::<()>(&mut *conn)?;
+
+        Ok(None)
+    }
+}
+
+impl<ElementContent: Serialize> Cache<ElementContent> {
+    /// Sets given cache field to the element or returns error on failure. Every write bumps
+    /// element version.
     pub fn set(&self, field: &str, value: &ElementContent) -> Result<(), IpcError> {
+        self.set_with_ttl(field, value, self.ttl)
+    }
+
+    /// Same as [`Cache::set()`](Cache::set), but uses given `ttl` instead of the one shared by
+    /// cache elements.
+    pub fn set_with_ttl(
+        &self,
+        field: &str,
+        value: &ElementContent,
+        ttl: OptionalTtl,
+    ) -> Result<(), IpcError> {
         let mut conn = self.pool.get()?;
 
-        let element = CacheElement::new(timestamp_u128_now()?, value);
+        let content = serde_json::to_string(value)?;
+
+        redis::Script::new(SET_SCRIPT)
+            .key(self.shard_key(field))
+            .key(self.versions_key(field))
+            .arg(field)
+            .arg(timestamp_u128_now()?.to_string())
+            .arg(&content)
+            .invoke::<u64>(&mut *conn)?;
+
+        self.expire(&mut conn, field, ttl)
+    }
+
+    /// Sets element only if its current version is equal to `expected_version` (`0` for
+    /// element, which was never set), so concurrent writers may implement merge/retry flows.
+    /// Returns new element version.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) with [`Conflict`](IpcErrorKind::Conflict) kind when
+    /// version has changed, or other kind on connection or encoding failure.
+    pub fn set_if_version(
+        &self,
+        field: &str,
+        expected_version: u64,
+        value: &ElementContent,
+    ) -> Result<u64, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let version = expected_version + 1;
+        let element = CacheElement::with_version(timestamp_u128_now()?, version, value);
 
         let json = serde_json::to_string(&element)?;
 
-        let _ = conn.hset::<&str, &str, &str, ()>(&self.name, field, &json)?;
+        let current = redis::Script::new(SET_IF_VERSION_SCRIPT)
+            .key(self.shard_key(field))
+            .key(self.versions_key(field))
+            .arg(field)
+            .arg(expected_version)
+            .arg(&json)
+            .invoke::<i64>(&mut *conn)?;
+
+        if current >= 0 {
+            return Err(IpcError::new(
+                IpcErrorKind::Conflict,
+                format!("Expected version {expected_version}, but current version is {current}."),
+            ));
+        }
 
-        // optionally sets expiration
-        if let Some(ttl) = self.ttl {
-            // ttl set for max i64 value, if `Duration` was too big
-            let ttl = i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX);
+        self.expire(&mut conn, field, self.ttl)?;
 
-            let _ =
-                conn.hexpire::<&str, &str, Vec<i8>>(&self.name, ttl, ExpireOption::NONE, field)?;
+        Ok(version)
+    }
+
+    /// Sets expiration of element and its version, if `ttl` is given.
+    fn expire(
+        &self,
+        conn: &mut RedisConnection,
+        field: &str,
+        ttl: OptionalTtl,
+    ) -> Result<(), IpcError> {
+        if let Some(ttl) = ttl {
+            expire_pipe(&self.shard_key(field), field, ttl, self.ttl_jitter).exec(&mut **conn)?;
         }
 
         Ok(())
     }
+}
 
-    /// Checks if cache element with given name exists. Returns error on failure.
+/// Builds pipeline, which sets expiration of element stored in hash `key` and of its version.
+/// It's shared by sync and async caches.
+fn expire_pipe(key: &str, field: &str, ttl: time::Duration, jitter: u8) -> redis::Pipeline {
+    let ttl = expire_secs(ttl, jitter);
+
+    let mut pipe = redis::pipe();
+    pipe.hexpire(key, ttl, ExpireOption::NONE, field)
+        .ignore()
+        .hexpire(format!("{key}{VERSIONS_SUFFIX}"), ttl, ExpireOption::NONE, field)
+        .ignore();
+
+    pipe
+}
+
+/// Returns `ttl` changed by jitter of up to ± `jitter` % in whole seconds, which are used by
+/// `HEXPIRE`. It's at least 1 s, because redis deletes element immediately, when its ttl is
+/// rounded down to 0 s.
+fn expire_secs(ttl: time::Duration, jitter: u8) -> i64 {
+    let ttl = jittered_ttl(ttl, jitter).as_secs().max(1);
+
+    // ttl set for max i64 value, if `Duration` was too big
+    i64::try_from(ttl).unwrap_or(i64::MAX)
+}
+
+/// Read only handle of [`Cache`]. It doesn't allow to set nor delete elements, so it may be handed
+/// to consumer components when only the owning service should write given cache.
+///
+/// For writing use [`WriteCache`] or [`Cache`]
+pub struct ReadOnlyCache<ElementContent> {
+    /// Wrapped cache
+    cache: Cache<ElementContent>,
+}
+
+impl<ElementContent> Clone for ReadOnlyCache<ElementContent> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<ElementContent> ReadOnlyCache<ElementContent> {
+    /// Creates new read only cache, using existing pool.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`RedisPool`](RedisPool)
+    /// * name - cache name, will be used as redis hash name
+    /// * read_timeout - timeout for reading operations (in ms)
+    pub fn new(pool: RedisPool, name: &str, read_timeout: OptionalTimeout) -> Self {
+        Self {
+            cache: Cache::new(pool, name, None, read_timeout),
+        }
+    }
+
+    /// Creates read only cache for given [`Channel`]. Channel name is used as cache name.
+    pub fn for_channel<C: Channel<Message = ElementContent>>(
+        pool: RedisPool,
+        read_timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(pool, C::NAME, read_timeout)
+    }
+
+    /// See [`Cache::with_shards()`](Cache::with_shards).
+    pub fn with_shards(self, shards: u32) -> Self {
+        Self {
+            cache: self.cache.with_shards(shards),
+        }
+    }
+
+    /// See [`Cache::with_cancellation()`](Cache::with_cancellation).
+    pub fn with_cancellation(self, token: CancellationToken) -> Self {
+        Self {
+            cache: self.cache.with_cancellation(token),
+        }
+    }
+
+    /// See [`Cache::with_read_repair()`](Cache::with_read_repair).
+    pub fn with_read_repair(self, read_repair: ReadRepair) -> Self {
+        Self {
+            cache: self.cache.with_read_repair(read_repair),
+        }
+    }
+
+    /// See [`Cache::with_decode_mode()`](Cache::with_decode_mode).
+    pub fn with_decode_mode(self, decode_mode: DecodeMode) -> Self {
+        Self {
+            cache: self.cache.with_decode_mode(decode_mode),
+        }
+    }
+
+    /// See [`Cache::with_corruption_hook()`](Cache::with_corruption_hook).
+    pub fn with_corruption_hook<F>(self, hook: F) -> Self
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        Self {
+            cache: self.cache.with_corruption_hook(hook),
+        }
+    }
+
+    /// See [`Cache::get_version()`](Cache::get_version).
+    pub fn get_version(&self, field: &str) -> Result<Option<u64>, IpcError> {
+        self.cache.get_version(field)
+    }
+
+    /// See [`Cache::exists()`](Cache::exists).
     pub fn exists(&self, field: &str) -> Result<bool, IpcError> {
-        let mut conn = self.pool.get()?;
+        self.cache.exists(field)
+    }
+}
 
-        let result = conn.hexists::<&str, &str, u8>(&self.name, field)?;
+impl<ElementContent: DeserializeOwned> ReadOnlyCache<ElementContent> {
+    /// See [`Cache::get()`](Cache::get).
+    pub fn get(&self, field: &str) -> Result<Option<CacheElement<ElementContent>>, IpcError> {
+        self.cache.get(field)
+    }
 
-        Ok(result != 0)
+    /// See [`Cache::get_if_modified()`](Cache::get_if_modified).
+    pub fn get_if_modified(
+        &self,
+        field: &str,
+        known_version: u64,
+    ) -> Result<ConditionalGet<ElementContent>, IpcError> {
+        self.cache.get_if_modified(field, known_version)
     }
 
-    /// Deletes cache field by given key. Returns error on failure.
+    /// See [`Cache::get_all()`](Cache::get_all).
+    pub fn get_all(&self) -> Result<HashMap<String, CacheElement<ElementContent>>, IpcError> {
+        self.cache.get_all()
+    }
+
+    /// See [`Cache::scan()`](Cache::scan).
+    pub fn scan(
+        &self,
+        pattern: &str,
+    ) -> Result<Vec<(String, CacheElement<ElementContent>)>, IpcError> {
+        self.cache.scan(pattern)
+    }
+
+    /// See [`Cache::b_get()`](Cache::b_get).
+    pub fn b_get(&self, field: &str) -> Result<CacheElement<ElementContent>, IpcError> {
+        self.cache.b_get(field)
+    }
+}
+
+impl<ElementContent> From<Cache<ElementContent>> for ReadOnlyCache<ElementContent> {
+    fn from(cache: Cache<ElementContent>) -> Self {
+        Self { cache }
+    }
+}
+
+/// Write only handle of [`Cache`]. It doesn't allow to read elements.
+///
+/// For reading use [`ReadOnlyCache`] or [`Cache`]
+pub struct WriteCache<ElementContent> {
+    /// Wrapped cache
+    cache: Cache<ElementContent>,
+}
+
+impl<ElementContent> Clone for WriteCache<ElementContent> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<ElementContent> WriteCache<ElementContent> {
+    /// Creates new write only cache, using existing pool.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`RedisPool`](RedisPool)
+    /// * name - cache name, will be used as redis hash name
+    /// * ttl - time to live for every new cache element (in ms)
+    pub fn new(pool: RedisPool, name: &str, ttl: OptionalTtl) -> Self {
+        Self {
+            cache: Cache::new(pool, name, ttl, None),
+        }
+    }
+
+    /// Creates write only cache for given [`Channel`]. Channel name is used as cache name.
+    pub fn for_channel<C: Channel<Message = ElementContent>>(
+        pool: RedisPool,
+        ttl: OptionalTtl,
+    ) -> Self {
+        Self::new(pool, C::NAME, ttl)
+    }
+
+    /// See [`Cache::with_shards()`](Cache::with_shards).
+    pub fn with_shards(self, shards: u32) -> Self {
+        Self {
+            cache: self.cache.with_shards(shards),
+        }
+    }
+
+    /// See [`Cache::with_ttl_jitter()`](Cache::with_ttl_jitter).
+    pub fn with_ttl_jitter(self, percent: u8) -> Self {
+        Self {
+            cache: self.cache.with_ttl_jitter(percent),
+        }
+    }
+
+    /// See [`Cache::delete()`](Cache::delete).
     pub fn delete(&self, field: &str) -> Result<(), IpcError> {
-        let mut conn = self.pool.get()?;
+        self.cache.delete(field)
+    }
+}
 
-        conn.hdel::<&str, &str, ()>(&self.name, field)?;
+impl<ElementContent: Serialize> WriteCache<ElementContent> {
+    /// See [`Cache::set()`](Cache::set).
+    pub fn set(&self, field: &str, value: &ElementContent) -> Result<(), IpcError> {
+        self.cache.set(field, value)
+    }
 
-        Ok(())
+    /// See [`Cache::set_with_ttl()`](Cache::set_with_ttl).
+    pub fn set_with_ttl(
+        &self,
+        field: &str,
+        value: &ElementContent,
+        ttl: OptionalTtl,
+    ) -> Result<(), IpcError> {
+        self.cache.set_with_ttl(field, value, ttl)
+    }
+
+    /// See [`Cache::set_if_version()`](Cache::set_if_version).
+    pub fn set_if_version(
+        &self,
+        field: &str,
+        expected_version: u64,
+        value: &ElementContent,
+    ) -> Result<u64, IpcError> {
+        self.cache.set_if_version(field, expected_version, value)
     }
 }
 
-/// Returns current 128 bit unix timestamp
-fn timestamp_u128_now() -> Result<u128, time::SystemTimeError> {
-    Ok(time::SystemTime::now()
-        .duration_since(time::UNIX_EPOCH)?
-        .as_millis())
+impl<ElementContent> From<Cache<ElementContent>> for WriteCache<ElementContent> {
+    fn from(cache: Cache<ElementContent>) -> Self {
+        Self { cache }
+    }
+}
+
+
+/// Returns shard of given field. Uses FNV-1a hash, because it has to be stable between processes
+/// and compiler versions.
+fn shard_index(field: &str, shards: u32) -> u32 {
+    (stable_hash(field) % u64::from(shards)) as u32
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shard_index_is_stable() {
+        // FNV-1a of "a" is 0xaf63dc4c8601ec8c
+        assert_eq!(shard_index("a", 1000), (0xaf63dc4c8601ec8c_u64 % 1000) as u32);
+    }
+
+    #[test]
+    fn shard_index_in_range() {
+        for field in ["", "a", "user:1", "user:2", "some longer field name"] {
+            assert!(shard_index(field, 7) < 7);
+        }
+    }
+
+    #[test]
+    fn expire_secs_is_at_least_one_second() {
+        for _ in 0..100 {
+            assert!(expire_secs(time::Duration::from_secs(2), 100) >= 1);
+        }
+
+        assert_eq!(expire_secs(time::Duration::from_millis(200), 0), 1);
+        assert_eq!(expire_secs(time::Duration::MAX, 0), i64::MAX);
+    }
 }
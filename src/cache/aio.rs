@@ -0,0 +1,192 @@
+use super::{expire_pipe, shard_index, CacheElement, SET_SCRIPT, VERSIONS_SUFFIX};
+use crate::channel::Channel;
+use crate::error::{IpcError, IpcErrorKind};
+use crate::helpers::timestamp_u128_now;
+use crate::rt;
+use crate::{AsyncRedisConnection, OptionalTimeout, OptionalTtl, Timeout};
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Async variant of [`Cache`](super::Cache). It uses the same redis layout, so both may be used
+/// for the same cache.
+pub struct AsyncCache<ElementContent> {
+    /// redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    conn: AsyncRedisConnection,
+    /// Cache name
+    name: Arc<String>,
+    /// Time to live for elements in cache. It is shared for every element.
+    ttl: OptionalTtl,
+    /// phantom to specify type of elements in cache
+    phantom: PhantomData<ElementContent>,
+    /// timeout for reading operation
+    read_timeout: Timeout,
+    /// Number of redis hashes, which fields are split across. `1` means no sharding.
+    shards: u32,
+    /// Max random change of ttl in percents, `0` disables jitter
+    ttl_jitter: u8,
+}
+
+// Implemented manually, because derive would require `ElementContent: Clone`.
+impl<ElementContent> Clone for AsyncCache<ElementContent> {
+    fn clone(&self) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            name: Arc::clone(&self.name),
+            ttl: self.ttl,
+            phantom: PhantomData,
+            read_timeout: self.read_timeout,
+            shards: self.shards,
+            ttl_jitter: self.ttl_jitter,
+        }
+    }
+}
+
+impl<ElementContent> AsyncCache<ElementContent> {
+    /// Creates new cache, using existing connection.
+    ///
+    /// # Arguments
+    ///
+    /// * conn - redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection), it may be
+    ///   shared with other structures
+    /// * name - cache name, will be used as redis hash name
+    /// * ttl - time to live for every new cache element
+    /// * read_timeout - timeout for reading operations
+    pub fn new(
+        conn: AsyncRedisConnection,
+        name: &str,
+        ttl: OptionalTtl,
+        read_timeout: OptionalTimeout,
+    ) -> Self {
+        // maps None as 0, which means infinite timeout
+        let read_timeout = read_timeout.unwrap_or(Duration::ZERO);
+
+        Self {
+            conn,
+            name: Arc::new(name.to_string()),
+            ttl,
+            read_timeout,
+            phantom: PhantomData,
+            shards: 1,
+            ttl_jitter: 0,
+        }
+    }
+
+    /// Creates cache for given [`Channel`]. Channel name is used as cache name.
+    pub fn for_channel<C: Channel<Message = ElementContent>>(
+        conn: AsyncRedisConnection,
+        ttl: OptionalTtl,
+        read_timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(conn, C::NAME, ttl, read_timeout)
+    }
+
+    /// See [`Cache::with_shards()`](super::Cache::with_shards).
+    pub fn with_shards(mut self, shards: u32) -> Self {
+        self.shards = shards.max(1);
+        self
+    }
+
+    /// See [`Cache::with_ttl_jitter()`](super::Cache::with_ttl_jitter).
+    pub fn with_ttl_jitter(mut self, percent: u8) -> Self {
+        self.ttl_jitter = percent;
+        self
+    }
+
+    /// See [`Cache::exists()`](super::Cache::exists).
+    pub async fn exists(&mut self, field: &str) -> Result<bool, IpcError> {
+        let key = self.shard_key(field);
+
+        let result = self.conn.hexists::<&str, &str, u8>(&key, field).await?;
+
+        Ok(result != 0)
+    }
+
+    /// See [`Cache::delete()`](super::Cache::delete).
+    pub async fn delete(&mut self, field: &str) -> Result<(), IpcError> {
+        let key = self.shard_key(field);
+        let versions_key = format!("{key}{VERSIONS_SUFFIX}");
+
+        redis::pipe()
+            .atomic()
+            .hdel(&key, field)
+            .hdel(&versions_key, field)
+            .exec_async(&mut self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Name of redis hash, which stores given field.
+    fn shard_key(&self, field: &str) -> String {
+        if self.shards <= 1 {
+            return self.name.to_string();
+        }
+
+        format!("{}:{}", self.name, shard_index(field, self.shards))
+    }
+}
+
+impl<ElementContent: DeserializeOwned> AsyncCache<ElementContent> {
+    /// See [`Cache::get()`](super::Cache::get).
+    pub async fn get(
+        &mut self,
+        field: &str,
+    ) -> Result<Option<CacheElement<ElementContent>>, IpcError> {
+        let key = self.shard_key(field);
+
+        let element = self.conn.hget::<&str, &str, Option<String>>(&key, field).await?;
+
+        Ok(match element {
+            Some(element) => Some(serde_json::from_str(&element)?),
+            None => None,
+        })
+    }
+
+    /// Awaits a cache element with given name. Returns error when timeout exceeds, same as
+    /// [`Cache::b_get()`](super::Cache::b_get).
+    pub async fn b_get(&mut self, field: &str) -> Result<CacheElement<ElementContent>, IpcError> {
+        let start_time = Instant::now();
+        let sleep_duration = Duration::from_millis(50);
+
+        loop {
+            if let Ok(Some(elem)) = self.get(field).await {
+                return Ok(elem);
+            }
+
+            if !self.read_timeout.is_zero() && start_time.elapsed() >= self.read_timeout {
+                return Err(IpcError::new(IpcErrorKind::Timeout, "Request timed out."));
+            }
+
+            rt::sleep(sleep_duration).await;
+        }
+    }
+}
+
+impl<ElementContent: Serialize> AsyncCache<ElementContent> {
+    /// See [`Cache::set()`](super::Cache::set).
+    pub async fn set(&mut self, field: &str, value: &ElementContent) -> Result<(), IpcError> {
+        let key = self.shard_key(field);
+        let versions_key = format!("{key}{VERSIONS_SUFFIX}");
+
+        let content = serde_json::to_string(value)?;
+
+        redis::Script::new(SET_SCRIPT)
+            .key(&key)
+            .key(&versions_key)
+            .arg(field)
+            .arg(timestamp_u128_now()?.to_string())
+            .arg(&content)
+            .invoke_async::<u64>(&mut self.conn)
+            .await?;
+
+        if let Some(ttl) = self.ttl {
+            expire_pipe(&key, field, ttl, self.ttl_jitter).exec_async(&mut self.conn).await?;
+        }
+
+        Ok(())
+    }
+}
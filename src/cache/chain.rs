@@ -0,0 +1,256 @@
+use super::{Cache, MemoryCache};
+use crate::error::IpcError;
+use crate::OptionalTtl;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Single level of [`CacheChain`], e.g. [`MemoryCache`] or redis based [`Cache`].
+pub trait CacheLevel<ElementContent>: Send + Sync {
+    /// Returns element content or [`None`] if it doesn't exist on this level.
+    fn get(&self, field: &str) -> Result<Option<ElementContent>, IpcError>;
+
+    /// Sets element on this level with given `ttl`.
+    fn set(&self, field: &str, value: &ElementContent, ttl: OptionalTtl) -> Result<(), IpcError>;
+
+    /// Deletes element from this level.
+    fn delete(&self, field: &str) -> Result<(), IpcError>;
+}
+
+impl<ElementContent> CacheLevel<ElementContent> for Cache<ElementContent>
+where
+    ElementContent: Serialize + DeserializeOwned + Send + Sync,
+{
+    fn get(&self, field: &str) -> Result<Option<ElementContent>, IpcError> {
+        Ok(Cache::get(self, field)?.map(|element| element.into_content()))
+    }
+
+    fn set(&self, field: &str, value: &ElementContent, ttl: OptionalTtl) -> Result<(), IpcError> {
+        self.set_with_ttl(field, value, ttl)
+    }
+
+    fn delete(&self, field: &str) -> Result<(), IpcError> {
+        Cache::delete(self, field)
+    }
+}
+
+impl<ElementContent> CacheLevel<ElementContent> for MemoryCache<ElementContent>
+where
+    ElementContent: Clone + Send + Sync,
+{
+    fn get(&self, field: &str) -> Result<Option<ElementContent>, IpcError> {
+        MemoryCache::get(self, field)
+    }
+
+    fn set(&self, field: &str, value: &ElementContent, ttl: OptionalTtl) -> Result<(), IpcError> {
+        self.set_with_ttl(field, value, ttl)
+    }
+
+    fn delete(&self, field: &str) -> Result<(), IpcError> {
+        MemoryCache::delete(self, field)
+    }
+}
+
+/// Function, which loads element when it can't be found on any level.
+type Loader<ElementContent> =
+    Arc<dyn Fn(&str) -> Result<Option<ElementContent>, IpcError> + Send + Sync>;
+
+/// Level of chain with its own time to live.
+struct ChainLevel<ElementContent> {
+    cache: Arc<dyn CacheLevel<ElementContent>>,
+    ttl: OptionalTtl,
+}
+
+impl<ElementContent> Clone for ChainLevel<ElementContent> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: Arc::clone(&self.cache),
+            ttl: self.ttl,
+        }
+    }
+}
+
+/// Multi-level cache, which queries levels in order (e.g. L1 [`MemoryCache`], L2 redis
+/// [`Cache`]) and back-fills upper levels on hit. When element can't be found on any level,
+/// optional loader is used and its result is stored on every level.
+///
+/// # Examples
+/// ```ignored
+/// let chain = CacheChain::new()
+///     .with_level(MemoryCache::new(None), Some(Duration::from_secs(5)))
+///     .with_level(redis_cache, Some(Duration::from_secs(300)))
+///     .with_loader(|field| load_from_db(field));
+///
+/// let user = chain.get("user:1")?;
+/// ```
+pub struct CacheChain<ElementContent> {
+    /// Levels queried in order
+    levels: Vec<ChainLevel<ElementContent>>,
+    /// Optional loader used on miss
+    loader: Option<Loader<ElementContent>>,
+}
+
+impl<ElementContent> Clone for CacheChain<ElementContent> {
+    fn clone(&self) -> Self {
+        Self {
+            levels: self.levels.clone(),
+            loader: self.loader.clone(),
+        }
+    }
+}
+
+impl<ElementContent> Default for CacheChain<ElementContent> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<ElementContent> CacheChain<ElementContent> {
+    /// Creates chain without any level.
+    pub fn new() -> Self {
+        Self {
+            levels: Vec::new(),
+            loader: None,
+        }
+    }
+
+    /// Appends level to the chain. Elements stored on this level by the chain use given `ttl`.
+    pub fn with_level<Level>(mut self, cache: Level, ttl: OptionalTtl) -> Self
+    where
+        Level: CacheLevel<ElementContent> + 'static,
+    {
+        self.levels.push(ChainLevel {
+            cache: Arc::new(cache),
+            ttl,
+        });
+        self
+    }
+
+    /// Sets loader, which is called when element can't be found on any level. Loaded element is
+    /// stored on every level.
+    pub fn with_loader<F>(mut self, loader: F) -> Self
+    where
+        F: Fn(&str) -> Result<Option<ElementContent>, IpcError> + Send + Sync + 'static,
+    {
+        self.loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Returns element from the first level, which contains it, and back-fills upper levels.
+    /// Falls back to loader if element isn't cached.
+    pub fn get(&self, field: &str) -> Result<Option<ElementContent>, IpcError> {
+        for (index, level) in self.levels.iter().enumerate() {
+            if let Some(value) = level.cache.get(field)? {
+                self.fill(&self.levels[..index], field, &value)?;
+                return Ok(Some(value));
+            }
+        }
+
+        let Some(loader) = &self.loader else {
+            return Ok(None);
+        };
+
+        let value = loader(field)?;
+
+        if let Some(value) = &value {
+            self.fill(&self.levels, field, value)?;
+        }
+
+        Ok(value)
+    }
+
+    /// Sets element on every level.
+    pub fn set(&self, field: &str, value: &ElementContent) -> Result<(), IpcError> {
+        self.fill(&self.levels, field, value)
+    }
+
+    /// Checks if element is cached on any level. Loader isn't used.
+    pub fn exists(&self, field: &str) -> Result<bool, IpcError> {
+        for level in &self.levels {
+            if level.cache.get(field)?.is_some() {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Deletes element from every level.
+    pub fn delete(&self, field: &str) -> Result<(), IpcError> {
+        for level in &self.levels {
+            level.cache.delete(field)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores element on given levels.
+    fn fill(
+        &self,
+        levels: &[ChainLevel<ElementContent>],
+        field: &str,
+        value: &ElementContent,
+    ) -> Result<(), IpcError> {
+        for level in levels {
+            level.cache.set(field, value, level.ttl)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn back_fills_upper_levels() {
+        let l1 = MemoryCache::new(None);
+        let l2 = MemoryCache::new(None);
+
+        l2.set("field", &1).unwrap();
+
+        let chain = CacheChain::new()
+            .with_level(l1.clone(), None)
+            .with_level(l2, None);
+
+        assert_eq!(chain.get("field").unwrap(), Some(1));
+        assert_eq!(l1.get("field").unwrap(), Some(1));
+    }
+
+    #[test]
+    fn uses_loader_on_miss() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_clone = Arc::clone(&calls);
+
+        let l1 = MemoryCache::new(None);
+
+        let chain = CacheChain::new()
+            .with_level(l1.clone(), None)
+            .with_loader(move |field| {
+                calls_clone.fetch_add(1, Ordering::SeqCst);
+                Ok(Some(field.len()))
+            });
+
+        assert_eq!(chain.get("four").unwrap(), Some(4));
+        assert_eq!(chain.get("four").unwrap(), Some(4));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(l1.get("four").unwrap(), Some(4));
+    }
+
+    #[test]
+    fn delete_removes_from_every_level() {
+        let l1 = MemoryCache::new(None);
+        let l2 = MemoryCache::new(None);
+
+        let chain = CacheChain::new()
+            .with_level(l1, None)
+            .with_level(l2, None);
+
+        chain.set("field", &1).unwrap();
+        chain.delete("field").unwrap();
+
+        assert!(!chain.exists("field").unwrap());
+    }
+}
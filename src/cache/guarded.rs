@@ -0,0 +1,189 @@
+use super::{Cache, CacheElement};
+use crate::error::{IpcError, IpcErrorKind};
+use crate::lease::RELEASE_SCRIPT;
+use redis::{Commands, ExistenceCheck, SetExpiry, SetOptions};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Suffix of the redis key, which stores lock of map entry.
+const LOCK_SUFFIX: &str = ":lock:";
+
+/// Time between lock acquisition attempts.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Shared map based on [`Cache`] storage, which entries may be locked for exclusive
+/// read-modify-write access across processes with
+/// [`GuardedMap::lock_entry()`](GuardedMap::lock_entry).
+///
+/// Lock is held for `lease` at most, so entry isn't locked forever when its holder dies. Writes
+/// done with [`EntryGuard`] after the lease expired and somebody else modified the entry fail
+/// with [`Conflict`](IpcErrorKind::Conflict) error.
+pub struct GuardedMap<ElementContent> {
+    /// Storage of map entries
+    cache: Cache<ElementContent>,
+    /// Max time entry lock is held
+    lease: Duration,
+}
+
+// Implemented manually, because derive would require `ElementContent: Clone`.
+impl<ElementContent> Clone for GuardedMap<ElementContent> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+            lease: self.lease,
+        }
+    }
+}
+
+impl<ElementContent> GuardedMap<ElementContent> {
+    /// Creates map stored in given cache.
+    ///
+    /// # Arguments
+    ///
+    /// * cache - storage of map entries, its read timeout is used as lock acquisition timeout
+    /// * lease - max time entry lock is held
+    pub fn new(cache: Cache<ElementContent>, lease: Duration) -> Self {
+        Self { cache, lease }
+    }
+
+    /// Returns cache, which stores map entries. It may be used for reads, which don't need lock.
+    pub fn get_cache(&self) -> &Cache<ElementContent> {
+        &self.cache
+    }
+
+    /// Locks entry with given key. Blocks thread until lock is acquired. Waits indefinitely or
+    /// returns error after cache read timeout if it was set.
+    ///
+    /// Lock is released when returned guard is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure, timeout or cancellation of the
+    /// cache.
+    pub fn lock_entry(&self, key: &str) -> Result<EntryGuard<'_, ElementContent>, IpcError> {
+        let start_time = Instant::now();
+
+        let lock_key = self.lock_key(key);
+        let token = Uuid::new_v4().to_string();
+        let lease = u64::try_from(self.lease.as_millis()).unwrap_or(u64::MAX);
+        let opts = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::PX(lease));
+
+        loop {
+            if let Some(token) = &self.cache.cancellation {
+                token.check()?;
+            }
+
+            let mut conn = self.cache.pool.get()?;
+            let acquired = conn.set_options::<&str, &str, bool>(&lock_key, &token, opts)?;
+            drop(conn);
+
+            if acquired {
+                break;
+            }
+
+            let timeout = self.cache.read_timeout;
+            if !timeout.is_zero() && start_time.elapsed() >= timeout {
+                return Err(IpcError::new(IpcErrorKind::Timeout, "Lock request timed out."));
+            }
+
+            thread::sleep(LOCK_RETRY_INTERVAL);
+        }
+
+        let mut guard = EntryGuard {
+            map: self,
+            key: key.to_string(),
+            token,
+            version: 0,
+        };
+
+        // lock is released by guard drop on failure
+        guard.version = self.cache.get_version(key)?.unwrap_or(0);
+
+        Ok(guard)
+    }
+
+    fn lock_key(&self, key: &str) -> String {
+        format!("{}{}{}", self.cache.name, LOCK_SUFFIX, key)
+    }
+}
+
+/// Exclusive access to entry of [`GuardedMap`]. Lock is released on drop.
+pub struct EntryGuard<'a, ElementContent> {
+    map: &'a GuardedMap<ElementContent>,
+    /// Key of locked entry
+    key: String,
+    /// Identifies lock holder, so lock taken over after lease can't be released. Empty when lock
+    /// was released.
+    token: String,
+    /// Entry version known by lock holder, `0` if entry was never set
+    version: u64,
+}
+
+impl<ElementContent> EntryGuard<'_, ElementContent> {
+    /// Returns key of locked entry.
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    /// Deletes entry. Returns error on failure.
+    pub fn delete(&self) -> Result<(), IpcError> {
+        self.map.cache.delete(&self.key)
+    }
+
+    /// Releases lock. Same as drop, but returns error on failure.
+    pub fn unlock(mut self) -> Result<(), IpcError> {
+        self.release()
+    }
+
+    fn release(&mut self) -> Result<(), IpcError> {
+        let token = mem::take(&mut self.token);
+
+        if token.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.map.cache.pool.get()?;
+
+        redis::Script::new(RELEASE_SCRIPT)
+            .key(self.map.lock_key(&self.key))
+            .arg(&token)
+            .invoke::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+}
+
+impl<ElementContent: DeserializeOwned> EntryGuard<'_, ElementContent> {
+    /// Returns locked entry or [`None`] if it doesn't exist.
+    pub fn get(&self) -> Result<Option<CacheElement<ElementContent>>, IpcError> {
+        self.map.cache.get(&self.key)
+    }
+}
+
+impl<ElementContent: Serialize> EntryGuard<'_, ElementContent> {
+    /// Sets locked entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) with [`Conflict`](IpcErrorKind::Conflict) kind when entry
+    /// was modified by somebody else since it was locked (e.g. lease expired), or other kind on
+    /// connection or encoding failure.
+    pub fn set(&mut self, value: &ElementContent) -> Result<(), IpcError> {
+        self.version = self.map.cache.set_if_version(&self.key, self.version, value)?;
+
+        Ok(())
+    }
+}
+
+impl<ElementContent> Drop for EntryGuard<'_, ElementContent> {
+    fn drop(&mut self) {
+        // lock expires after lease anyway
+        let _ = self.release();
+    }
+}
@@ -0,0 +1,133 @@
+use crate::error::IpcError;
+use crate::helpers::jittered_ttl;
+use crate::OptionalTtl;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Cached content with optional expiration instant.
+type MemoryElement<ElementContent> = (ElementContent, Option<Instant>);
+
+/// In-process cache, which may be used as the first level of [`CacheChain`](super::CacheChain).
+/// It is not shared between processes, clones share the same storage.
+#[derive(Clone)]
+pub struct MemoryCache<ElementContent: Clone> {
+    /// Elements with optional expiration instant
+    elements: Arc<Mutex<HashMap<String, MemoryElement<ElementContent>>>>,
+    /// Time to live for elements in cache. It is shared for every element.
+    ttl: OptionalTtl,
+    /// Max random change of ttl in percents, `0` disables jitter
+    ttl_jitter: u8,
+}
+
+impl<ElementContent: Clone> MemoryCache<ElementContent> {
+    /// Creates new empty cache.
+    ///
+    /// # Arguments
+    ///
+    /// * ttl - time to live for every new cache element or [`None`] if elements never expire
+    pub fn new(ttl: OptionalTtl) -> Self {
+        Self {
+            elements: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+            ttl_jitter: 0,
+        }
+    }
+
+    /// See [`Cache::with_ttl_jitter()`](super::Cache::with_ttl_jitter).
+    pub fn with_ttl_jitter(mut self, percent: u8) -> Self {
+        self.ttl_jitter = percent;
+        self
+    }
+
+    /// Returns a copy of cache element or [`None`] if it doesn't exist or has expired.
+    pub fn get(&self, field: &str) -> Result<Option<ElementContent>, IpcError> {
+        let mut elements = self.elements.lock()?;
+
+        let expired = match elements.get(field) {
+            Some((_, Some(expires_at))) => *expires_at <= Instant::now(),
+            Some((_, None)) => false,
+            None => return Ok(None),
+        };
+
+        if expired {
+            elements.remove(field);
+            return Ok(None);
+        }
+
+        Ok(elements.get(field).map(|(content, _)| content.clone()))
+    }
+
+    /// Sets given cache field to the element.
+    pub fn set(&self, field: &str, value: &ElementContent) -> Result<(), IpcError> {
+        self.set_with_ttl(field, value, self.ttl)
+    }
+
+    /// Same as [`MemoryCache::set()`](MemoryCache::set), but uses given `ttl` instead of the one
+    /// shared by cache elements.
+    pub fn set_with_ttl(
+        &self,
+        field: &str,
+        value: &ElementContent,
+        ttl: OptionalTtl,
+    ) -> Result<(), IpcError> {
+        // element never expires, if its expiration instant overflows
+        let expires_at =
+            ttl.and_then(|ttl| Instant::now().checked_add(jittered_ttl(ttl, self.ttl_jitter)));
+
+        self.elements
+            .lock()?
+            .insert(field.to_string(), (value.clone(), expires_at));
+
+        Ok(())
+    }
+
+    /// Checks if cache element with given name exists and hasn't expired.
+    pub fn exists(&self, field: &str) -> Result<bool, IpcError> {
+        Ok(self.get(field)?.is_some())
+    }
+
+    /// Deletes cache field by given key.
+    pub fn delete(&self, field: &str) -> Result<(), IpcError> {
+        self.elements.lock()?.remove(field);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn set_get_delete() {
+        let cache = MemoryCache::new(None);
+
+        cache.set("field", &String::from("value")).unwrap();
+        assert_eq!(cache.get("field").unwrap(), Some(String::from("value")));
+
+        cache.delete("field").unwrap();
+        assert!(!cache.exists("field").unwrap());
+    }
+
+    #[test]
+    fn element_expires() {
+        let cache = MemoryCache::new(Some(Duration::from_millis(10)));
+
+        cache.set("field", &1).unwrap();
+        thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(cache.get("field").unwrap(), None);
+    }
+
+    #[test]
+    fn huge_ttl_never_expires() {
+        let cache = MemoryCache::new(Some(Duration::MAX));
+
+        cache.set("field", &1).unwrap();
+
+        assert_eq!(cache.get("field").unwrap(), Some(1));
+    }
+}
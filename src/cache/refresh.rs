@@ -0,0 +1,45 @@
+use super::Cache;
+use crate::background::{self, BackgroundHandle};
+use crate::error::IpcError;
+use crate::lease::LeaderLease;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Suffix of the redis key, which stores refresh leader of cache field.
+const LEADER_SUFFIX: &str = ":refresh-leader:";
+
+/// Handle of refresh started with [`Cache::auto_refresh()`](Cache::auto_refresh). Refresh is
+/// stopped when handle is dropped and leadership is released, so other process may take over
+/// refreshing immediately.
+pub type RefreshHandle = BackgroundHandle;
+
+impl<ElementContent: Serialize + Send + 'static> Cache<ElementContent> {
+    /// Periodically re-computes element with `loader` and sets it, so hot elements are refreshed
+    /// before they expire. First refresh is done immediately, next ones every `interval`.
+    ///
+    /// Many processes may refresh the same field, but only one of them (the leader) does it.
+    /// Leadership is stored in redis key `<name>:refresh-leader:<field>` and expires after three
+    /// intervals, so other process takes over when the leader dies.
+    ///
+    /// Failed refreshes are skipped and retried after next interval.
+    pub fn auto_refresh<F>(&self, field: &str, interval: Duration, loader: F) -> RefreshHandle
+    where
+        F: Fn() -> Result<ElementContent, IpcError> + Send + 'static,
+    {
+        let cache = self.clone();
+        let field = field.to_string();
+        let lease = LeaderLease::new(
+            self.pool.clone(),
+            format!("{}{}{}", self.name, LEADER_SUFFIX, field),
+            interval.saturating_mul(3),
+        );
+
+        background::spawn_periodic(interval, move || {
+            if let Ok(true) = lease.acquire() {
+                if let Ok(value) = loader() {
+                    let _ = cache.set(&field, &value);
+                }
+            }
+        })
+    }
+}
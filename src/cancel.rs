@@ -0,0 +1,67 @@
+//! Cancellation of blocking reads, see [`CancellationToken`].
+
+use crate::error::{IpcError, IpcErrorKind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "redis")]
+use std::time::Duration;
+
+/// Max time blocking read waits before it checks for cancellation again.
+#[cfg(feature = "redis")]
+pub(crate) const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Handle, which interrupts blocking reads of structures it was passed to, e.g. with
+/// [`ReadQueue::with_cancellation()`](crate::ReadQueue::with_cancellation). Interrupted reads
+/// return error with [`Cancelled`](IpcErrorKind::Cancelled) kind.
+///
+/// Clones share the same state, so token may be cancelled from other thread, e.g. on shutdown.
+/// Cancellation is permanent.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    /// Set when token is cancelled
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates new, not cancelled token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels token. Blocking reads using it are interrupted within 250 ms.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Checks if token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Returns [`Cancelled`](IpcErrorKind::Cancelled) error if token was cancelled.
+    pub(crate) fn check(&self) -> Result<(), IpcError> {
+        if self.is_cancelled() {
+            return Err(IpcError::new(IpcErrorKind::Cancelled, "Operation cancelled."));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+
+        assert!(clone.check().is_ok());
+
+        token.cancel();
+
+        assert!(clone.is_cancelled());
+        assert!(matches!(clone.check().unwrap_err().kind(), IpcErrorKind::Cancelled));
+    }
+}
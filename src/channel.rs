@@ -0,0 +1,67 @@
+//! Compile time channel definitions. Channel binds a name (used as redis key) with a message
+//! type, so producing or consuming wrong type on a channel is a compile error.
+//!
+//! Channels are usually defined in one shared module with [`channel!`](crate::channel!) macro and
+//! used with `for_channel()` constructors of queues, streams and cache.
+//!
+//! # Examples
+//! ```
+//! use redis_ipc::channel;
+//! use redis_ipc::channel::Channel;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! pub struct OrderCreated {
+//!     id: u64,
+//! }
+//!
+//! channel!(pub Orders: OrderCreated = "orders");
+//!
+//! assert_eq!(Orders::NAME, "orders");
+//!
+//! // let queue = WriteQueue::for_channel::<Orders>(pool);
+//! // queue.publish(&OrderCreated { id: 1 })?;
+//! ```
+
+/// Channel definition. Usually it is implemented for marker types with
+/// [`channel!`](crate::channel!) macro.
+pub trait Channel {
+    /// Type of messages sent on this channel.
+    type Message;
+    /// Channel name, used as redis key.
+    const NAME: &'static str;
+}
+
+/// Defines marker type implementing [`Channel`].
+///
+/// Syntax: `channel!(<visibility> <MarkerName>: <MessageType> = <name>);`, where name is a
+/// `&'static str` constant expression.
+#[macro_export]
+macro_rules! channel {
+    ($(#[$meta:meta])* $vis:vis $ident:ident: $message:ty = $name:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $ident;
+
+        impl $crate::channel::Channel for $ident {
+            type Message = $message;
+            const NAME: &'static str = $name;
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::channel!(Numbers: u64 = "numbers");
+
+    fn channel_name<C: Channel<Message = u64>>() -> &'static str {
+        C::NAME
+    }
+
+    #[test]
+    fn defines_channel() {
+        assert_eq!(channel_name::<Numbers>(), "numbers");
+    }
+}
@@ -0,0 +1,991 @@
+//! Transport-agnostic part of the crate: message envelopes, cache elements and stream ids.
+//!
+//! This module doesn't depend on redis or any socket, so it also compiles with
+//! `default-features = false` (e.g. for `wasm32-wasi`). Plugins running in WASM sandboxes may use
+//! it to build and parse the same messages, which native services send through redis.
+
+use crate::error::{IpcError, IpcErrorKind};
+use serde::de::value::MapDeserializer;
+use serde::de::{DeserializeOwned, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize};
+use serde_json::{Error as SerdeJsonError, Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the envelope field (or stream entry field), which marks messages replayed from
+/// [`DeadLetterQueue`](crate::dlq::DeadLetterQueue).
+pub(crate) const REDELIVERED_FIELD: &str = "redelivered";
+
+/// Name of the stream entry field, which stores id of the message amended by correction or
+/// tombstone, see [`WriteStream::publish_correction()`](crate::WriteStream::publish_correction).
+pub(crate) const AMENDS_FIELD: &str = "amends";
+
+/// Name of the stream entry field, which marks tombstones, see
+/// [`WriteStream::publish_tombstone()`](crate::WriteStream::publish_tombstone).
+pub(crate) const TOMBSTONE_FIELD: &str = "tombstone";
+
+/// Name of the envelope field, which stores key of unique job, see
+/// [`WriteQueue::publish_unique()`](crate::WriteQueue::publish_unique).
+pub(crate) const UNIQUE_KEY_FIELD: &str = "unique_key";
+
+/// Crockford's base32 alphabet, which encodes ULIDs.
+pub(crate) const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Actual message content in redis streams is send in only one field as a string, this is the name
+/// of this field.
+pub const CONTENT_FIELD: &str = "content";
+
+/// Encoding of stream message content into stream entry fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldEncoding {
+    /// Content is stored as JSON in the single `content` field.
+    #[default]
+    Content,
+    /// Every top-level field of content is stored in its own stream entry field, so messages are
+    /// readable by non-Rust consumers and `redis-cli`. Strings are stored as they are, other
+    /// values as JSON. `null` fields (e.g. [`None`]) are omitted. Content has to be serialized to
+    /// JSON object without `content` field and with at least one non-null field.
+    ///
+    /// Entries without `content` field are decoded this way, so readers don't need any
+    /// configuration.
+    Flat,
+}
+
+/// Handling of message content fields, which are unknown to the content type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    /// Unknown fields are ignored.
+    #[default]
+    Lenient,
+    /// Unknown fields are rejected with [`InvalidData`](IpcErrorKind::InvalidData) error, e.g.
+    /// so canary consumer detects schema drift before it silently loses data.
+    Strict,
+}
+
+impl DecodeMode {
+    /// Deserializes JSON string. In strict mode fields unknown to `T` are rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when JSON can't be deserialized to `T` or it has unknown
+    /// fields in strict mode.
+    pub fn from_str<T: DeserializeOwned>(self, json: &str) -> Result<T, IpcError> {
+        match self {
+            Self::Lenient => Ok(serde_json::from_str(json)?),
+            Self::Strict => {
+                let mut deserializer = serde_json::Deserializer::from_str(json);
+                let mut unknown = Vec::new();
+
+                let value = serde_ignored::deserialize(&mut deserializer, |path| {
+                    unknown.push(path.to_string());
+                })?;
+                deserializer.end()?;
+
+                reject_unknown(value, &unknown)
+            }
+        }
+    }
+
+    /// Deserializes JSON value. In strict mode fields unknown to `T` are rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when value can't be deserialized to `T` or it has unknown
+    /// fields in strict mode.
+    pub fn from_value<T: DeserializeOwned>(self, value: Value) -> Result<T, IpcError> {
+        match self {
+            Self::Lenient => Ok(serde_json::from_value(value)?),
+            Self::Strict => {
+                let mut unknown = Vec::new();
+
+                let value = serde_ignored::deserialize(value, |path| {
+                    unknown.push(path.to_string());
+                })?;
+
+                reject_unknown(value, &unknown)
+            }
+        }
+    }
+}
+
+/// Returns error listing unknown fields or value if there are none.
+fn reject_unknown<T>(value: T, unknown: &[String]) -> Result<T, IpcError> {
+    if unknown.is_empty() {
+        return Ok(value);
+    }
+
+    Err(IpcError::new(
+        IpcErrorKind::InvalidData,
+        format!("Unknown fields: {}.", unknown.join(", ")),
+    ))
+}
+
+/// Wrapper struct for messages in [`WriteQueue`](crate::WriteQueue).
+#[derive(Serialize)]
+pub struct WriteQueueMessage<MessageContent: Serialize> {
+    /// Message id
+    uuid: String,
+    /// Custom content
+    content: MessageContent,
+    /// Envelope fields unknown to this crate version, preserved when message is forwarded.
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl<MessageContent: Serialize> WriteQueueMessage<MessageContent> {
+    pub fn new(uuid: String, content: MessageContent) -> WriteQueueMessage<MessageContent> {
+        Self {
+            uuid,
+            content,
+            extra: Map::new(),
+        }
+    }
+
+    /// Sets additional envelope fields.
+    pub fn with_extra(mut self, extra: Map<String, Value>) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    pub fn get_uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    pub fn get_content(&self) -> &MessageContent {
+        &self.content
+    }
+
+    pub fn get_extra(&self) -> &Map<String, Value> {
+        &self.extra
+    }
+}
+
+/// Wrapper for messages in [`ReadQueue`](crate::ReadQueue).
+#[derive(Deserialize)]
+pub struct ReadQueueMessage<MessageContent> {
+    uuid: String,
+    content: MessageContent,
+    /// Envelope fields unknown to this crate version. They are kept, so they are not dropped
+    /// when message is forwarded with [`WriteQueue::forward()`](crate::WriteQueue::forward).
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl<MessageContent: DeserializeOwned> ReadQueueMessage<MessageContent> {
+    /// Deserializes string and builds message from it.
+    ///
+    /// # Errors
+    /// Returns [`Error`](serde_json::Error) produced by [`serde_json::from_str()](serde_json::from_str)
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(message: String) -> Result<ReadQueueMessage<MessageContent>, SerdeJsonError> {
+        serde_json::from_str::<ReadQueueMessage<MessageContent>>(&message)
+    }
+}
+
+impl<MessageContent> ReadQueueMessage<MessageContent> {
+    pub fn get_uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    pub fn get_content(&self) -> &MessageContent {
+        &self.content
+    }
+
+    /// Returns envelope fields unknown to this crate version.
+    pub fn get_extra(&self) -> &Map<String, Value> {
+        &self.extra
+    }
+
+    /// Checks if message was replayed from [`DeadLetterQueue`](crate::dlq::DeadLetterQueue).
+    pub fn is_redelivered(&self) -> bool {
+        self.extra.get(REDELIVERED_FIELD) == Some(&Value::Bool(true))
+    }
+
+    /// Returns key of unique job or [`None`] if message wasn't published as unique job.
+    pub fn get_unique_key(&self) -> Option<&str> {
+        self.extra.get(UNIQUE_KEY_FIELD).and_then(Value::as_str)
+    }
+
+    /// Returns time, when message was sent, derived from its id. It's [`None`] when id doesn't
+    /// embed timestamp, see [`id_timestamp()`].
+    pub fn sent_at(&self) -> Option<SystemTime> {
+        id_timestamp(&self.uuid)
+    }
+}
+
+/// Returns time embedded in message id, which is ULID or UUID v7 (e.g. generated with
+/// [`IdStrategy`](crate::queue::IdStrategy)). Other ids (e.g. UUID v4) don't embed time, so
+/// [`None`] is returned for them.
+pub fn id_timestamp(id: &str) -> Option<SystemTime> {
+    let millis = match id.len() {
+        // ULID: the first 10 characters encode 48-bit timestamp
+        26 => id.as_bytes()[..10].iter().try_fold(0u64, |millis, char| {
+            let digit = ULID_ALPHABET
+                .iter()
+                .position(|symbol| *symbol == char.to_ascii_uppercase())?;
+            Some(millis << 5 | digit as u64)
+        })?,
+        // UUID v7: the first 48 bits are timestamp, version is the 13th hex digit
+        36 if id.as_bytes()[14] == b'7' => {
+            u64::from_str_radix(&format!("{}{}", id.get(..8)?, id.get(9..13)?), 16).ok()?
+        }
+        _ => return None,
+    };
+
+    Some(UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+/// Encodes queue message to the payload, which is pushed to redis list.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when content can't be serialized.
+pub fn encode_queue_message<MessageContent: Serialize>(
+    message: &WriteQueueMessage<MessageContent>,
+) -> Result<String, IpcError> {
+    Ok(serde_json::to_string(message)?)
+}
+
+/// Decodes queue message from payload read from redis list.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when payload isn't valid envelope or content can't be
+/// deserialized.
+pub fn decode_queue_message<MessageContent: DeserializeOwned>(
+    payload: &str,
+) -> Result<ReadQueueMessage<MessageContent>, IpcError> {
+    Ok(serde_json::from_str(payload)?)
+}
+
+/// Same as [`decode_queue_message()`], but content is deserialized according to given mode.
+/// Envelope fields unknown to this crate version are always allowed.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when payload isn't valid envelope or content can't be
+/// deserialized.
+pub fn decode_queue_message_with<MessageContent: DeserializeOwned>(
+    payload: &str,
+    mode: DecodeMode,
+) -> Result<ReadQueueMessage<MessageContent>, IpcError> {
+    if mode == DecodeMode::Lenient {
+        return decode_queue_message(payload);
+    }
+
+    let message = serde_json::from_str::<ReadQueueMessage<Value>>(payload)?;
+
+    Ok(ReadQueueMessage {
+        uuid: message.uuid,
+        content: mode.from_value(message.content)?,
+        extra: message.extra,
+    })
+}
+
+/// Decodes cache element. In strict mode fields of element content unknown to `ElementContent`
+/// are rejected, see [`DecodeMode`].
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when payload can't be deserialized to element or content has
+/// unknown fields in strict mode.
+pub fn decode_cache_element_with<ElementContent: DeserializeOwned>(
+    payload: &str,
+    mode: DecodeMode,
+) -> Result<CacheElement<ElementContent>, IpcError> {
+    let element = serde_json::from_str::<CacheElement<Value>>(payload)?;
+
+    Ok(CacheElement {
+        timestamp: element.timestamp,
+        version: element.version,
+        content: mode.from_value(element.content)?,
+    })
+}
+
+/// Redis stream message id.
+///
+/// According to [official redis docs](https://redis.io/docs/latest/develop/data-types/streams/)
+/// id is stored in format: `<millisecondsTime>-<sequenceNumber>`, where `<millisecondsTime>`
+/// and `<sequenceNumber>` are unsigned 64-bit integers. Ids are ordered the same way as messages
+/// in stream.
+///
+/// It is displayed and parsed (with [`str::parse()`]) in redis format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    /// Unix timestamp (in ms) of the message
+    timestamp: u64,
+    /// Sequence number of messages added in the same millisecond
+    sequence: u64,
+}
+
+impl StreamId {
+    /// Smallest id, which is also used as "no id".
+    pub const ZERO: StreamId = StreamId::new(0, 0);
+
+    pub const fn new(timestamp: u64, sequence: u64) -> Self {
+        Self {
+            timestamp,
+            sequence,
+        }
+    }
+
+    /// Returns unix timestamp (in ms) part of the id.
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns sequence number part of the id.
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the first id of given time. Times before unix epoch are mapped to
+    /// [`StreamId::ZERO`].
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let timestamp = time
+            .duration_since(UNIX_EPOCH)
+            .map(|since| u64::try_from(since.as_millis()).unwrap_or(u64::MAX))
+            .unwrap_or(0);
+
+        Self::new(timestamp, 0)
+    }
+
+    /// Returns time, when message with this id was added to stream.
+    pub fn to_system_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.timestamp)
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.timestamp, self.sequence)
+    }
+}
+
+impl FromStr for StreamId {
+    type Err = io::Error;
+
+    fn from_str(id_str: &str) -> Result<Self, Self::Err> {
+        // Id should have only two parts
+        if let Some((timestamp, seq)) = id_str.split_once('-') {
+            if let (Ok(timestamp), Ok(seq)) = (timestamp.parse(), seq.parse()) {
+                return Ok(Self::new(timestamp, seq));
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Invalid id string. Please provide \"<millisecondsTime>-<sequenceNumber>\".",
+        ))
+    }
+}
+
+impl From<SystemTime> for StreamId {
+    fn from(time: SystemTime) -> Self {
+        Self::from_system_time(time)
+    }
+}
+
+impl From<(u64, u64)> for StreamId {
+    fn from((timestamp, sequence): (u64, u64)) -> Self {
+        Self::new(timestamp, sequence)
+    }
+}
+
+impl From<StreamId> for (u64, u64) {
+    fn from(id: StreamId) -> Self {
+        (id.timestamp, id.sequence)
+    }
+}
+
+/// Stream message wrapper object (dto)
+pub struct StreamMessage<MessageContent> {
+    /// Message id
+    id: StreamId,
+    /// Custom message content
+    content: MessageContent,
+    /// Stream entry fields other than content, e.g. added by newer crate versions. They are
+    /// preserved when message is forwarded with
+    /// [`WriteStream::forward()`](crate::WriteStream::forward).
+    extra: HashMap<String, String>,
+}
+
+impl<MessageContent> StreamMessage<MessageContent> {
+    pub fn new(id: StreamId, content: MessageContent) -> Self {
+        Self {
+            id,
+            content,
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Sets additional stream entry fields.
+    pub fn with_extra(mut self, extra: HashMap<String, String>) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// Returns stream entry fields other than content.
+    pub fn get_extra(&self) -> &HashMap<String, String> {
+        &self.extra
+    }
+
+    /// Checks if message was replayed from [`DeadLetterQueue`](crate::dlq::DeadLetterQueue).
+    pub fn is_redelivered(&self) -> bool {
+        self.extra.get(REDELIVERED_FIELD).map(String::as_str) == Some("true")
+    }
+
+    /// Returns id of the message amended by this correction or tombstone, [`None`] for regular
+    /// messages.
+    pub fn get_amended_id(&self) -> Option<StreamId> {
+        self.extra.get(AMENDS_FIELD).and_then(|id| id.parse().ok())
+    }
+
+    /// Checks if message is a tombstone, which deletes message with
+    /// [amended id](StreamMessage::get_amended_id).
+    pub fn is_tombstone(&self) -> bool {
+        self.extra.get(TOMBSTONE_FIELD).map(String::as_str) == Some("true")
+    }
+
+    pub fn get_content(&self) -> &MessageContent {
+        &self.content
+    }
+
+    /// Consumes message and returns its content.
+    pub fn into_content(self) -> MessageContent {
+        self.content
+    }
+
+    pub fn get_id(&self) -> StreamId {
+        self.id
+    }
+
+    /// Returns time, when message was added to stream, derived from its id.
+    pub fn sent_at(&self) -> SystemTime {
+        self.id.to_system_time()
+    }
+}
+
+/// Stringifies redis id to format `<millisecondsTime>-<sequenceNumber>`. Same as
+/// [`StreamId`] display.
+pub fn stringify_id(id: &StreamId) -> String {
+    id.to_string()
+}
+
+/// Parses redis stream id (stored in [`String`](String)) from `&str`. Same as [`StreamId`]
+/// [`FromStr`] implementation.
+///
+/// # Errors
+///
+/// Returns [`io::Error`] with [`InvalidInput`](io::ErrorKind::InvalidInput) kind, when id is
+/// improper.
+pub fn parse_id(id_str: &str) -> Result<StreamId, io::Error> {
+    id_str.parse()
+}
+
+/// Encodes stream message to stream entry fields: content field and extra fields of the message.
+/// Message id isn't encoded, because it is generated by redis.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when content can't be serialized.
+pub fn encode_stream_message<MessageContent: Serialize>(
+    message: &StreamMessage<MessageContent>,
+) -> Result<Vec<(String, String)>, IpcError> {
+    encode_stream_message_with(message, FieldEncoding::Content)
+}
+
+/// Same as [`encode_stream_message()`], but content is encoded with given encoding. Extra fields
+/// with the same names as content fields are replaced.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when content can't be serialized or encoded.
+pub fn encode_stream_message_with<MessageContent: Serialize>(
+    message: &StreamMessage<MessageContent>,
+    encoding: FieldEncoding,
+) -> Result<Vec<(String, String)>, IpcError> {
+    let content = encode_stream_content(&message.content, encoding)?;
+
+    let mut fields: Vec<(String, String)> = message
+        .extra
+        .iter()
+        .filter(|(field, _)| content.iter().all(|(name, _)| name != *field))
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect();
+    fields.extend(content);
+
+    Ok(fields)
+}
+
+/// Encodes message content to stream entry fields with given encoding.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when content can't be serialized or it isn't JSON object
+/// without `content` field and with a non-null field in [`FieldEncoding::Flat`] encoding.
+pub fn encode_stream_content<MessageContent: Serialize>(
+    content: &MessageContent,
+    encoding: FieldEncoding,
+) -> Result<Vec<(String, String)>, IpcError> {
+    if encoding == FieldEncoding::Content {
+        return Ok(vec![(CONTENT_FIELD.to_string(), serde_json::to_string(content)?)]);
+    }
+
+    let Value::Object(object) = serde_json::to_value(content)? else {
+        return Err(IpcError::new(
+            IpcErrorKind::InvalidData,
+            "Flat encoded content has to be an object.",
+        ));
+    };
+
+    if object.contains_key(CONTENT_FIELD) {
+        return Err(IpcError::new(
+            IpcErrorKind::InvalidData,
+            "Flat encoded content can't have content field.",
+        ));
+    }
+
+    // null fields are omitted, so `Some("null")` isn't mistaken for `None`
+    let fields: Vec<(String, String)> = object
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(field, value)| match value {
+            Value::String(value) => (field, value),
+            value => (field, value.to_string()),
+        })
+        .collect();
+
+    if fields.is_empty() {
+        return Err(IpcError::new(
+            IpcErrorKind::InvalidData,
+            "Flat encoded content has to have a non-null field.",
+        ));
+    }
+
+    Ok(fields)
+}
+
+/// Decodes stream message from its id and stream entry fields. Fields other than content are
+/// stored as message extra fields.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when id is improper, `content` field is missing or it can't
+/// be parsed to `MessageContent`.
+pub fn decode_stream_message<MessageContent: DeserializeOwned>(
+    id: &str,
+    fields: &HashMap<String, String>,
+) -> Result<StreamMessage<MessageContent>, IpcError> {
+    decode_stream_message_with(id, fields, DecodeMode::Lenient)
+}
+
+/// Same as [`decode_stream_message()`], but content is deserialized according to given mode.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when id is improper, `content` field is missing or it can't
+/// be parsed to `MessageContent`.
+pub fn decode_stream_message_with<MessageContent: DeserializeOwned>(
+    id: &str,
+    fields: &HashMap<String, String>,
+    mode: DecodeMode,
+) -> Result<StreamMessage<MessageContent>, IpcError> {
+    let id = parse_id(id)?;
+
+    let Some(content) = fields.get(CONTENT_FIELD) else {
+        // all fields are kept as extra, so forwarded message doesn't lose unknown ones
+        let content = decode_flat_content(fields, mode)?;
+        return Ok(StreamMessage::new(id, content).with_extra(fields.clone()));
+    };
+
+    let content = match mode {
+        DecodeMode::Lenient => serde_json::from_str::<MessageContent>(content).map_err(|error| {
+            IpcError::with_source(
+                IpcErrorKind::InvalidData,
+                "Message content can't be parsed.",
+                error,
+            )
+        })?,
+        DecodeMode::Strict => mode.from_str::<MessageContent>(content)?,
+    };
+
+    let extra = fields
+        .iter()
+        .filter(|(field, _)| field.as_str() != CONTENT_FIELD)
+        .map(|(field, value)| (field.clone(), value.clone()))
+        .collect();
+
+    Ok(StreamMessage::new(id, content).with_extra(extra))
+}
+
+/// Decodes content stored with [`FieldEncoding::Flat`] encoding.
+fn decode_flat_content<MessageContent: DeserializeOwned>(
+    fields: &HashMap<String, String>,
+    mode: DecodeMode,
+) -> Result<MessageContent, IpcError> {
+    let entries = fields
+        .iter()
+        // metadata fields are stored next to content ones, so strict mode doesn't reject them
+        .filter(|(field, _)| mode == DecodeMode::Lenient || !is_metadata_field(field))
+        .map(|(field, value)| (field.as_str(), FieldDeserializer(value)));
+    let deserializer = MapDeserializer::<_, SerdeJsonError>::new(entries);
+
+    match mode {
+        DecodeMode::Lenient => MessageContent::deserialize(deserializer).map_err(|error| {
+            IpcError::with_source(
+                IpcErrorKind::InvalidData,
+                "Message content can't be parsed.",
+                error,
+            )
+        }),
+        DecodeMode::Strict => {
+            let mut unknown = Vec::new();
+
+            let content = serde_ignored::deserialize(deserializer, |path| {
+                unknown.push(path.to_string());
+            })?;
+
+            reject_unknown(content, &unknown)
+        }
+    }
+}
+
+/// Checks if stream entry field is metadata added by this crate, not a content field.
+fn is_metadata_field(field: &str) -> bool {
+    [REDELIVERED_FIELD, AMENDS_FIELD, TOMBSTONE_FIELD].contains(&field)
+}
+
+/// Deserializer of single flat encoded stream entry field. Strings are read as they are, other
+/// values are parsed as JSON.
+struct FieldDeserializer<'a>(&'a str);
+
+impl<'de> IntoDeserializer<'de, SerdeJsonError> for FieldDeserializer<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for FieldDeserializer<'_> {
+    type Error = SerdeJsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match serde_json::from_str::<Value>(self.0) {
+            Ok(value) => value.deserialize_any(visitor),
+            Err(_) => visitor.visit_str(self.0),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // `None` is encoded by omitting the field, so present field is always `Some`
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match serde_json::from_str::<Value>(self.0) {
+            // unit variants are stored as plain strings
+            Ok(value @ Value::Object(_)) => value.deserialize_enum(name, variants, visitor),
+            _ => IntoDeserializer::<SerdeJsonError>::into_deserializer(self.0)
+                .deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 bytes byte_buf unit unit_struct seq
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Wrapper struct for elements in cache.
+#[derive(Serialize, Deserialize)]
+pub struct CacheElement<ElementContent> {
+    timestamp: u128,
+    /// Element version, bumped on every write. Elements written by older crate versions have
+    /// version `0`.
+    #[serde(default)]
+    version: u64,
+    content: ElementContent,
+}
+
+impl<ElementContent> CacheElement<ElementContent> {
+    /// Creates a new `CacheElement`. `timestamp` param should be unix timestamp.
+    pub fn new(timestamp: u128, content: ElementContent) -> Self {
+        Self {
+            timestamp,
+            version: 0,
+            content,
+        }
+    }
+
+    /// Creates a new `CacheElement` with given version.
+    pub fn with_version(timestamp: u128, version: u64, content: ElementContent) -> Self {
+        Self {
+            timestamp,
+            version,
+            content,
+        }
+    }
+
+    /// Getter for timestamp field
+    pub fn get_timestamp_128(&self) -> u128 {
+        self.timestamp
+    }
+
+    /// Getter for version field
+    pub fn get_version(&self) -> u64 {
+        self.version
+    }
+
+    /// Getter for content field
+    pub fn get_content(&self) -> &ElementContent {
+        &self.content
+    }
+
+    /// Consumes element and returns its content.
+    pub fn into_content(self) -> ElementContent {
+        self.content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Debug)]
+    struct Order {
+        id: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Status {
+        New,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Shipment {
+        name: String,
+        code: String,
+        count: u32,
+        note: Option<String>,
+        status: Status,
+    }
+
+    #[test]
+    fn flat_encoding_round_trip() {
+        let shipment = Shipment {
+            name: String::from("box"),
+            code: String::from("42"),
+            count: 3,
+            note: None,
+            status: Status::New,
+        };
+
+        let fields: HashMap<String, String> =
+            encode_stream_content(&shipment, FieldEncoding::Flat).unwrap().into_iter().collect();
+        assert_eq!(fields["name"], "box");
+        assert_eq!(fields["count"], "3");
+        assert_eq!(fields["status"], "New");
+
+        let message = decode_stream_message::<Shipment>("1-0", &fields).unwrap();
+        assert_eq!(message.get_content(), &shipment);
+
+        assert!(encode_stream_content(&1, FieldEncoding::Flat).is_err());
+    }
+
+    #[test]
+    fn flat_encoding_keeps_null_string() {
+        let mut shipment = Shipment {
+            name: String::from("box"),
+            code: String::from("null"),
+            count: 3,
+            note: Some(String::from("null")),
+            status: Status::New,
+        };
+
+        let fields: HashMap<String, String> =
+            encode_stream_content(&shipment, FieldEncoding::Flat).unwrap().into_iter().collect();
+        let message = decode_stream_message::<Shipment>("1-0", &fields).unwrap();
+        assert_eq!(message.get_content(), &shipment);
+
+        shipment.note = None;
+
+        let fields: HashMap<String, String> =
+            encode_stream_content(&shipment, FieldEncoding::Flat).unwrap().into_iter().collect();
+        assert!(!fields.contains_key("note"));
+
+        let message = decode_stream_message::<Shipment>("1-0", &fields).unwrap();
+        assert_eq!(message.get_content(), &shipment);
+    }
+
+    #[test]
+    fn strict_mode_rejects_unknown_fields() {
+        let payload = r#"{"uuid":"1","content":{"id":1,"note":"a"},"trace_id":"abc"}"#;
+
+        let lenient = decode_queue_message_with::<Order>(payload, DecodeMode::Lenient).unwrap();
+        assert_eq!(lenient.get_content().id, 1);
+
+        let strict = decode_queue_message_with::<Order>(payload, DecodeMode::Strict);
+        assert!(matches!(strict.err().unwrap().kind(), IpcErrorKind::InvalidData));
+
+        // unknown envelope fields are allowed
+        let payload = r#"{"uuid":"1","content":{"id":1},"trace_id":"abc"}"#;
+        assert!(decode_queue_message_with::<Order>(payload, DecodeMode::Strict).is_ok());
+
+        assert!(DecodeMode::Strict.from_str::<Order>(r#"{"id":1,"note":"a"}"#).is_err());
+    }
+
+    #[test]
+    fn lenient_decode_keeps_serde_error_as_source() {
+        let fields = HashMap::from([(CONTENT_FIELD.to_string(), String::from("{"))]);
+        let error = decode_stream_message::<Order>("1-0", &fields).err().unwrap();
+        assert!(error.downcast_ref::<SerdeJsonError>().is_some());
+
+        let fields = HashMap::from([(String::from("id"), String::from("a"))]);
+        let error = decode_stream_message::<Order>("1-0", &fields).err().unwrap();
+        assert!(error.downcast_ref::<SerdeJsonError>().is_some());
+    }
+
+    #[test]
+    fn strict_mode_accepts_metadata_of_flat_entries() {
+        let mut fields = HashMap::from([(String::from("id"), String::from("1"))]);
+        fields.insert(REDELIVERED_FIELD.to_string(), String::from("true"));
+        fields.insert(AMENDS_FIELD.to_string(), String::from("1-0"));
+
+        let message = decode_stream_message_with::<Order>("2-0", &fields, DecodeMode::Strict);
+        assert_eq!(message.unwrap().get_content().id, 1);
+
+        fields.insert(String::from("note"), String::from("a"));
+        assert!(decode_stream_message_with::<Order>("2-0", &fields, DecodeMode::Strict).is_err());
+    }
+
+    #[test]
+    fn timestamp_is_read_from_id() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_469_922_850_259);
+
+        assert_eq!(id_timestamp("01ARZ3NDEKTSV4RRFFQ69G5FAV"), Some(time));
+        assert_eq!(id_timestamp("01arz3ndektsv4rrffq69g5fav"), Some(time));
+        assert_eq!(id_timestamp("01563e3a-b5d3-7d67-8b2f-1c6b5e1a0c3d"), Some(time));
+        assert_eq!(id_timestamp("f47ac10b-58cc-4372-a567-0e02b2c3d479"), None);
+        assert_eq!(id_timestamp("1"), None);
+    }
+
+    #[test]
+    fn unknown_envelope_fields_are_preserved() {
+        let json = r#"{"uuid":"1","content":"hello","trace_id":"abc","headers":{"a":1}}"#;
+
+        let read = ReadQueueMessage::<String>::from_str(json.to_string()).unwrap();
+
+        assert_eq!(read.get_extra().len(), 2);
+
+        let write = WriteQueueMessage::new(read.uuid.clone(), &read.content)
+            .with_extra(read.extra.clone());
+
+        let forwarded: Value = serde_json::to_value(&write).unwrap();
+
+        assert_eq!(forwarded, serde_json::from_str::<Value>(json).unwrap());
+    }
+
+    #[test]
+    fn queue_message_round_trip() {
+        let message = WriteQueueMessage::new("1".to_string(), "hello");
+
+        let payload = encode_queue_message(&message).unwrap();
+        let decoded = decode_queue_message::<String>(&payload).unwrap();
+
+        assert_eq!(decoded.get_uuid(), "1");
+        assert_eq!(decoded.get_content(), "hello");
+    }
+
+    #[test]
+    fn stream_message_round_trip() {
+        let extra = HashMap::from([("trace_id".to_string(), "abc".to_string())]);
+        let message = StreamMessage::new(StreamId::new(1, 0), 42).with_extra(extra.clone());
+
+        let fields = encode_stream_message(&message).unwrap().into_iter().collect();
+        let decoded = decode_stream_message::<i32>("1-0", &fields).unwrap();
+
+        assert_eq!(decoded.get_id(), StreamId::new(1, 0));
+        assert_eq!(*decoded.get_content(), 42);
+        assert_eq!(decoded.get_extra(), &extra);
+    }
+
+    #[test]
+    fn stream_id_decoding() {
+        let example = "123456-789102";
+
+        let result = parse_id(example).unwrap();
+
+        assert_eq!(result, StreamId::new(123456, 789102));
+    }
+
+    #[test]
+    #[should_panic]
+    fn stream_id_decoding_fails_on_too_short() {
+        // improper id
+        let example = "123456";
+
+        let _ = parse_id(example).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn stream_id_decoding_fails_on_too_bg_num() {
+        // to big number id
+        let example = "999999999999999999999999-123";
+
+        let _ = parse_id(example).unwrap();
+    }
+
+    #[test]
+    fn stream_id_round_trip() {
+        let id = StreamId::new(17, 3);
+
+        assert_eq!(parse_id(&stringify_id(&id)).unwrap(), id);
+    }
+
+    #[test]
+    fn stream_ids_are_ordered() {
+        assert!(StreamId::new(1, 5) < StreamId::new(2, 0));
+        assert!(StreamId::new(2, 0) < StreamId::new(2, 1));
+        assert_eq!(StreamId::new(2, 1).to_string(), "2-1");
+    }
+
+    #[test]
+    fn stream_id_system_time_round_trip() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let id = StreamId::from_system_time(time);
+
+        assert_eq!(id, StreamId::new(1_700_000_000_123, 0));
+        assert_eq!(id.to_system_time(), time);
+    }
+}
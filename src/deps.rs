@@ -0,0 +1,29 @@
+//! Re-exports of dependencies, whose types appear in the API of this crate (pool, client,
+//! errors, JSON values). Using them instead of direct dependencies keeps versions matching, so
+//! e.g. [`RedisPool`](crate::RedisPool) may be built without pinning the same `redis` and
+//! `r2d2` versions as this crate.
+//!
+//! # Examples
+//! ```ignored
+//! use redis_ipc::deps::{r2d2, redis};
+//!
+//! let client = redis::Client::open("redis://127.0.0.1")?;
+//! let pool = r2d2::Pool::builder().build(client)?;
+//! ```
+
+#[cfg(feature = "streams")]
+pub use r2d2;
+#[cfg(feature = "streams")]
+pub use redis;
+pub use serde_json;
+#[cfg(feature = "redis")]
+pub use uuid;
+
+/// Redis client, which is managed by [`RedisPool`](crate::RedisPool).
+#[cfg(feature = "streams")]
+pub use redis::{Client, RedisError};
+/// Connection pool and its error.
+#[cfg(feature = "streams")]
+pub use r2d2::{Error as R2d2Error, Pool, PooledConnection};
+/// JSON value and error used by message content and codecs.
+pub use serde_json::{Error as SerdeJsonError, Value};
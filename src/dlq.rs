@@ -0,0 +1,516 @@
+//! Dead letter queue, which stores messages that couldn't be processed together with failure
+//! metadata.
+//!
+//! Dead letters are stored in redis hash `<name>:entries` (mapped by dead letter id) and their
+//! order is kept in redis list `<name>:ids`.
+
+use crate::codec::{
+    ReadQueueMessage, StreamMessage, WriteQueueMessage, CONTENT_FIELD, REDELIVERED_FIELD,
+};
+use crate::error::IpcError;
+use crate::helpers::timestamp_u128_now;
+use crate::redact::Redactor;
+use crate::RedisPool;
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Suffix of redis hash with dead letters.
+const ENTRIES_SUFFIX: &str = ":entries";
+/// Suffix of redis list with dead letter ids.
+const IDS_SUFFIX: &str = ":ids";
+/// Number of dead letters read at once during replay.
+const REPLAY_PAGE_SIZE: usize = 100;
+
+/// Removes dead letter `ARGV[1]` and returns it, or `nil` if it doesn't exist, so every dead
+/// letter is replayed by one caller only. Replayed ids are near the head of ids list, so `LREM`
+/// stops after the first match.
+const CLAIM_SCRIPT: &str = r"
+local entry = redis.call('HGET', KEYS[1], ARGV[1])
+if not entry then
+    return false
+end
+redis.call('HDEL', KEYS[1], ARGV[1])
+redis.call('LREM', KEYS[2], 1, ARGV[1])
+return entry
+";
+
+/// Kind of structure, which dead lettered message comes from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    Queue,
+    Stream,
+}
+
+/// Message, which couldn't be processed, with failure metadata.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DeadLetter<MessageContent> {
+    /// Dead letter id
+    id: String,
+    /// Original message content
+    payload: MessageContent,
+    /// Error, which caused dead lettering
+    error: String,
+    /// Number of processing attempts
+    attempts: u32,
+    /// Name of consumer, which failed
+    consumer: Option<String>,
+    /// Name of original queue or stream
+    source: String,
+    /// Kind of original structure
+    source_kind: SourceKind,
+    /// Original message id (queue message uuid or stringified stream id)
+    message_id: Option<String>,
+    /// Unix timestamp (ms) of first processing attempt, if known
+    first_attempt_at: Option<u128>,
+    /// Unix timestamp (ms) of dead lettering
+    failed_at: u128,
+    /// Envelope fields (or stream entry fields) of original message other than content, e.g.
+    /// added by newer producers. They are restored on replay.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    extra: Map<String, Value>,
+}
+
+impl<MessageContent> DeadLetter<MessageContent> {
+    /// Creates a new dead letter with single attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * payload - original message content
+    /// * source - name of original queue or stream
+    /// * source_kind - kind of original structure
+    /// * error - error, which caused dead lettering
+    pub fn new(
+        payload: MessageContent,
+        source: &str,
+        source_kind: SourceKind,
+        error: &str,
+    ) -> Result<Self, IpcError> {
+        Ok(Self {
+            id: Uuid::new_v4().to_string(),
+            payload,
+            error: error.to_string(),
+            attempts: 1,
+            consumer: None,
+            source: source.to_string(),
+            source_kind,
+            message_id: None,
+            first_attempt_at: None,
+            failed_at: timestamp_u128_now()?,
+            extra: Map::new(),
+        })
+    }
+
+    /// Creates dead letter from message read from [`ReadQueue`](crate::ReadQueue).
+    pub fn from_queue_message(
+        message: &ReadQueueMessage<MessageContent>,
+        queue: &str,
+        error: &str,
+    ) -> Result<Self, IpcError>
+    where
+        MessageContent: DeserializeOwned + Clone,
+    {
+        Ok(
+            Self::new(message.get_content().clone(), queue, SourceKind::Queue, error)?
+                .with_message_id(message.get_uuid())
+                .with_extra(message.get_extra().clone()),
+        )
+    }
+
+    /// Creates dead letter from message read from [`ReadStream`](crate::ReadStream).
+    pub fn from_stream_message(
+        message: &StreamMessage<MessageContent>,
+        stream: &str,
+        error: &str,
+    ) -> Result<Self, IpcError>
+    where
+        MessageContent: Clone,
+    {
+        let extra = message
+            .get_extra()
+            .iter()
+            .map(|(field, value)| (field.clone(), Value::String(value.clone())))
+            .collect();
+
+        Ok(
+            Self::new(message.get_content().clone(), stream, SourceKind::Stream, error)?
+                .with_message_id(&message.get_id().to_string())
+                .with_extra(extra),
+        )
+    }
+
+    /// Sets number of processing attempts.
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Sets name of consumer, which failed.
+    pub fn with_consumer(mut self, consumer: &str) -> Self {
+        self.consumer = Some(consumer.to_string());
+        self
+    }
+
+    /// Sets original message id.
+    pub fn with_message_id(mut self, message_id: &str) -> Self {
+        self.message_id = Some(message_id.to_string());
+        self
+    }
+
+    /// Sets fields of original message other than content, which are restored on replay.
+    pub fn with_extra(mut self, extra: Map<String, Value>) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// Sets unix timestamp (ms) of first processing attempt.
+    pub fn with_first_attempt_at(mut self, first_attempt_at: u128) -> Self {
+        self.first_attempt_at = Some(first_attempt_at);
+        self
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_payload(&self) -> &MessageContent {
+        &self.payload
+    }
+
+    pub fn get_error(&self) -> &str {
+        &self.error
+    }
+
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn get_consumer(&self) -> Option<&str> {
+        self.consumer.as_deref()
+    }
+
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn get_source_kind(&self) -> SourceKind {
+        self.source_kind
+    }
+
+    pub fn get_message_id(&self) -> Option<&str> {
+        self.message_id.as_deref()
+    }
+
+    pub fn get_first_attempt_at(&self) -> Option<u128> {
+        self.first_attempt_at
+    }
+
+    pub fn get_failed_at(&self) -> u128 {
+        self.failed_at
+    }
+
+    pub fn get_extra(&self) -> &Map<String, Value> {
+        &self.extra
+    }
+}
+
+/// Dead letter queue. It allows to store, list, inspect and replay [`DeadLetter`]s.
+#[derive(Clone)]
+pub struct DeadLetterQueue<MessageContent: Serialize + DeserializeOwned> {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// dead letter queue name
+    name: Arc<String>,
+    /// redaction of stored payloads
+    redactor: Option<Redactor>,
+    /// phantom indicating message type of queue instance
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: Serialize + DeserializeOwned> DeadLetterQueue<MessageContent> {
+    /// Builds dead letter queue with given name.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    /// * name - dead letter queue name, used as prefix of redis keys
+    pub fn new(pool: RedisPool, name: &str) -> Self {
+        Self {
+            pool,
+            name: Arc::new(name.to_string()),
+            redactor: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets redaction applied to payloads of stored dead letters, so sensitive fields aren't
+    /// kept in dead letter queue. Redacted payloads are also replayed, so redaction should keep
+    /// them parsable to `MessageContent`, see [`Redactor::mask_fields()`](Redactor::mask_fields).
+    pub fn with_redaction(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Stores dead letter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn push(&self, dead_letter: &DeadLetter<MessageContent>) -> Result<(), IpcError> {
+        let mut entry = serde_json::to_value(dead_letter)?;
+
+        if let (Some(redactor), Some(payload)) = (&self.redactor, entry.get_mut("payload")) {
+            redactor.redact_value(payload);
+        }
+
+        let json = serde_json::to_string(&entry)?;
+
+        let mut conn = self.pool.get()?;
+
+        redis::pipe()
+            .atomic()
+            .hset(self.entries_key(), &dead_letter.id, &json)
+            .rpush(self.ids_key(), &dead_letter.id)
+            .exec(&mut *conn)?;
+
+        Ok(())
+    }
+
+    /// Returns number of stored dead letters.
+    pub fn len(&self) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        Ok(conn.llen::<String, usize>(self.ids_key())?)
+    }
+
+    /// Checks if dead letter queue is empty.
+    pub fn is_empty(&self) -> Result<bool, IpcError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Lists at most `count` dead letters starting from `start` index (the oldest first).
+    pub fn list(
+        &self,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<DeadLetter<MessageContent>>, IpcError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.pool.get()?;
+
+        let stop = isize::try_from(start + count - 1).unwrap_or(isize::MAX);
+        let start = isize::try_from(start).unwrap_or(isize::MAX);
+
+        let ids = conn.lrange::<String, Vec<String>>(self.ids_key(), start, stop)?;
+
+        self.get_many(&ids)
+    }
+
+    /// Returns dead letter with given id or [`None`] if it doesn't exist.
+    pub fn inspect(&self, id: &str) -> Result<Option<DeadLetter<MessageContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let entry = conn.hget::<String, &str, Option<String>>(self.entries_key(), id)?;
+
+        Ok(match entry {
+            Some(entry) => Some(serde_json::from_str::<DeadLetter<MessageContent>>(&entry)?),
+            None => None,
+        })
+    }
+
+    /// Removes dead letter with given id. Returns `false` if it didn't exist.
+    pub fn remove(&self, id: &str) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let (removed, _) = redis::pipe()
+            .atomic()
+            .hdel(self.entries_key(), id)
+            .lrem(self.ids_key(), 0, id)
+            .query::<(u32, u32)>(&mut *conn)?;
+
+        Ok(removed != 0)
+    }
+
+    /// Re-publishes dead letter with given id to its original queue or stream (marked as
+    /// redelivered) and removes it from dead letter queue. Returns `false` if dead letter doesn't
+    /// exist or was replayed by other process meanwhile.
+    pub fn replay_entry(&self, id: &str) -> Result<bool, IpcError> {
+        self.replay_claimed(id, None)
+    }
+
+    /// Re-publishes dead letters matching `filter` (the oldest first) and removes them from dead
+    /// letter queue. Replayed messages are marked as redelivered, see
+    /// [`ReadQueueMessage::is_redelivered()`](ReadQueueMessage::is_redelivered). Returns number
+    /// of replayed dead letters.
+    ///
+    /// # Arguments
+    ///
+    /// * filter - selects dead letters to replay
+    /// * rate - maximum number of replayed messages per second or [`None`] for no limit
+    /// * target - name of queue or stream to publish to or [`None`] for the original one
+    pub fn replay<F>(
+        &self,
+        filter: F,
+        rate: Option<u32>,
+        target: Option<&str>,
+    ) -> Result<usize, IpcError>
+    where
+        F: Fn(&DeadLetter<MessageContent>) -> bool,
+    {
+        let interval = rate
+            .filter(|rate| *rate > 0)
+            .map(|rate| Duration::from_secs(1) / rate);
+
+        // ids are read once, so dead letters pushed during replay are not replayed
+        let ids = {
+            let mut conn = self.pool.get()?;
+            conn.lrange::<String, Vec<String>>(self.ids_key(), 0, -1)?
+        };
+
+        let mut replayed = 0;
+        let mut next_publish = Instant::now();
+
+        for ids in ids.chunks(REPLAY_PAGE_SIZE) {
+            for dead_letter in self.get_many(ids)? {
+                if !filter(&dead_letter) {
+                    continue;
+                }
+
+                if let Some(interval) = interval {
+                    let now = Instant::now();
+                    if next_publish > now {
+                        thread::sleep(next_publish - now);
+                    }
+                    next_publish = Instant::now() + interval;
+                }
+
+                if self.replay_claimed(&dead_letter.id, target)? {
+                    replayed += 1;
+                }
+            }
+        }
+
+        Ok(replayed)
+    }
+
+    /// Removes dead letter from dead letter queue and re-publishes it to `target` or its
+    /// original source. Dead letter, which can't be re-published, is returned to the front of
+    /// dead letter queue. Returns `false` if dead letter doesn't exist.
+    fn replay_claimed(&self, id: &str, target: Option<&str>) -> Result<bool, IpcError> {
+        let entry = {
+            let mut conn = self.pool.get()?;
+
+            redis::Script::new(CLAIM_SCRIPT)
+                .key(self.entries_key())
+                .key(self.ids_key())
+                .arg(id)
+                .invoke::<Option<String>>(&mut *conn)?
+        };
+
+        let Some(entry) = entry else {
+            return Ok(false);
+        };
+
+        let replayed = serde_json::from_str::<DeadLetter<MessageContent>>(&entry)
+            .map_err(IpcError::from)
+            .and_then(|dead_letter| {
+                self.republish(&dead_letter, target.unwrap_or(&dead_letter.source))
+            });
+
+        if let Err(error) = replayed {
+            let mut conn = self.pool.get()?;
+
+            redis::pipe()
+                .atomic()
+                .hset(self.entries_key(), id, &entry)
+                .lpush(self.ids_key(), id)
+                .exec(&mut *conn)?;
+
+            return Err(error);
+        }
+
+        Ok(true)
+    }
+
+    /// Returns existing dead letters with given ids.
+    fn get_many(&self, ids: &[String]) -> Result<Vec<DeadLetter<MessageContent>>, IpcError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut conn = self.pool.get()?;
+
+        let entries = redis::cmd("HMGET")
+            .arg(self.entries_key())
+            .arg(ids)
+            .query::<Vec<Option<String>>>(&mut *conn)?;
+
+        entries
+            .into_iter()
+            .flatten()
+            .map(|entry| Ok(serde_json::from_str::<DeadLetter<MessageContent>>(&entry)?))
+            .collect()
+    }
+
+    /// Publishes dead letter payload to queue or stream with given name, using format of
+    /// original structure. Message is marked as redelivered.
+    fn republish(
+        &self,
+        dead_letter: &DeadLetter<MessageContent>,
+        target: &str,
+    ) -> Result<(), IpcError> {
+        let mut conn = self.pool.get()?;
+
+        match dead_letter.source_kind {
+            SourceKind::Queue => {
+                let uuid = dead_letter
+                    .message_id
+                    .clone()
+                    .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+                let mut extra = dead_letter.extra.clone();
+                extra.insert(REDELIVERED_FIELD.to_string(), Value::Bool(true));
+
+                let message = WriteQueueMessage::new(uuid, &dead_letter.payload).with_extra(extra);
+                let json = serde_json::to_string(&message)?;
+
+                conn.lpush::<&str, &str, ()>(target, &json)?;
+            }
+            SourceKind::Stream => {
+                let json = serde_json::to_string(&dead_letter.payload)?;
+
+                let mut fields: Vec<(String, String)> = dead_letter
+                    .extra
+                    .iter()
+                    .filter(|(field, _)| *field != REDELIVERED_FIELD && *field != CONTENT_FIELD)
+                    .map(|(field, value)| match value {
+                        Value::String(value) => (field.clone(), value.clone()),
+                        value => (field.clone(), value.to_string()),
+                    })
+                    .collect();
+                fields.push((REDELIVERED_FIELD.to_string(), String::from("true")));
+                fields.push((CONTENT_FIELD.to_string(), json));
+
+                conn.xadd::<&str, &str, String, String, String>(target, "*", &fields)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn entries_key(&self) -> String {
+        format!("{}{}", self.name, ENTRIES_SUFFIX)
+    }
+
+    fn ids_key(&self) -> String {
+        format!("{}{}", self.name, IDS_SUFFIX)
+    }
+}
@@ -1,6 +1,8 @@
 //! This module covers everything related to error handling in this crate.
 
+#[cfg(feature = "streams")]
 use r2d2::Error as R2d2Error;
+#[cfg(feature = "streams")]
 use redis::RedisError;
 use serde_json::Error as SerdeJsonError;
 use std::error::Error;
@@ -9,6 +11,9 @@ use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::sync::PoisonError;
 use std::time::SystemTimeError;
 
+/// Result type used in this crate, shorthand for `Result<T, IpcError>`.
+pub type IpcResult<T> = Result<T, IpcError>;
+
 /// Error kinds used in this crate. For more specific error kinds handling use source error.
 #[non_exhaustive]
 #[derive(Debug)]
@@ -19,6 +24,14 @@ pub enum IpcErrorKind {
     Timeout,
     /// Serializing/deserializing error.
     InvalidData,
+    /// Optimistic locking conflict, e.g. cache element version changed since it was read.
+    Conflict,
+    /// Structure required by operation doesn't exist, e.g. stream, which isn't created
+    /// automatically.
+    NotFound,
+    /// Blocking operation was interrupted with
+    /// [`CancellationToken`](crate::cancel::CancellationToken).
+    Cancelled,
     /// Error when accessing memory, e.g. poisoned lock. Should not ever happen.
     MemoryAccessError,
     /// IoError, which does not contain in any kind above.
@@ -35,6 +48,8 @@ pub struct IpcError {
     kind: IpcErrorKind,
     /// Source error or string.
     error: Box<dyn Error + Send + Sync>,
+    /// Description of failed operation, when source is an error of other crate
+    message: Option<String>,
 }
 
 impl IpcError {
@@ -47,6 +62,19 @@ impl IpcError {
         Self {
             kind,
             error: error.into(),
+            message: None,
+        }
+    }
+
+    /// Constructs new error from given `kind`, `message` describing failed operation and source
+    /// `error`, which stays available with [`downcast_ref()`](Self::downcast_ref).
+    pub fn with_source<E>(kind: IpcErrorKind, message: &str, error: E) -> Self
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        Self {
+            message: Some(message.to_string()),
+            ..Self::new(kind, error)
         }
     }
 
@@ -64,8 +92,40 @@ impl IpcError {
     pub fn get_ref(&self) -> &(dyn Error + 'static) {
         self.error.as_ref()
     }
+
+    /// Checks if source error is of type `E`.
+    pub fn is<E: Error + 'static>(&self) -> bool {
+        self.error.is::<E>()
+    }
+
+    /// Returns reference to source error, if it's of type `E`, e.g.
+    /// `error.downcast_ref::<RedisError>()`.
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        self.error.downcast_ref::<E>()
+    }
+
+    /// Returns code of redis error, which caused this error (e.g. `LOADING` or `READONLY`), so
+    /// temporary server states may be handled differently than other failures.
+    #[cfg(feature = "streams")]
+    pub fn redis_code(&self) -> Option<&str> {
+        self.downcast_ref::<RedisError>().and_then(RedisError::code)
+    }
+}
+
+/// Source of [`IpcError`](IpcError) converted from [`PoisonError`](PoisonError), which can't be
+/// kept itself, because it holds lock guard.
+#[derive(Debug)]
+pub struct PoisonedLockError;
+
+impl fmt::Display for PoisonedLockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cannot access guard.")
+    }
 }
 
+impl Error for PoisonedLockError {}
+
+#[cfg(feature = "streams")]
 impl From<RedisError> for IpcError {
     fn from(error: RedisError) -> Self {
         IpcError::new(IpcErrorKind::ConnectionFailure, error)
@@ -78,6 +138,7 @@ impl From<SerdeJsonError> for IpcError {
     }
 }
 
+#[cfg(feature = "streams")]
 impl From<R2d2Error> for IpcError {
     fn from(error: R2d2Error) -> Self {
         IpcError::new(IpcErrorKind::ConnectionFailure, error)
@@ -90,16 +151,23 @@ impl From<SystemTimeError> for IpcError {
     }
 }
 
-/// Converts [`PoisonError`](PoisonError) into [`IpcError`](IpcError). Error content is dropped.
+/// Converts [`PoisonError`](PoisonError) into [`IpcError`](IpcError) with [`PoisonedLockError`]
+/// source. Error content is dropped.
 impl<T> From<PoisonError<T>> for IpcError {
     fn from(_: PoisonError<T>) -> Self {
-        IpcError::new(IpcErrorKind::MemoryAccessError, "Cannot access guard.")
+        IpcError::new(IpcErrorKind::MemoryAccessError, PoisonedLockError)
     }
 }
 
-/// Converts [`io::Error`](IoError) into [`IpcError`](IpcError) and map its kind.
+/// Converts [`io::Error`](IoError) into [`IpcError`](IpcError) and map its kind. [`IpcError`]
+/// wrapped in [`io::Error`](IoError) is returned unchanged.
 impl From<IoError> for IpcError {
     fn from(error: IoError) -> Self {
+        let error = match error.downcast::<IpcError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+
         match error.kind() {
             IoErrorKind::InvalidData | IoErrorKind::InvalidInput => {
                 IpcError::new(IpcErrorKind::InvalidData, error)
@@ -115,9 +183,46 @@ impl From<IoError> for IpcError {
     }
 }
 
+/// Converts [`IpcError`](IpcError) into [`io::Error`](IoError) with matching kind, e.g. for
+/// code using `?` in functions returning `io::Result`. [`IpcError`] is kept as source, so it may
+/// be recovered with [`io::Error::into_inner()`](IoError::into_inner) and downcasting.
+impl From<IpcError> for IoError {
+    fn from(error: IpcError) -> Self {
+        let kind = match error.kind() {
+            IpcErrorKind::ConnectionFailure => IoErrorKind::ConnectionRefused,
+            IpcErrorKind::Timeout => IoErrorKind::TimedOut,
+            IpcErrorKind::InvalidData => IoErrorKind::InvalidData,
+            IpcErrorKind::Conflict => IoErrorKind::AlreadyExists,
+            IpcErrorKind::NotFound => IoErrorKind::NotFound,
+            IpcErrorKind::Cancelled => IoErrorKind::Interrupted,
+            IpcErrorKind::MemoryAccessError => IoErrorKind::Deadlock,
+            IpcErrorKind::OtherIoError | IpcErrorKind::Other => IoErrorKind::Other,
+        };
+
+        IoError::new(kind, error)
+    }
+}
+
+/// Converts [`anyhow::Error`] into [`IpcError`](IpcError). Wrapped [`IpcError`] is returned
+/// unchanged, other errors get [`IpcErrorKind::Other`] kind.
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for IpcError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<IpcError>() {
+            Ok(error) => error,
+            Err(error) => {
+                IpcError::new(IpcErrorKind::Other, Box::<dyn Error + Send + Sync>::from(error))
+            }
+        }
+    }
+}
+
 impl fmt::Display for IpcError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "IpcError: {}", self.error.to_string())
+        match &self.message {
+            Some(message) => write!(f, "IpcError: {} ({})", message, self.error),
+            None => write!(f, "IpcError: {}", self.error),
+        }
     }
 }
 
@@ -127,3 +232,64 @@ impl Error for IpcError {
         Some(self.error.as_ref())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_error_conversion_keeps_kind_and_source() {
+        let io_error = IoError::from(IpcError::new(IpcErrorKind::Timeout, "Request timed out."));
+        assert_eq!(io_error.kind(), IoErrorKind::TimedOut);
+
+        let error = IpcError::from(io_error);
+        assert!(matches!(error.kind(), IpcErrorKind::Timeout));
+        assert_eq!(error.to_string(), "IpcError: Request timed out.");
+
+        let error = IpcError::from(IoError::new(IoErrorKind::InvalidInput, "invalid"));
+        assert!(matches!(error.kind(), IpcErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn source_error_is_downcast() {
+        let error = IpcError::from(PoisonError::new(()));
+        assert!(error.is::<PoisonedLockError>());
+
+        let time = std::time::UNIX_EPOCH.duration_since(std::time::SystemTime::now());
+        let error = IpcError::from(time.unwrap_err());
+        assert!(error.downcast_ref::<SystemTimeError>().is_some());
+        assert!(error.downcast_ref::<IoError>().is_none());
+
+        let json = serde_json::from_str::<u32>("a").unwrap_err();
+        let error = IpcError::with_source(IpcErrorKind::InvalidData, "Cannot parse.", json);
+        assert!(error.downcast_ref::<SerdeJsonError>().is_some());
+        assert!(error.to_string().starts_with("IpcError: Cannot parse. ("));
+    }
+
+    #[cfg(feature = "streams")]
+    #[test]
+    fn redis_error_code_is_returned() {
+        let error = IpcError::from(RedisError::from((
+            redis::ErrorKind::BusyLoadingError,
+            "Loading",
+            "Redis is loading the dataset in memory".to_string(),
+        )));
+        assert_eq!(error.redis_code(), Some("LOADING"));
+
+        let error = IpcError::new(IpcErrorKind::Other, "not redis");
+        assert_eq!(error.redis_code(), None);
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn anyhow_error_conversion_unwraps_ipc_error() {
+        let error = IpcError::from(anyhow::Error::from(IpcError::new(
+            IpcErrorKind::NotFound,
+            "Stream doesn't exist.",
+        )));
+        assert!(matches!(error.kind(), IpcErrorKind::NotFound));
+
+        let error = IpcError::from(anyhow::anyhow!("failed"));
+        assert!(matches!(error.kind(), IpcErrorKind::Other));
+    }
+}
@@ -1,9 +1,17 @@
 //! Module provides some helper functions, which may be useful when building ipc.
 
-use crate::{RedisPool};
+use crate::error::{IpcError, IpcErrorKind};
+use crate::{RedisConnection, RedisPool};
 use r2d2::Pool;
 use redis::Client;
+#[cfg(feature = "aio")]
+use redis::io::tcp::{socket2::TcpKeepalive, TcpSettings};
+#[cfg(feature = "redis")]
+use std::collections::hash_map::RandomState;
 use std::error::Error;
+#[cfg(feature = "redis")]
+use std::hash::BuildHasher;
+use std::time;
 
 /// Creates [`RedisPool`](RedisPool) using given url.
 ///
@@ -32,3 +40,254 @@ pub fn connect(redis_url: String) -> Result<RedisPool, Box<dyn Error>> {
     Ok(pool)
 }
 
+/// Same as [`connect()`](connect), but applies given connection options.
+///
+/// Redis client doesn't expose sockets of sync connections, so TCP keepalive and `TCP_NODELAY`
+/// can't be set on them and options, which set them, are rejected with an error instead of
+/// being silently ignored. Connect timeout limits time [`Pool::get()`](r2d2::Pool::get) waits
+/// for a connection, including establishing a new one.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) with [`Other`](crate::error::IpcErrorKind::Other) kind when
+/// keepalive or `TCP_NODELAY` is set.
+///
+/// Returns [`RedisError`](redis::RedisError) when cannot connect to redis server.
+///
+/// Returns [`r2d2::Error`](r2d2::Error) when pool creation fails.
+pub fn connect_with_options(
+    redis_url: String,
+    options: &ConnectionOptions,
+) -> Result<RedisPool, Box<dyn Error>> {
+    if options.keepalive.is_some() || options.nodelay == Some(true) {
+        return Err(Box::new(IpcError::new(
+            IpcErrorKind::Other,
+            "TCP keepalive and nodelay can't be set on sync connections.",
+        )));
+    }
+
+    let client = Client::open(redis_url)?;
+
+    let mut builder = Pool::builder();
+    if let Some(timeout) = options.connect_timeout {
+        builder = builder.connection_timeout(timeout);
+    }
+
+    Ok(builder.build(client)?)
+}
+
+/// Creates [`AsyncRedisConnection`](crate::AsyncRedisConnection) using given url.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when cannot connect to redis server.
+#[cfg(feature = "aio")]
+pub async fn connect_async(redis_url: &str) -> Result<crate::AsyncRedisConnection, IpcError> {
+    let client = Client::open(redis_url)?;
+    Ok(crate::rt::connect(&client).await?)
+}
+
+/// Creates [`AsyncRedisPool`](crate::AsyncRedisPool) with `size` connections using given url.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when cannot connect to redis server.
+#[cfg(feature = "aio")]
+pub async fn connect_async_pool(
+    redis_url: &str,
+    size: usize,
+) -> Result<crate::AsyncRedisPool, IpcError> {
+    let client = Client::open(redis_url)?;
+    crate::pool::MultiplexedPool::new(&client, size).await
+}
+
+/// Same as [`connect_async()`](connect_async), but applies given connection options.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when cannot connect to redis server or connect timeout
+/// elapses.
+#[cfg(feature = "aio")]
+pub async fn connect_async_with_options(
+    redis_url: &str,
+    options: &ConnectionOptions,
+) -> Result<crate::AsyncRedisConnection, IpcError> {
+    let client = Client::open(redis_url)?;
+    Ok(crate::rt::connect_with_config(&client, &options.async_config()).await?)
+}
+
+/// Same as [`connect_async_pool()`](connect_async_pool), but applies given connection options
+/// to every connection.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when cannot connect to redis server or connect timeout
+/// elapses.
+#[cfg(feature = "aio")]
+pub async fn connect_async_pool_with_options(
+    redis_url: &str,
+    size: usize,
+    options: &ConnectionOptions,
+) -> Result<crate::AsyncRedisPool, IpcError> {
+    let client = Client::open(redis_url)?;
+    crate::pool::MultiplexedPool::connect_with_config(&client, size, &options.async_config()).await
+}
+
+/// Socket settings of redis connections, see [`connect_with_options()`](connect_with_options)
+/// and [`connect_async_with_options()`](connect_async_with_options). Settings, which aren't set,
+/// use redis client defaults.
+///
+/// Long-idle connections (e.g. of blocking consumers) behind NAT or load balancer may be silently
+/// dropped, so blocking reads on them hang forever. TCP keepalive detects such connections.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConnectionOptions {
+    /// Idle time before the first TCP keepalive probe is sent
+    keepalive: Option<time::Duration>,
+    /// Value of `TCP_NODELAY` option
+    nodelay: Option<bool>,
+    /// Max time of establishing connection
+    connect_timeout: Option<time::Duration>,
+}
+
+impl ConnectionOptions {
+    /// Creates options with redis client defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables TCP keepalive, first probe is sent after connection is idle for `idle`.
+    ///
+    /// It's supported only by async connections, [`connect_with_options()`](connect_with_options)
+    /// returns error, when it's set.
+    pub fn with_keepalive(mut self, idle: time::Duration) -> Self {
+        self.keepalive = Some(idle);
+        self
+    }
+
+    /// Sets `TCP_NODELAY` option, which disables Nagle's algorithm.
+    ///
+    /// Sync connections always have it disabled, so
+    /// [`connect_with_options()`](connect_with_options) returns error, when it's set to `true`.
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Sets max time of establishing connection. For sync pools it's max time of getting
+    /// connection from the pool, see [`connect_with_options()`](connect_with_options).
+    pub fn with_connect_timeout(mut self, timeout: time::Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Builds redis config of async connections.
+    #[cfg(feature = "aio")]
+    fn async_config(&self) -> redis::AsyncConnectionConfig {
+        let mut tcp = TcpSettings::default();
+        if let Some(nodelay) = self.nodelay {
+            tcp = tcp.set_nodelay(nodelay);
+        }
+        if let Some(idle) = self.keepalive {
+            tcp = tcp.set_keepalive(TcpKeepalive::new().with_time(idle));
+        }
+
+        let config = redis::AsyncConnectionConfig::new().set_tcp_settings(tcp);
+
+        match self.connect_timeout {
+            Some(timeout) => config.set_connection_timeout(timeout),
+            None => config,
+        }
+    }
+}
+
+/// Eagerly establishes `n` connections in the pool (limited by pool max size) and checks them
+/// with `PING`, so the first burst of traffic doesn't pay connection-establishment latency.
+/// Authentication and database selection configured in redis url are performed while
+/// connecting.
+///
+/// Returns number of warmed up connections.
+///
+/// # Errors
+///
+/// Returns [`IpcError`](IpcError) when connection can't be established or doesn't respond.
+pub fn warm_up(pool: &RedisPool, n: u32) -> Result<u32, IpcError> {
+    let n = n.min(pool.max_size());
+
+    // connections are held until all are established, otherwise pool would reuse idle ones
+    let mut connections: Vec<RedisConnection> = Vec::with_capacity(n as usize);
+
+    for _ in 0..n {
+        let mut conn = pool.get()?;
+        redis::cmd("PING").exec(&mut *conn)?;
+        connections.push(conn);
+    }
+
+    Ok(n)
+}
+
+/// Returns FNV-1a hash of given value. It is used, where hash has to be stable between processes
+/// and compiler versions.
+pub(crate) fn stable_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Returns current 128 bit unix timestamp (in ms)
+#[cfg(feature = "redis")]
+pub(crate) fn timestamp_u128_now() -> Result<u128, time::SystemTimeError> {
+    Ok(time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)?
+        .as_millis())
+}
+
+/// Returns `ttl` changed by random jitter of up to ± `percent` % (limited to 100), so elements
+/// written at the same time don't expire simultaneously.
+#[cfg(feature = "redis")]
+pub(crate) fn jittered_ttl(ttl: time::Duration, percent: u8) -> time::Duration {
+    if percent == 0 {
+        return ttl;
+    }
+
+    // every `RandomState` has different random keys, so hash of anything is random
+    let random = RandomState::new().hash_one(time::Instant::now());
+    // maps random to [-1, 1]
+    let random = random as f64 / u64::MAX as f64 * 2.0 - 1.0;
+
+    let factor = 1.0 + random * f64::from(percent.min(100)) / 100.0;
+
+    // ttl is kept if jittered one overflows
+    time::Duration::try_from_secs_f64(ttl.as_secs_f64() * factor).unwrap_or(ttl)
+}
+
+#[cfg(all(test, feature = "redis"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jittered_ttl_is_in_range() {
+        let ttl = time::Duration::from_secs(100);
+
+        for _ in 0..100 {
+            let jittered = jittered_ttl(ttl, 10);
+            assert!(jittered >= time::Duration::from_secs(90));
+            assert!(jittered <= time::Duration::from_secs(110));
+        }
+    }
+
+    #[test]
+    fn zero_jitter_keeps_ttl() {
+        let ttl = time::Duration::from_secs(100);
+
+        assert_eq!(jittered_ttl(ttl, 0), ttl);
+    }
+
+    #[test]
+    fn full_jitter_is_in_range() {
+        let ttl = time::Duration::from_millis(200);
+
+        for _ in 0..100 {
+            assert!(jittered_ttl(ttl, 100) <= time::Duration::from_millis(400));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Leadership leases stored in redis, which elect one of many processes to run a background
+//! task, e.g. cache refresh or recurring jobs.
+
+use crate::error::IpcError;
+use crate::RedisPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Acquires or renews leadership. Returns `1` when caller is the leader.
+const ACQUIRE_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+end
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return 1
+end
+return 0
+";
+
+/// Releases leadership, if caller is the leader.
+pub(crate) const RELEASE_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
+/// Lease of leadership stored in redis key, which holds token of the leader and expires unless
+/// it's renewed, so other process takes over when the leader dies. Leadership is released when
+/// lease is dropped.
+pub(crate) struct LeaderLease {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Leader key
+    key: String,
+    /// Unique token of this process
+    token: String,
+    /// Leadership ttl in ms
+    ttl_ms: u64,
+}
+
+impl LeaderLease {
+    /// Builds lease of given key, which expires after `ttl`.
+    pub(crate) fn new(pool: RedisPool, key: String, ttl: Duration) -> Self {
+        Self {
+            pool,
+            key,
+            token: Uuid::new_v4().to_string(),
+            ttl_ms: u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX),
+        }
+    }
+
+    /// Acquires or renews leadership. Returns `true` when caller is the leader.
+    pub(crate) fn acquire(&self) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let res = redis::Script::new(ACQUIRE_SCRIPT)
+            .key(&self.key)
+            .arg(&self.token)
+            .arg(self.ttl_ms)
+            .invoke::<u8>(&mut *conn)?;
+
+        Ok(res == 1)
+    }
+
+    /// Releases leadership, if caller is the leader.
+    pub(crate) fn release(&self) -> Result<(), IpcError> {
+        let mut conn = self.pool.get()?;
+
+        redis::Script::new(RELEASE_SCRIPT)
+            .key(&self.key)
+            .arg(&self.token)
+            .invoke::<u8>(&mut *conn)?;
+
+        Ok(())
+    }
+}
+
+impl Drop for LeaderLease {
+    fn drop(&mut self) {
+        let _ = self.release();
+    }
+}
@@ -3,29 +3,103 @@
 //! are destined to be used in inter-process or service-to-service communication.
 
 
+#[cfg(feature = "redis")]
+pub mod backfill;
+#[cfg(feature = "streams")]
+pub mod background;
+pub mod batch;
+#[cfg(feature = "http-bridge")]
+pub mod bridge;
+#[cfg(feature = "redis")]
 pub mod cache;
+#[cfg(feature = "streams")]
+pub mod cancel;
+pub mod channel;
+pub mod codec;
+pub mod deps;
+#[cfg(feature = "redis")]
+pub mod dlq;
+#[cfg(feature = "redis")]
+mod lease;
+#[cfg(feature = "redis")]
+pub mod notify;
+#[cfg(feature = "aio")]
+pub mod pool;
+#[cfg(feature = "aio")]
+pub mod pubsub;
+#[cfg(feature = "redis")]
 pub mod queue;
+#[cfg(feature = "streams")]
+pub mod reconnect;
+pub mod redact;
+#[cfg(feature = "streams")]
+pub mod scope;
+#[cfg(feature = "streams")]
+pub mod settings;
+#[cfg(feature = "streams")]
+pub mod shard;
+#[cfg(feature = "redis")]
+pub mod spill;
+#[cfg(feature = "streams")]
+pub mod status;
+#[cfg(feature = "streams")]
 pub mod stream;
+#[cfg(feature = "streams")]
+pub mod watchdog;
+#[cfg(feature = "streams")]
 pub mod helpers;
 pub mod error;
+pub mod message;
+#[cfg(feature = "aio")]
+mod rt;
+pub mod schema;
 
 
+#[cfg(feature = "streams")]
 use r2d2::{Pool, PooledConnection};
+#[cfg(feature = "streams")]
 use redis::Client;
 use std::time::Duration;
 
 // re-exports:
 /// Simple cache, based on redis hash. May be used by multiple processes.
-pub use cache::Cache;
+#[cfg(feature = "redis")]
+pub use cache::{Cache, ReadOnlyCache, WriteCache};
+/// Async cache.
+#[cfg(feature = "aio")]
+pub use cache::AsyncCache;
 /// Task queue. Contains read and write variants. Based on redis list.
+#[cfg(feature = "redis")]
 pub use queue::{ReadQueue, WriteQueue};
+/// Async task queue.
+#[cfg(feature = "aio")]
+pub use queue::{AsyncReadQueue, AsyncWriteQueue};
 /// Event stream based on redis streams.
-pub use stream::{ReadStream, WriteStream};
+#[cfg(feature = "streams")]
+pub use stream::{GroupReadStream, MultiReadStream, ReadStream, WriteStream};
+/// Async event stream.
+#[cfg(feature = "aio")]
+pub use stream::{AsyncReadStream, AsyncWriteStream};
+/// Async adaptor of sync event stream.
+#[cfg(feature = "tokio")]
+pub use stream::ReadStreamBridge;
+/// Derive macro for [`Message`](message::Message) trait.
+#[cfg(feature = "derive")]
+pub use redis_ipc_derive::IpcMessage;
 
 /// Type alias for [`Pool`](Pool) with [`Client`](Client), which is used widely in this crate.
+#[cfg(feature = "streams")]
 pub type RedisPool = Pool<Client>;
 /// Alias for connection, which may be got from pool.
+#[cfg(feature = "streams")]
 pub type RedisConnection = PooledConnection<Client>;
+/// Alias for async connection used by async structures. It is cheap to clone, clones share one
+/// socket.
+#[cfg(feature = "aio")]
+pub type AsyncRedisConnection = redis::aio::MultiplexedConnection;
+/// Alias for pool of async connections, which may be shared by async structures.
+#[cfg(feature = "aio")]
+pub type AsyncRedisPool = pool::MultiplexedPool;
 
 /// Alias for specifying timeouts in this crate.
 pub type Timeout = Duration;
@@ -0,0 +1,154 @@
+//! This module covers metadata of message types used in this crate.
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::ops::Deref;
+
+/// Metadata of message type, which is used to identify messages independently of rust type path.
+///
+/// It may be implemented manually or derived with `#[derive(IpcMessage)]` when `derive` feature
+/// is enabled.
+///
+/// # Examples
+/// ```
+/// use redis_ipc::message::Message;
+///
+/// struct OrderCreated {
+///     id: u64,
+/// }
+///
+/// impl Message for OrderCreated {
+///     const TYPE_NAME: &'static str = "order.created";
+///     const SCHEMA_VERSION: u32 = 1;
+///     const CHANNEL: &'static str = "orders";
+/// }
+///
+/// assert_eq!(OrderCreated::CHANNEL, "orders");
+/// ```
+pub trait Message {
+    /// Stable type name. It should not change when type is renamed or moved.
+    const TYPE_NAME: &'static str;
+    /// Schema version, should be bumped on incompatible changes.
+    const SCHEMA_VERSION: u32;
+    /// Default channel name (queue, stream or cache name) for this message type.
+    const CHANNEL: &'static str;
+}
+
+/// Enum message type with variants tagged by stable names, e.g. adjacently tagged serde enum
+/// used as payload of channel carrying several message types. Tags are registered, so message
+/// with tag unknown to this consumer is rejected by [`Tagged`] with error, which reports the tag.
+///
+/// # Examples
+/// ```
+/// use redis_ipc::message::{Tagged, TaggedMessage};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// #[serde(tag = "type", content = "content")]
+/// enum OrderEvent {
+///     #[serde(rename = "order.created")]
+///     Created { id: u64 },
+///     #[serde(rename = "order.cancelled")]
+///     Cancelled { id: u64 },
+/// }
+///
+/// impl TaggedMessage for OrderEvent {
+///     const TAGS: &'static [&'static str] = &["order.created", "order.cancelled"];
+/// }
+///
+/// let json = r#"{"type":"order.shipped","content":{"id":1}}"#;
+/// let error = serde_json::from_str::<Tagged<OrderEvent>>(json).err().unwrap();
+/// assert!(error.to_string().contains("order.shipped"));
+/// ```
+///
+/// Queue or stream of such messages is read as e.g. `ReadQueue<Tagged<OrderEvent>>`.
+pub trait TaggedMessage {
+    /// Name of the field, which holds tag (`tag` in serde enum attributes).
+    const TAG_FIELD: &'static str = "type";
+    /// Tags of all variants. They should not change when variants are renamed.
+    const TAGS: &'static [&'static str];
+}
+
+/// Returns tag of encoded tagged message or [`None`] if it has no tag.
+pub fn message_tag<T: TaggedMessage>(value: &Value) -> Option<&str> {
+    value.get(T::TAG_FIELD).and_then(Value::as_str)
+}
+
+/// Wrapper of [`TaggedMessage`], which is (de)serialized the same way as wrapped message, but
+/// message with missing or unregistered tag is rejected with error reporting the tag, instead of
+/// generic serde error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tagged<T>(pub T);
+
+impl<T> Tagged<T> {
+    /// Consumes wrapper and returns message.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Tagged<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Serialize> Serialize for Tagged<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: TaggedMessage + DeserializeOwned> Deserialize<'de> for Tagged<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+
+        let Some(tag) = message_tag::<T>(&value) else {
+            return Err(D::Error::custom(format!("Missing message tag `{}`.", T::TAG_FIELD)));
+        };
+
+        if !T::TAGS.contains(&tag) {
+            return Err(D::Error::custom(format!(
+                "Unknown message tag `{}`, expected one of: {}.",
+                tag,
+                T::TAGS.join(", ")
+            )));
+        }
+
+        T::deserialize(value).map(Tagged).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(tag = "type", content = "content")]
+    enum Event {
+        #[serde(rename = "created")]
+        Created(u64),
+    }
+
+    impl TaggedMessage for Event {
+        const TAGS: &'static [&'static str] = &["created"];
+    }
+
+    #[test]
+    fn decodes_registered_tags_only() {
+        let json = serde_json::to_string(&Tagged(Event::Created(1))).unwrap();
+        assert_eq!(json, r#"{"type":"created","content":1}"#);
+
+        let decoded = serde_json::from_str::<Tagged<Event>>(&json).unwrap();
+        assert_eq!(decoded.into_inner(), Event::Created(1));
+
+        let error = serde_json::from_str::<Tagged<Event>>(r#"{"type":"deleted","content":1}"#);
+        assert!(error.err().unwrap().to_string().contains("`deleted`"));
+
+        let error = serde_json::from_str::<Tagged<Event>>(r#"{"content":1}"#);
+        assert!(error.err().unwrap().to_string().contains("Missing"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Cross-process notification primitive, similar to condition variable.
+//!
+//! [`Notify::notify_one()`](Notify::notify_one) wakes one process blocked in
+//! [`Notify::wait()`](Notify::wait) and [`Notify::notify_all()`](Notify::notify_all) wakes all
+//! of them. It may be used for "data is ready" signaling without polling e.g. a cache.
+//!
+//! Waiting is based on blocking pop of redis lists: every waiter has its own list, which is
+//! pushed by `notify_all()`, and all waiters share one permit list pushed by `notify_one()`.
+
+use crate::error::{IpcError, IpcErrorKind};
+use crate::{OptionalTimeout, RedisConnection, RedisPool, Timeout};
+use redis::Commands;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[cfg(feature = "aio")]
+mod aio;
+
+#[cfg(feature = "aio")]
+pub use aio::AsyncNotify;
+
+/// Suffix of the redis list, which stores permit pushed by `notify_one()`.
+const PERMIT_SUFFIX: &str = ":permit";
+/// Suffix of the redis sorted set, which stores waiter ids scored by their deadlines.
+const WAITERS_SUFFIX: &str = ":waiters";
+/// Infix of waiter list names, `<name>:waiter:<id>`.
+const WAITER_INFIX: &str = ":waiter:";
+
+/// Stores permit, unless it is already stored.
+const NOTIFY_ONE_SCRIPT: &str = r"
+if redis.call('LLEN', KEYS[1]) == 0 then
+    redis.call('LPUSH', KEYS[1], '1')
+end
+return 1
+";
+
+/// Registers waiter `ARGV[1]` until `ARGV[2]` ms from now (`0` means forever).
+const REGISTER_SCRIPT: &str = r"
+local deadline = '+inf'
+if tonumber(ARGV[2]) > 0 then
+    local time = redis.call('TIME')
+    local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+    deadline = now + tonumber(ARGV[2])
+end
+redis.call('ZADD', KEYS[1], deadline, ARGV[1])
+return 1
+";
+
+/// Wakes all registered waiters, which didn't time out yet. `ARGV[1]` is prefix of waiter lists.
+/// Returns number of woken waiters.
+const NOTIFY_ALL_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', '(' .. now)
+local waiters = redis.call('ZRANGE', KEYS[1], 0, -1)
+for _, id in ipairs(waiters) do
+    local key = ARGV[1] .. id
+    redis.call('LPUSH', key, '1')
+    redis.call('PEXPIRE', key, 60000)
+end
+redis.call('DEL', KEYS[1])
+return #waiters
+";
+
+/// Cross-process notification primitive, see [module docs](self).
+///
+/// `notify_one()` called while nobody waits stores a single permit, so the next `wait()`
+/// returns immediately. `notify_all()` wakes only processes, which are waiting at the moment.
+#[derive(Clone)]
+pub struct Notify {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Notification name
+    name: Arc<String>,
+    /// Wait timeout, 0 if no timeout
+    timeout: Timeout,
+}
+
+impl Notify {
+    /// Creates notification with given name.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`RedisPool`](RedisPool)
+    /// * name - notification name, used as prefix of redis keys
+    /// * timeout - wait timeout or [`None`] for infinite timeout
+    pub fn new(pool: RedisPool, name: &str, timeout: OptionalTimeout) -> Self {
+        Self {
+            pool,
+            name: Arc::new(name.to_string()),
+            timeout: timeout.unwrap_or(Duration::ZERO),
+        }
+    }
+
+    /// Wakes one waiting process or stores permit for the next one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn notify_one(&self) -> Result<(), IpcError> {
+        let mut conn = self.pool.get()?;
+
+        redis::Script::new(NOTIFY_ONE_SCRIPT)
+            .key(permit_key(&self.name))
+            .invoke::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    /// Wakes all waiting processes and returns their number.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn notify_all(&self) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        Ok(redis::Script::new(NOTIFY_ALL_SCRIPT)
+            .key(waiters_key(&self.name))
+            .arg(waiter_key(&self.name, ""))
+            .invoke::<usize>(&mut *conn)?)
+    }
+
+    /// Blocks thread until process is notified.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) with [`Timeout`](IpcErrorKind::Timeout) kind when
+    /// timeout exceeds, or other kind on connection failure.
+    pub fn wait(&self) -> Result<(), IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let id = Uuid::new_v4().to_string();
+        let waiter_key = waiter_key(&self.name, &id);
+
+        redis::Script::new(REGISTER_SCRIPT)
+            .key(waiters_key(&self.name))
+            .arg(&id)
+            .arg(timeout_millis(self.timeout))
+            .invoke::<()>(&mut *conn)?;
+
+        let res = conn.brpop::<&[String], Vec<String>>(
+            &[waiter_key.clone(), permit_key(&self.name)],
+            self.timeout.as_secs_f64(),
+        );
+
+        unregister(&mut conn, &self.name, &id)?;
+
+        if res?.is_empty() {
+            return Err(timeout_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes waiter and its list.
+fn unregister(conn: &mut RedisConnection, name: &str, id: &str) -> Result<(), IpcError> {
+    redis::pipe()
+        .zrem(waiters_key(name), id)
+        .del(waiter_key(name, id))
+        .exec(&mut **conn)?;
+
+    Ok(())
+}
+
+/// Error returned when wait times out.
+fn timeout_error() -> IpcError {
+    IpcError::new(IpcErrorKind::Timeout, "Wait timed out.")
+}
+
+/// Timeout in ms, `0` means infinite timeout.
+fn timeout_millis(timeout: Timeout) -> u64 {
+    u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Name of redis list, which stores permit of given notification.
+fn permit_key(name: &str) -> String {
+    format!("{name}{PERMIT_SUFFIX}")
+}
+
+/// Name of redis sorted set, which stores waiters of given notification.
+fn waiters_key(name: &str) -> String {
+    format!("{name}{WAITERS_SUFFIX}")
+}
+
+/// Name of redis list, which wakes given waiter.
+fn waiter_key(name: &str, id: &str) -> String {
+    format!("{name}{WAITER_INFIX}{id}")
+}
@@ -0,0 +1,88 @@
+use super::{
+    permit_key, timeout_error, timeout_millis, waiter_key, waiters_key, NOTIFY_ALL_SCRIPT,
+    NOTIFY_ONE_SCRIPT, REGISTER_SCRIPT,
+};
+use crate::error::IpcError;
+use crate::{AsyncRedisConnection, OptionalTimeout, Timeout};
+use redis::AsyncCommands;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Async variant of [`Notify`](super::Notify). It uses the same redis keys, so sync and async
+/// processes may notify each other.
+///
+/// [`AsyncNotify::wait()`](AsyncNotify::wait) blocks the connection until notification arrives,
+/// so multiplexed connection used by waiting process shouldn't be shared with other structures.
+#[derive(Clone)]
+pub struct AsyncNotify {
+    /// redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    conn: AsyncRedisConnection,
+    /// Notification name
+    name: Arc<String>,
+    /// Wait timeout, 0 if no timeout
+    timeout: Timeout,
+}
+
+impl AsyncNotify {
+    /// See [`Notify::new()`](super::Notify::new).
+    pub fn new(conn: AsyncRedisConnection, name: &str, timeout: OptionalTimeout) -> Self {
+        Self {
+            conn,
+            name: Arc::new(name.to_string()),
+            timeout: timeout.unwrap_or(Duration::ZERO),
+        }
+    }
+
+    /// See [`Notify::notify_one()`](super::Notify::notify_one).
+    pub async fn notify_one(&mut self) -> Result<(), IpcError> {
+        redis::Script::new(NOTIFY_ONE_SCRIPT)
+            .key(permit_key(&self.name))
+            .invoke_async::<()>(&mut self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// See [`Notify::notify_all()`](super::Notify::notify_all).
+    pub async fn notify_all(&mut self) -> Result<usize, IpcError> {
+        Ok(redis::Script::new(NOTIFY_ALL_SCRIPT)
+            .key(waiters_key(&self.name))
+            .arg(waiter_key(&self.name, ""))
+            .invoke_async::<usize>(&mut self.conn)
+            .await?)
+    }
+
+    /// See [`Notify::wait()`](super::Notify::wait).
+    pub async fn wait(&mut self) -> Result<(), IpcError> {
+        let id = Uuid::new_v4().to_string();
+        let waiter_key = waiter_key(&self.name, &id);
+
+        redis::Script::new(REGISTER_SCRIPT)
+            .key(waiters_key(&self.name))
+            .arg(&id)
+            .arg(timeout_millis(self.timeout))
+            .invoke_async::<()>(&mut self.conn)
+            .await?;
+
+        let res = self
+            .conn
+            .brpop::<&[String], Vec<String>>(
+                &[waiter_key.clone(), permit_key(&self.name)],
+                self.timeout.as_secs_f64(),
+            )
+            .await;
+
+        redis::pipe()
+            .zrem(waiters_key(&self.name), &id)
+            .del(&waiter_key)
+            .exec_async(&mut self.conn)
+            .await?;
+
+        if res?.is_empty() {
+            return Err(timeout_error());
+        }
+
+        Ok(())
+    }
+}
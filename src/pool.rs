@@ -0,0 +1,77 @@
+//! Pool of async redis connections, see [`MultiplexedPool`].
+
+use crate::error::IpcError;
+use crate::rt;
+use crate::AsyncRedisConnection;
+use redis::{AsyncConnectionConfig, Client};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Fixed size pool of [`AsyncRedisConnection`](AsyncRedisConnection). Every connection is
+/// multiplexed, so [`MultiplexedPool::get()`](MultiplexedPool::get) hands out connections in
+/// round robin order instead of checking them out exclusively. It spreads load of many async
+/// structures over a few sockets.
+///
+/// Blocking commands (e.g. [`AsyncReadQueue::b_next()`](crate::AsyncReadQueue::b_next)) block
+/// the whole connection, so blocking readers should use a dedicated connection built with
+/// [`helpers::connect_async()`](crate::helpers::connect_async).
+///
+/// Clones share the same connections.
+#[derive(Clone)]
+pub struct MultiplexedPool {
+    /// Established connections
+    connections: Arc<[AsyncRedisConnection]>,
+    /// Index of the next connection to hand out
+    next: Arc<AtomicUsize>,
+}
+
+impl MultiplexedPool {
+    /// Establishes `size` connections (at least one) with given client.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when any connection can't be established.
+    pub async fn new(client: &Client, size: usize) -> Result<Self, IpcError> {
+        let mut connections = Vec::with_capacity(size.max(1));
+
+        for _ in 0..size.max(1) {
+            connections.push(rt::connect(client).await?);
+        }
+
+        Ok(Self {
+            connections: connections.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Same as [`MultiplexedPool::new()`](MultiplexedPool::new), but connections are
+    /// established with given config.
+    pub(crate) async fn connect_with_config(
+        client: &Client,
+        size: usize,
+        config: &AsyncConnectionConfig,
+    ) -> Result<Self, IpcError> {
+        let mut connections = Vec::with_capacity(size.max(1));
+
+        for _ in 0..size.max(1) {
+            connections.push(rt::connect_with_config(client, config).await?);
+        }
+
+        Ok(Self {
+            connections: connections.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Returns the next connection. It is cheap and never waits.
+    pub fn get(&self) -> AsyncRedisConnection {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.connections.len();
+
+        self.connections[index].clone()
+    }
+
+    /// Number of connections in the pool.
+    pub fn get_size(&self) -> usize {
+        self.connections.len()
+    }
+}
@@ -0,0 +1,154 @@
+//! Async publish/subscribe based on redis pub/sub channels.
+//!
+//! Unlike queues and streams, messages are delivered only to subscribers connected at the time
+//! of publishing and they aren't stored anywhere.
+
+use crate::channel::Channel;
+use crate::error::IpcError;
+use crate::AsyncRedisConnection;
+use futures_core::Stream;
+use redis::aio::PubSubStream;
+use redis::{AsyncCommands, Client};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Message received by [`AsyncSubscriber`].
+pub struct PubSubMessage<MessageContent> {
+    /// Name of the channel, which message was published to
+    channel: String,
+    /// Custom content
+    content: MessageContent,
+}
+
+impl<MessageContent> PubSubMessage<MessageContent> {
+    pub fn get_channel(&self) -> &str {
+        &self.channel
+    }
+
+    pub fn get_content(&self) -> &MessageContent {
+        &self.content
+    }
+}
+
+/// Publishes messages to redis pub/sub channel. Messages may be received with
+/// [`AsyncSubscriber`].
+#[derive(Clone)]
+pub struct AsyncPublisher<MessageContent: Serialize> {
+    /// redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    conn: AsyncRedisConnection,
+    /// Channel name
+    name: Arc<String>,
+    /// Phantom for message content type
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: Serialize> AsyncPublisher<MessageContent> {
+    /// Builds publisher for channel with given name.
+    ///
+    /// # Arguments
+    ///
+    /// * conn - redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection), it may be
+    ///   shared with other structures
+    /// * name - pub/sub channel name
+    pub fn new(conn: AsyncRedisConnection, name: &str) -> Self {
+        Self {
+            conn,
+            name: Arc::new(name.to_string()),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds publisher for given [`Channel`]. Channel name is used as pub/sub channel name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(conn: AsyncRedisConnection) -> Self {
+        Self::new(conn, C::NAME)
+    }
+
+    /// Publishes message and returns number of subscribers, which received it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub async fn publish(&mut self, message: &MessageContent) -> Result<usize, IpcError> {
+        let json = serde_json::to_string(message)?;
+
+        Ok(self.conn.publish::<&str, String, usize>(&self.name, json).await?)
+    }
+
+    /// Channel name getter.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Subscriber of redis pub/sub channels. It implements [`Stream`], which yields typed messages
+/// published with [`AsyncPublisher`]. Messages, which can't be deserialized, are yielded as
+/// errors.
+///
+/// Subscriber uses its own connection, because subscribed connection can't be used for other
+/// commands.
+pub struct AsyncSubscriber<MessageContent: DeserializeOwned> {
+    /// Stream of raw messages
+    messages: PubSubStream,
+    /// Phantom for message content type
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: DeserializeOwned> AsyncSubscriber<MessageContent> {
+    /// Subscribes to channels with given names.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when connection can't be established.
+    pub async fn new(client: &Client, channels: &[&str]) -> Result<Self, IpcError> {
+        let mut pubsub = client.get_async_pubsub().await?;
+
+        pubsub.subscribe(channels).await?;
+
+        Ok(Self {
+            messages: pubsub.into_on_message(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Subscribes to given [`Channel`]. Channel name is used as pub/sub channel name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when connection can't be established.
+    pub async fn for_channel<C: Channel<Message = MessageContent>>(
+        client: &Client,
+    ) -> Result<Self, IpcError> {
+        Self::new(client, &[C::NAME]).await
+    }
+}
+
+// Message content is never stored, so it doesn't have to be `Unpin`.
+impl<MessageContent: DeserializeOwned> Unpin for AsyncSubscriber<MessageContent> {}
+
+impl<MessageContent: DeserializeOwned> Stream for AsyncSubscriber<MessageContent> {
+    type Item = Result<PubSubMessage<MessageContent>, IpcError>;
+
+    /// Yields received messages. Stream ends when connection is closed.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let msg = match Pin::new(&mut self.messages).poll_next(cx) {
+            Poll::Ready(Some(msg)) => msg,
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let res = msg
+            .get_payload::<String>()
+            .map_err(IpcError::from)
+            .and_then(|payload| Ok(serde_json::from_str(&payload)?))
+            .map(|content| PubSubMessage {
+                channel: msg.get_channel_name().to_string(),
+                content,
+            });
+
+        Poll::Ready(Some(res))
+    }
+}
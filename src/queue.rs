@@ -1,64 +1,135 @@
+use crate::batch::BatchResult;
+use crate::cancel::{CancellationToken, CANCEL_CHECK_INTERVAL};
+use crate::channel::Channel;
+use crate::codec::UNIQUE_KEY_FIELD;
 use crate::error::{IpcError, IpcErrorKind};
-use crate::{OptionalTimeout, RedisPool, Timeout};
+use crate::reconnect::{is_not_delivered, Reconnect};
+use crate::settings::{resolve, Settings};
+use crate::spill::SpillBuffer;
+use crate::watchdog::Watchdog;
+use crate::{OptionalTimeout, RedisConnection, RedisPool, Timeout};
 use redis::Commands;
 use serde::de::DeserializeOwned;
-use serde::{Deserialize, Serialize};
-use serde_json::Error as SerdeJsonError;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
+use std::ops::{Bound, RangeBounds};
 use std::sync::Arc;
-use std::time::Duration;
-use uuid::Uuid;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-/// Wrapper struct for messages in [`WriteQueue`].
-#[derive(Serialize)]
-pub struct WriteQueueMessage<MessageContent: Serialize> {
-    /// Message id
-    uuid: String,
-    /// Custom content
-    content: MessageContent,
-}
+#[cfg(feature = "aio")]
+mod aio;
+mod fair;
+mod id;
+mod multi;
+mod reaper;
+mod recurring;
+mod reliable;
 
-impl<MessageContent: Serialize> WriteQueueMessage<MessageContent> {
-    pub fn new(uuid: String, content: MessageContent) -> WriteQueueMessage<MessageContent> {
-        Self { uuid, content }
-    }
+#[cfg(feature = "aio")]
+pub use aio::{AsyncReadQueue, AsyncWriteQueue};
+pub use fair::FairReadQueue;
+pub use id::{IdGenerator, IdStrategy};
+pub use multi::{MultiReadQueue, SourcedMessage};
+pub use reaper::{QueueReaper, ReaperHandle};
+pub use recurring::{InvalidJobHook, RecurringScheduler, SchedulerHandle};
+pub use reliable::ReliableReadQueue;
+pub use crate::codec::{
+    decode_queue_message as decode_message, decode_queue_message_with as decode_message_with,
+    encode_queue_message as encode_message, DecodeMode, ReadQueueMessage, WriteQueueMessage,
+};
 
-    pub fn get_uuid(&self) -> &str {
-        &self.uuid
-    }
+/// Suffix of the redis set, which stores keys of pending and in-flight unique jobs.
+const UNIQUE_SUFFIX: &str = ":unique";
 
-    pub fn get_content(&self) -> &MessageContent {
-        &self.content
-    }
-}
+/// Pushes message only if its unique key wasn't in the set yet. Returns `1` when pushed.
+const PUBLISH_UNIQUE_SCRIPT: &str = r"
+if redis.call('SADD', KEYS[2], ARGV[1]) == 1 then
+    redis.call('LPUSH', KEYS[1], ARGV[2])
+    return 1
+end
+return 0
+";
 
-/// Wrapper for messages in [`ReadQueue`].
-#[derive(Deserialize)]
-pub struct ReadQueueMessage<MessageContent> {
-    uuid: String,
-    content: MessageContent,
-}
+/// Removes queue list and releases keys of unique jobs (stored in envelope field `ARGV[1]`),
+/// which were waiting in it, and keys `ARGV[2..]` of purged messages read by the caller.
+/// Returns number of removed messages.
+const PURGE_SCRIPT: &str = r"
+local messages = redis.call('LRANGE', KEYS[1], 0, -1)
+for _, message in ipairs(messages) do
+    local ok, decoded = pcall(cjson.decode, message)
+    if ok and type(decoded) == 'table' and type(decoded[ARGV[1]]) == 'string' then
+        redis.call('SREM', KEYS[2], decoded[ARGV[1]])
+    end
+end
+for i = 2, #ARGV do
+    redis.call('SREM', KEYS[2], ARGV[i])
+end
+redis.call('DEL', KEYS[1])
+return #messages
+";
 
-impl<MessageContent: DeserializeOwned> ReadQueueMessage<MessageContent> {
-    /// Deserializes string and builds message from it.
-    ///
-    /// # Errors
-    /// Returns [`Error`](serde_json::Error) produced by [`serde_json::from_str()](serde_json::from_str)
-    pub fn from_str(message: String) -> Result<ReadQueueMessage<MessageContent>, SerdeJsonError> {
-        Ok(serde_json::from_str::<ReadQueueMessage<MessageContent>>(
-            &message,
-        )?)
-    }
+/// Suffix of the redis sorted set, which stores keys of scheduled (delayed or debounced)
+/// messages scored by due time.
+const SCHEDULED_SUFFIX: &str = ":scheduled";
+/// Suffix of the redis hash, which stores payloads of scheduled messages.
+const SCHEDULED_PAYLOADS_SUFFIX: &str = ":scheduled:payloads";
+/// Max time blocking read waits before it checks for newly scheduled messages.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+/// Shortest blocking wait, zero would block indefinitely.
+const MIN_WAIT: Duration = Duration::from_millis(10);
 
-    pub fn get_uuid(&self) -> &str {
-        &self.uuid
-    }
+/// Schedules message `ARGV[2]` ms from now (redis clock), unless its key is already scheduled,
+/// and replaces its payload.
+const SCHEDULE_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('ZADD', KEYS[1], 'NX', now + tonumber(ARGV[2]), ARGV[1])
+redis.call('HSET', KEYS[2], ARGV[1], ARGV[3])
+return 1
+";
 
-    pub fn get_content(&self) -> &MessageContent {
-        &self.content
-    }
-}
+/// Moves due time of scheduled message to `ARGV[2]` ms from now. Returns `1` when message was
+/// still scheduled.
+const RESCHEDULE_SCRIPT: &str = r"
+if not redis.call('ZSCORE', KEYS[1], ARGV[1]) then
+    return 0
+end
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('ZADD', KEYS[1], 'XX', now + tonumber(ARGV[2]), ARGV[1])
+return 1
+";
+
+/// Removes scheduled message. Returns `1` when message was still scheduled.
+const CANCEL_SCHEDULED_SCRIPT: &str = r"
+local removed = redis.call('ZREM', KEYS[1], ARGV[1])
+redis.call('HDEL', KEYS[2], ARGV[1])
+return removed
+";
+
+/// Pushes due scheduled messages to the queue. Returns ms until the next message is due or `-1`
+/// when there are no scheduled messages.
+const PROMOTE_SCHEDULED_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local due = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', now)
+for _, key in ipairs(due) do
+    local payload = redis.call('HGET', KEYS[3], key)
+    if payload then
+        redis.call('LPUSH', KEYS[1], payload)
+    end
+    redis.call('HDEL', KEYS[3], key)
+    redis.call('ZREM', KEYS[2], key)
+end
+local next = redis.call('ZRANGE', KEYS[2], 0, 0, 'WITHSCORES')
+if next[2] then
+    return tonumber(next[2]) - now
+end
+return -1
+";
 
 /// Queue dedicated for writing tasks only.
 ///
@@ -71,6 +142,10 @@ pub struct WriteQueue<MessageContent: Serialize> {
     name: Arc<String>,
     /// phantom indicating message type of queue instance
     phantom: PhantomData<MessageContent>,
+    /// local buffer for messages published while redis is unreachable
+    spill: Option<SpillBuffer>,
+    /// strategy of generating message ids
+    id_strategy: IdStrategy,
 }
 
 impl<MessageContent: Serialize> WriteQueue<MessageContent> {
@@ -85,49 +160,498 @@ impl<MessageContent: Serialize> WriteQueue<MessageContent> {
             name: Arc::new(name.to_string()),
             pool,
             phantom: PhantomData,
+            spill: None,
+            id_strategy: IdStrategy::default(),
         }
     }
 
+    /// Builds [`WriteQueue`] for given [`Channel`]. Channel name is used as queue name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(pool: RedisPool) -> Self {
+        Self::new(pool, C::NAME)
+    }
+
+    /// Sets spill buffer. When connection to redis is refused or dropped, published messages are
+    /// appended to it instead of returning error. They have to be replayed with
+    /// [`SpillBuffer::replay()`](SpillBuffer::replay) or
+    /// [`SpillBuffer::recover()`](SpillBuffer::recover). Timeouts are returned as errors, because
+    /// message may have been pushed already.
+    ///
+    /// Delivery of spilled messages is at-least-once, e.g. message may be pushed twice if replay
+    /// times out after it was pushed. Consumers should deduplicate messages by uuid when needed.
+    pub fn with_spill(mut self, spill: SpillBuffer) -> Self {
+        self.spill = Some(spill);
+        self
+    }
+
+    /// Sets strategy of generating ids of published messages. Random UUID v4 is used by
+    /// default.
+    pub fn with_id_strategy(mut self, id_strategy: IdStrategy) -> Self {
+        self.id_strategy = id_strategy;
+        self
+    }
+
     /// Publishes task to the queue. Uses queue name, which may be accessed using 
     /// `WriteQueue::get_name(&self)`
     ///
     /// # Errors
     ///
     /// Returns [`IpcError`](IpcError) on connection or decoding failure. See error docs for 
-    /// more info.
+    /// more info. When [spill buffer](WriteQueue::with_spill) is set, message is spilled instead
+    /// of returning error if connection to redis is refused or dropped.
     pub fn publish(&mut self, message_content: &MessageContent) -> Result<(), IpcError> {
-        let message = WriteQueueMessage::new(Uuid::new_v4().to_string(), message_content);
+        let message = WriteQueueMessage::new(self.id_strategy.generate(), message_content);
+
+        let json = encode_message(&message)?;
+
+        self.push(message.get_uuid(), &json)
+    }
+
+    /// Publishes many tasks to the queue with one command, so producers enqueuing hundreds of
+    /// tasks don't pay a round trip each. Tasks are read in order of `messages`. Returns id or
+    /// error of every message with its index in `messages`. Messages, which can't be encoded, are
+    /// reported as failed and the rest is published.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure, no message is published then. When
+    /// [spill buffer](WriteQueue::with_spill) is set, messages are spilled instead of returning
+    /// error if connection to redis is refused or dropped.
+    pub fn publish_many(
+        &mut self,
+        messages: &[MessageContent],
+    ) -> Result<BatchResult<String>, IpcError> {
+        let mut result = BatchResult::new();
+        let mut indices = Vec::with_capacity(messages.len());
+        let mut encoded = Vec::with_capacity(messages.len());
+
+        for (index, message_content) in messages.iter().enumerate() {
+            let message = WriteQueueMessage::new(self.id_strategy.generate(), message_content);
+
+            match encode_message(&message) {
+                Ok(json) => {
+                    indices.push(index);
+                    encoded.push((message.get_uuid().to_string(), json));
+                }
+                Err(error) => result.push_failure(index, error),
+            }
+        }
+
+        if !encoded.is_empty() {
+            let pairs: Vec<(&str, &str)> = encoded
+                .iter()
+                .map(|(uuid, json)| (uuid.as_str(), json.as_str()))
+                .collect();
+            self.push_many(&pairs)?;
+        }
+
+        for (index, (uuid, _)) in indices.into_iter().zip(encoded) {
+            result.push_success(index, uuid);
+        }
+
+        Ok(result)
+    }
+
+    /// Publishes already received message to this queue. Message id and envelope fields unknown
+    /// to this crate version are preserved, so forwarding doesn't drop metadata added by newer
+    /// producers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn forward(&mut self, message: &ReadQueueMessage<MessageContent>) -> Result<(), IpcError> {
+        let message = WriteQueueMessage::new(message.get_uuid().to_string(), message.get_content())
+            .with_extra(message.get_extra().clone());
+
+        let json = encode_message(&message)?;
+
+        self.push(message.get_uuid(), &json)
+    }
+
+    /// Publishes task to the sub-queue of given tenant. Such tasks are consumed with
+    /// [`FairReadQueue`], which round-robins across tenants, so one tenant can't starve others.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn publish_for_tenant(
+        &mut self,
+        tenant: &str,
+        message_content: &MessageContent,
+    ) -> Result<(), IpcError> {
+        let message = WriteQueueMessage::new(self.id_strategy.generate(), message_content);
 
-        let json = serde_json::to_string(&message)?;
+        let json = encode_message(&message)?;
 
         let mut conn = self.pool.get()?;
 
-        conn.lpush::<&str, &str, ()>(&self.name, &json)?;
+        redis::pipe()
+            .atomic()
+            .lpush(fair::tenant_key(&self.name, tenant), &json)
+            .sadd(fair::tenants_key(&self.name), tenant)
+            .exec(&mut *conn)?;
 
         Ok(())
     }
 
+    /// Publishes unique job. Only one job with given `key` may be pending or in-flight at a time,
+    /// so publish is a no-op while previous job with the same key wasn't released with
+    /// [`ReadQueue::release_unique()`](ReadQueue::release_unique). It fits jobs like "rebuild
+    /// index for tenant X", where repeated requests should be collapsed.
+    ///
+    /// Returns `true` when job was published, `false` when job with the same key is already
+    /// pending. Unique jobs are never spilled. Keys of jobs, which weren't released (e.g. because
+    /// consumer crashed), may be listed with
+    /// [`get_unique_keys()`](WriteQueue::get_unique_keys) and cleared with
+    /// [`clear_unique()`](WriteQueue::clear_unique).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn publish_unique(
+        &mut self,
+        key: &str,
+        message_content: &MessageContent,
+    ) -> Result<bool, IpcError> {
+        let mut extra = Map::new();
+        extra.insert(UNIQUE_KEY_FIELD.to_string(), Value::String(key.to_string()));
+
+        let message = WriteQueueMessage::new(self.id_strategy.generate(), message_content)
+            .with_extra(extra);
+
+        let json = encode_message(&message)?;
+
+        let mut conn = self.pool.get()?;
+
+        let pushed = redis::Script::new(PUBLISH_UNIQUE_SCRIPT)
+            .key(self.name.as_str())
+            .key(unique_key(&self.name))
+            .arg(key)
+            .arg(&json)
+            .invoke::<u8>(&mut *conn)?;
+
+        Ok(pushed == 1)
+    }
+
+    /// Returns keys of unique jobs, which are pending or in-flight (weren't released yet).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn get_unique_keys(&self) -> Result<Vec<String>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        Ok(conn.smembers::<String, Vec<String>>(unique_key(&self.name))?)
+    }
+
+    /// Releases key of unique job without its message, e.g. when consumer crashed before it
+    /// called [`ReadQueue::release_unique()`](ReadQueue::release_unique). Pending job with this
+    /// key isn't removed, so the next job with the same key may be published next to it.
+    /// Returns `false` if key wasn't held.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn clear_unique(&self, key: &str) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let removed = conn.srem::<String, &str, u32>(unique_key(&self.name), key)?;
+
+        Ok(removed > 0)
+    }
+
+    /// Publishes message, which is delivered after `delay`. Returns message uuid, which may be
+    /// used to [cancel](WriteQueue::cancel_scheduled) or [reschedule](WriteQueue::reschedule)
+    /// it before delivery.
+    ///
+    /// Scheduled messages are delivered only by readers with
+    /// [`ReadQueue::with_scheduled_delivery()`](ReadQueue::with_scheduled_delivery) enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn publish_delayed(
+        &mut self,
+        message_content: &MessageContent,
+        delay: Duration,
+    ) -> Result<String, IpcError> {
+        let uuid = self.id_strategy.generate();
+
+        self.schedule(&uuid, &uuid, message_content, delay)?;
+
+        Ok(uuid)
+    }
+
+    /// Publishes message, which is delivered at given time, see
+    /// [`WriteQueue::publish_delayed()`](WriteQueue::publish_delayed). Delay is computed with
+    /// local clock, time in the past delivers message on the next read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn publish_at(
+        &mut self,
+        message_content: &MessageContent,
+        at: SystemTime,
+    ) -> Result<String, IpcError> {
+        let delay = at.duration_since(SystemTime::now()).unwrap_or_default();
+
+        self.publish_delayed(message_content, delay)
+    }
+
+    /// Publishes message delayed by `window`. Repeated publishes with the same `key` within the
+    /// window collapse into one message with the latest content, which is delivered when window
+    /// started by the first publish passes. It saves consumers from bursts of e.g. rebuild
+    /// requests triggered by change events.
+    ///
+    /// Debounced messages are delivered only by readers with
+    /// [`ReadQueue::with_scheduled_delivery()`](ReadQueue::with_scheduled_delivery) enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn publish_debounced(
+        &mut self,
+        key: &str,
+        message_content: &MessageContent,
+        window: Duration,
+    ) -> Result<(), IpcError> {
+        self.schedule(key, &self.id_strategy.generate(), message_content, window)
+    }
+
+    /// Cancels scheduled message with given uuid (or debounce key). Returns `false` when message
+    /// was already delivered or doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn cancel_scheduled(&self, uuid: &str) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let removed = redis::Script::new(CANCEL_SCHEDULED_SCRIPT)
+            .key(scheduled_key(&self.name))
+            .key(scheduled_payloads_key(&self.name))
+            .arg(uuid)
+            .invoke::<u8>(&mut *conn)?;
+
+        Ok(removed == 1)
+    }
+
+    /// Moves delivery of scheduled message with given uuid (or debounce key) to `new_delay` from
+    /// now. Returns `false` when message was already delivered or doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn reschedule(&self, uuid: &str, new_delay: Duration) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let rescheduled = redis::Script::new(RESCHEDULE_SCRIPT)
+            .key(scheduled_key(&self.name))
+            .arg(uuid)
+            .arg(u64::try_from(new_delay.as_millis()).unwrap_or(u64::MAX))
+            .invoke::<u8>(&mut *conn)?;
+
+        Ok(rescheduled == 1)
+    }
+
+    /// Returns number of scheduled messages waiting for delivery.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn scheduled_len(&self) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        Ok(conn.zcard::<String, usize>(scheduled_key(&self.name))?)
+    }
+
+    /// Returns due time of the earliest scheduled message or [`None`] if there are no scheduled
+    /// messages. Due time, which is far in the past, means that no reader with
+    /// [scheduled delivery](ReadQueue::with_scheduled_delivery) is running.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn next_due(&self) -> Result<Option<SystemTime>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let next = conn.zrange_withscores::<String, Vec<(String, f64)>>(
+            scheduled_key(&self.name),
+            0,
+            0,
+        )?;
+
+        Ok(next.first().map(|(_, score)| due_time(*score)))
+    }
+
+    /// Returns uuids (or debounce keys) and due times of scheduled messages, which are due in
+    /// given time `range`, ordered by due time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn list_scheduled(
+        &self,
+        range: impl RangeBounds<SystemTime>,
+    ) -> Result<Vec<(String, SystemTime)>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let scheduled = conn.zrangebyscore_withscores::<String, String, String, Vec<(String, f64)>>(
+            scheduled_key(&self.name),
+            score_bound(range.start_bound(), "-inf"),
+            score_bound(range.end_bound(), "+inf"),
+        )?;
+
+        Ok(scheduled
+            .into_iter()
+            .map(|(key, score)| (key, due_time(score)))
+            .collect())
+    }
+
+    /// Returns number of messages waiting in the queue. Scheduled messages aren't counted, see
+    /// [`WriteQueue::scheduled_len()`](WriteQueue::scheduled_len).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn len(&self) -> Result<usize, IpcError> {
+        queue_len(&self.pool, &self.name)
+    }
+
+    /// Checks if there are no messages waiting in the queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn is_empty(&self) -> Result<bool, IpcError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Removes all messages waiting in the queue. Returns number of removed messages. Keys of
+    /// removed unique jobs are released, so they may be published again. Scheduled messages
+    /// are kept and delivered when they're due.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn purge(&self) -> Result<usize, IpcError> {
+        purge_queue(&self.pool, &self.name, &[])
+    }
+
     /// Queue name getter.
     pub fn get_name(&self) -> &str {
         &self.name
     }
+
+    /// Schedules message under given key `delay` from now, unless the key is already scheduled.
+    /// Payload of scheduled message is replaced.
+    fn schedule(
+        &self,
+        key: &str,
+        uuid: &str,
+        message_content: &MessageContent,
+        delay: Duration,
+    ) -> Result<(), IpcError> {
+        let message = WriteQueueMessage::new(uuid.to_string(), message_content);
+
+        let json = encode_message(&message)?;
+
+        let mut conn = self.pool.get()?;
+
+        redis::Script::new(SCHEDULE_SCRIPT)
+            .key(scheduled_key(&self.name))
+            .key(scheduled_payloads_key(&self.name))
+            .arg(key)
+            .arg(u64::try_from(delay.as_millis()).unwrap_or(u64::MAX))
+            .arg(&json)
+            .invoke::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    /// Pushes encoded message to the queue or spills it, when it surely wasn't delivered and spill
+    /// buffer is set.
+    fn push(&self, uuid: &str, json: &str) -> Result<(), IpcError> {
+        self.push_many(&[(uuid, json)])
+    }
+
+    /// Pushes encoded messages (uuid and payload pairs) to the queue with one command or spills
+    /// them, when they surely weren't delivered and spill buffer is set.
+    fn push_many(&self, messages: &[(&str, &str)]) -> Result<(), IpcError> {
+        let payloads: Vec<&str> = messages.iter().map(|(_, json)| *json).collect();
+
+        let error = match self.pool.get() {
+            Ok(mut conn) => match conn.lpush::<&str, Vec<&str>, ()>(&self.name, payloads) {
+                Ok(()) => return Ok(()),
+                Err(error) if is_not_delivered(&error) => IpcError::from(error),
+                Err(error) => return Err(error.into()),
+            },
+            // pool fails only when connection can't be established
+            Err(error) => IpcError::from(error),
+        };
+
+        match &self.spill {
+            Some(spill) => messages
+                .iter()
+                .try_for_each(|(uuid, json)| spill.append(&self.name, uuid, json)),
+            None => Err(error),
+        }
+    }
 }
 
 /// Read only task queue. It is based on redis list.
 ///
 /// For writing use [`WriteQueue`]
-#[derive(Clone)]
 pub struct ReadQueue<MessageContent: DeserializeOwned> {
     /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
     pool: RedisPool,
+    /// pool used for blocking reads, if it's [`None`] `pool` is used
+    blocking_pool: Option<RedisPool>,
     /// blocking requests timeout
     timeout: Timeout,
     /// queue name
     name: Arc<String>,
+    /// max number of messages fetched with one request, `0` and `1` disable prefetch
+    prefetch: usize,
+    /// prefetched raw messages, in consumption order
+    prefetched: VecDeque<String>,
+    /// token, which interrupts blocking reads
+    cancellation: Option<CancellationToken>,
+    /// whether reads deliver due scheduled messages
+    scheduled_delivery: bool,
+    /// watchdog, which tracks blocking reads
+    watchdog: Option<Watchdog>,
+    /// reconnection policy of blocking reads
+    reconnect: Option<Reconnect>,
+    /// handling of unknown content fields
+    decode_mode: DecodeMode,
+    /// settings tuned at runtime
+    settings: Option<Settings>,
     /// phantom indicating message type of queue instance
     phantom: PhantomData<MessageContent>,
 }
 
+// Implemented manually, clones must not share prefetched messages.
+impl<MessageContent: DeserializeOwned> Clone for ReadQueue<MessageContent> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            blocking_pool: self.blocking_pool.clone(),
+            timeout: self.timeout,
+            name: Arc::clone(&self.name),
+            prefetch: self.prefetch,
+            prefetched: VecDeque::new(),
+            cancellation: self.cancellation.clone(),
+            scheduled_delivery: self.scheduled_delivery,
+            watchdog: self.watchdog.clone(),
+            reconnect: self.reconnect.clone(),
+            decode_mode: self.decode_mode,
+            settings: self.settings.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
 impl<MessageContent: DeserializeOwned> ReadQueue<MessageContent> {
     /// Builds a queue with given timeout and name.
     ///
@@ -143,31 +667,248 @@ impl<MessageContent: DeserializeOwned> ReadQueue<MessageContent> {
         Self {
             name: Arc::new(name.to_string()),
             pool,
+            blocking_pool: None,
             timeout,
+            prefetch: 0,
+            prefetched: VecDeque::new(),
+            cancellation: None,
+            scheduled_delivery: false,
+            watchdog: None,
+            reconnect: None,
+            decode_mode: DecodeMode::default(),
+            settings: None,
             phantom: PhantomData,
         }
     }
 
+    /// Builds [`ReadQueue`] for given [`Channel`]. Channel name is used as queue name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        pool: RedisPool,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(pool, C::NAME, timeout)
+    }
+
+    /// Sets separate pool for blocking reads ([`ReadQueue::b_next()`](ReadQueue::b_next)), so
+    /// long blocking requests can't starve short commands sharing the main pool.
+    pub fn with_blocking_pool(mut self, blocking_pool: RedisPool) -> Self {
+        self.blocking_pool = Some(blocking_pool);
+        self
+    }
+
+    /// Sets token, which interrupts blocking reads. When it is cancelled,
+    /// [`ReadQueue::b_next()`](ReadQueue::b_next) returns error with
+    /// [`Cancelled`](IpcErrorKind::Cancelled) kind and iteration over queue ends.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets watchdog, which reports blocking reads stuck far beyond the timeout.
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Sets reconnection policy, so [`ReadQueue::b_next()`](ReadQueue::b_next) waits until redis
+    /// is reachable again instead of returning connection error.
+    pub fn with_reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = Some(reconnect);
+        self
+    }
+
+    /// Sets handling of message content fields unknown to `MessageContent`. They are ignored by
+    /// default.
+    pub fn with_decode_mode(mut self, decode_mode: DecodeMode) -> Self {
+        self.decode_mode = decode_mode;
+        self
+    }
+
+    /// Enables delivery of scheduled messages (see
+    /// [`WriteQueue::publish_delayed()`](WriteQueue::publish_delayed) and
+    /// [`WriteQueue::publish_debounced()`](WriteQueue::publish_debounced)). Reads push due
+    /// scheduled messages to the queue first and blocking reads wake up when next one is due.
+    pub fn with_scheduled_delivery(mut self, enabled: bool) -> Self {
+        self.scheduled_delivery = enabled;
+        self
+    }
+
+    /// Fetches up to `count` messages with one request and keeps them in a local buffer, which
+    /// is consumed by next reads. It improves throughput for short tasks. `0` and `1` disable
+    /// prefetch.
+    ///
+    /// Unprocessed prefetched messages are returned to the queue on drop or with
+    /// [`ReadQueue::release_prefetched()`](ReadQueue::release_prefetched). They are lost if the
+    /// process crashes.
+    pub fn with_prefetch(mut self, count: usize) -> Self {
+        self.prefetch = count;
+        self
+    }
+
+    /// Attaches [`Settings`], whose timeout and prefetch override configured ones from the next
+    /// read.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// Returns timeout of the next blocking read.
+    fn current_timeout(&self) -> Timeout {
+        resolve(self.settings.as_ref(), Settings::get_timeout, self.timeout)
+    }
+
+    /// Returns number of messages fetched by the next read.
+    fn current_prefetch(&self) -> usize {
+        resolve(self.settings.as_ref(), Settings::get_prefetch, self.prefetch)
+    }
+
+    /// Returns number of messages waiting in the queue, including messages prefetched by this
+    /// reader.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn len(&self) -> Result<usize, IpcError> {
+        Ok(queue_len(&self.pool, &self.name)? + self.prefetched.len())
+    }
+
+    /// Checks if there are no messages waiting in the queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn is_empty(&self) -> Result<bool, IpcError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns at most `count` messages, which will be read next, without removing them from
+    /// the queue. Messages are ordered as they will be read, prefetched ones first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or decoding failure.
+    pub fn peek(&self, count: usize) -> Result<Vec<ReadQueueMessage<MessageContent>>, IpcError> {
+        let mut messages: Vec<String> = self.prefetched.iter().take(count).cloned().collect();
+        let remaining = count - messages.len();
+
+        if remaining > 0 {
+            let mut conn = self.pool.get()?;
+
+            // queue is consumed from the right side, so the next messages are at its end
+            let start = -isize::try_from(remaining).unwrap_or(isize::MAX);
+            let res = conn.lrange::<&str, Vec<String>>(&self.name, start, -1)?;
+            messages.extend(res.into_iter().rev());
+        }
+
+        messages
+            .iter()
+            .map(|msg| decode_message_with(msg, self.decode_mode))
+            .collect()
+    }
+
+    /// Removes all messages waiting in the queue, including messages prefetched by this reader.
+    /// Returns number of removed messages. Same as [`WriteQueue::purge()`](WriteQueue::purge),
+    /// keys of removed unique jobs are released and scheduled messages are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure, prefetched messages are dropped
+    /// anyway.
+    pub fn purge(&mut self) -> Result<usize, IpcError> {
+        let prefetched = self.prefetched.len();
+
+        // unique keys of prefetched jobs are released together with the ones in the queue
+        let unique_keys: Vec<String> = self
+            .prefetched
+            .drain(..)
+            .filter_map(|msg| serde_json::from_str::<Value>(&msg).ok())
+            .filter_map(|msg| msg.get(UNIQUE_KEY_FIELD)?.as_str().map(str::to_string))
+            .collect();
+
+        Ok(purge_queue(&self.pool, &self.name, &unique_keys)? + prefetched)
+    }
+
+    /// Returns prefetched messages, which weren't read yet, to the queue. They keep their order
+    /// and will be read before other messages. Returns number of returned messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure, messages are kept in the buffer.
+    pub fn release_prefetched(&mut self) -> Result<usize, IpcError> {
+        if self.prefetched.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.pool.get()?;
+
+        // queue is consumed from the right side, so the first prefetched message must be pushed
+        // as the last one
+        let messages: Vec<&String> = self.prefetched.iter().rev().collect();
+        conn.rpush::<&str, Vec<&String>, ()>(&self.name, messages)?;
+
+        let released = self.prefetched.len();
+        self.prefetched.clear();
+
+        Ok(released)
+    }
+
+    /// Releases key of unique job (see
+    /// [`WriteQueue::publish_unique()`](WriteQueue::publish_unique)), so job with the same key
+    /// may be published again. It should be called when job is processed or failed, otherwise
+    /// the key blocks next jobs until it's cleared with
+    /// [`WriteQueue::clear_unique()`](WriteQueue::clear_unique). Messages, which aren't unique
+    /// jobs, are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn release_unique(
+        &self,
+        message: &ReadQueueMessage<MessageContent>,
+    ) -> Result<(), IpcError> {
+        let Some(key) = message.get_unique_key() else {
+            return Ok(());
+        };
+
+        let mut conn = self.pool.get()?;
+
+        conn.srem::<String, &str, ()>(unique_key(&self.name), key)?;
+
+        Ok(())
+    }
+
     /// Returns the next message in queue or [`None`] if it was not found.
     ///
     /// # Errors
     /// Returns [`IpcError`](IpcError) when connection fails or decoding message fails. See error kind
     /// and source for more info.
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        if let Some(msg) = self.prefetched.pop_front() {
+            return Ok(Some(decode_message_with(&msg, self.decode_mode)?));
+        }
+
         let mut conn = self.pool.get()?;
 
-        let res = conn.rpop::<&str, Option<Vec<String>>>(&self.name, NonZeroUsize::new(1))?;
+        if self.scheduled_delivery {
+            promote_scheduled(&mut conn, &self.name)?;
+        }
+
+        let count = NonZeroUsize::new(self.current_prefetch().max(1));
+        let res = conn.rpop::<&str, Option<Vec<String>>>(&self.name, count)?;
 
         Ok(
             if let Some(res) = res {
-                // redis successful result contains array with strings, we requested only one message,
-                // so it should be an array of size 1
-                let msg = res.get(0).cloned().ok_or(IpcError::new(
+                // redis successful result contains array with at least one string, messages
+                // following the first one are prefetched
+                let mut res = res.into_iter();
+                let msg = res.next().ok_or(IpcError::new(
                     IpcErrorKind::InvalidData,
                     "Invalid redis message.",
                 ))?;
+                self.prefetched.extend(res);
 
-                Some(ReadQueueMessage::from_str(msg)?)
+                Some(decode_message_with(&msg, self.decode_mode)?)
             } else {
                 // None response indicates no message, but successfult response
                 None
@@ -176,23 +917,102 @@ impl<MessageContent: DeserializeOwned> ReadQueue<MessageContent> {
     }
 
     /// Blocking read next message from queue. If no message is available blocks thread and waits for timeout or indefinitely.
-    /// When timeout exceeds, error is returned.
+    /// When timeout exceeds, [`None`] is returned.
     ///
     /// # Errors
     ///
     /// Returns [`IpcError`](IpcError) on connection or parsing failure.
-    pub fn b_next(&mut self) -> Result<ReadQueueMessage<MessageContent>, IpcError> {
-        let mut conn = self.pool.get()?;
+    pub fn b_next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        if let Some(msg) = self.prefetched.pop_front() {
+            return Ok(Some(decode_message_with(&msg, self.decode_mode)?));
+        }
 
-        // return type of redis blocking pop is ["queue_name", "queue_elem"], br_pop takes timeout in float (seconds) 0.0 timeout is infinite
-        let res = conn.brpop::<&str, Vec<String>>(&self.name, self.timeout.as_secs_f64())?;
+        // call is tracked until guard is dropped
+        let _watch = self
+            .watchdog
+            .as_ref()
+            .map(|watchdog| watchdog.watch(&self.name, self.current_timeout()));
 
-        let msg = res.get(1).cloned().ok_or(IpcError::new(
-            IpcErrorKind::InvalidData,
-            "Invalid redis message.",
-        ))?;
+        let msg = match self.reconnect.clone() {
+            Some(reconnect) => {
+                let name = Arc::clone(&self.name);
+                let cancellation = self.cancellation.clone();
 
-        Ok(ReadQueueMessage::from_str(msg)?)
+                reconnect.run(&name, cancellation.as_ref(), || self.b_pop())?
+            }
+            None => self.b_pop()?,
+        };
+
+        msg.map(|msg| decode_message_with(&msg, self.decode_mode)).transpose()
+    }
+
+    /// Pops (blocking) raw message and prefetches next ones. Returns [`None`] on timeout.
+    fn b_pop(&mut self) -> Result<Option<String>, IpcError> {
+        let mut conn = self.blocking_pool.as_ref().unwrap_or(&self.pool).get()?;
+
+        let msg = if self.cancellation.is_some() || self.scheduled_delivery {
+            self.sliced_brpop(&mut conn)?
+        } else {
+            brpop(&mut conn, &self.name, self.current_timeout())?
+        };
+
+        let Some(msg) = msg else {
+            return Ok(None);
+        };
+
+        if let Some(count) = NonZeroUsize::new(self.current_prefetch().saturating_sub(1)) {
+            // message is already popped, so failed prefetch is ignored to not lose it
+            if let Ok(Some(res)) = conn.rpop::<&str, Option<Vec<String>>>(&self.name, Some(count)) {
+                self.prefetched.extend(res);
+            }
+        }
+
+        Ok(Some(msg))
+    }
+
+    /// Blocking pop split into short waits, so cancellation is checked and due scheduled
+    /// messages are delivered between them. Returns [`None`] on timeout.
+    fn sliced_brpop(&self, conn: &mut RedisConnection) -> Result<Option<String>, IpcError> {
+        let start_time = Instant::now();
+        let timeout = self.current_timeout();
+
+        loop {
+            // max wait of this slice, `None` means infinite wait
+            let mut wait = None;
+
+            if let Some(token) = &self.cancellation {
+                token.check()?;
+                wait = Some(CANCEL_CHECK_INTERVAL);
+            }
+
+            if self.scheduled_delivery {
+                let due = promote_scheduled(conn, &self.name)?
+                    .map_or(SCHEDULE_CHECK_INTERVAL, |due| due.min(SCHEDULE_CHECK_INTERVAL));
+                wait = Some(wait.map_or(due, |wait| wait.min(due)));
+            }
+
+            if !timeout.is_zero() {
+                let remaining = timeout.saturating_sub(start_time.elapsed());
+
+                if remaining.is_zero() {
+                    return Ok(None);
+                }
+
+                wait = Some(wait.map_or(remaining, |wait| wait.min(remaining)));
+            }
+
+            let wait = wait.map_or(Duration::ZERO, |wait| wait.max(MIN_WAIT));
+
+            if let Some(msg) = brpop(conn, &self.name, wait)? {
+                return Ok(Some(msg));
+            }
+        }
+    }
+}
+
+impl<MessageContent: DeserializeOwned> Drop for ReadQueue<MessageContent> {
+    fn drop(&mut self) {
+        let _ = self.release_prefetched();
     }
 }
 
@@ -217,13 +1037,118 @@ impl<MessageContent: DeserializeOwned> Iterator for ReadQueue<MessageContent> {
     ///  **This is a blocking method!**. Returns first message which can be read.
     ///
     /// # Warning
-    /// This method loops infinitely and will **never return [`None`]**.
+    /// This method loops infinitely and will **never return [`None`]**, unless
+    /// [cancellation token](ReadQueue::with_cancellation) is cancelled.
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let res = self.b_next();
-            if res.is_ok() {
-                return res.ok();
+            match self.b_next() {
+                Ok(Some(msg)) => return Some(msg),
+                Err(error) if matches!(error.kind(), IpcErrorKind::Cancelled) => return None,
+                Ok(None) | Err(_) => {}
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Returns length of redis list of given queue.
+fn queue_len(pool: &RedisPool, queue: &str) -> Result<usize, IpcError> {
+    let mut conn = pool.get()?;
+
+    Ok(conn.llen::<&str, usize>(queue)?)
+}
+
+/// Atomically removes redis list of given queue and releases unique keys of removed jobs and
+/// of given `unique_keys`. Returns number of removed messages.
+fn purge_queue(pool: &RedisPool, queue: &str, unique_keys: &[String]) -> Result<usize, IpcError> {
+    let mut conn = pool.get()?;
+
+    let len = redis::Script::new(PURGE_SCRIPT)
+        .key(queue)
+        .key(unique_key(queue))
+        .arg(UNIQUE_KEY_FIELD)
+        .arg(unique_keys)
+        .invoke::<usize>(&mut *conn)?;
+
+    Ok(len)
+}
+
+/// Blocking pop of one message, `timeout` of zero blocks indefinitely. Returns [`None`] on
+/// timeout.
+fn brpop(
+    conn: &mut RedisConnection,
+    queue: &str,
+    timeout: Duration,
+) -> Result<Option<String>, IpcError> {
+    // return type of redis blocking pop is ["queue_name", "queue_elem"], br_pop takes timeout in
+    // float (seconds) 0.0 timeout is infinite
+    let res = conn.brpop::<&str, Vec<String>>(queue, timeout.as_secs_f64())?;
+
+    Ok(res.into_iter().nth(1))
+}
+
+/// Pushes due scheduled messages to the queue. Returns time until the next scheduled message is
+/// due.
+fn promote_scheduled(
+    conn: &mut RedisConnection,
+    queue: &str,
+) -> Result<Option<Duration>, IpcError> {
+    let due = redis::Script::new(PROMOTE_SCHEDULED_SCRIPT)
+        .key(queue)
+        .key(scheduled_key(queue))
+        .key(scheduled_payloads_key(queue))
+        .invoke::<i64>(&mut **conn)?;
+
+    Ok(u64::try_from(due).ok().map(Duration::from_millis))
+}
+
+/// Name of redis set, which stores keys of pending unique jobs of given queue.
+fn unique_key(queue: &str) -> String {
+    format!("{queue}{UNIQUE_SUFFIX}")
+}
+
+/// Converts score of scheduled message (unix timestamp in ms) to its due time.
+fn due_time(score: f64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(score as u64)
+}
+
+/// Converts time range bound to `ZRANGEBYSCORE` bound.
+fn score_bound(bound: Bound<&SystemTime>, unbounded: &str) -> String {
+    let millis = |time: &SystemTime| {
+        time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis()
+    };
+
+    match bound {
+        Bound::Included(time) => millis(time).to_string(),
+        Bound::Excluded(time) => format!("({}", millis(time)),
+        Bound::Unbounded => unbounded.to_string(),
+    }
+}
+
+/// Name of redis sorted set, which stores due times of scheduled messages of given queue.
+fn scheduled_key(queue: &str) -> String {
+    format!("{queue}{SCHEDULED_SUFFIX}")
+}
+
+/// Name of redis hash, which stores payloads of scheduled messages of given queue.
+fn scheduled_payloads_key(queue: &str) -> String {
+    format!("{queue}{SCHEDULED_PAYLOADS_SUFFIX}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_bounds() {
+        let time = UNIX_EPOCH + Duration::from_millis(1500);
+
+        assert_eq!(score_bound(Bound::Included(&time), "-inf"), "1500");
+        assert_eq!(score_bound(Bound::Excluded(&time), "-inf"), "(1500");
+        assert_eq!(score_bound(Bound::Unbounded, "+inf"), "+inf");
+    }
+
+    #[test]
+    fn due_time_from_score() {
+        assert_eq!(due_time(1500.0), UNIX_EPOCH + Duration::from_millis(1500));
+    }
+}
@@ -0,0 +1,232 @@
+use super::{decode_message, encode_message, IdStrategy, ReadQueueMessage, WriteQueueMessage};
+use crate::channel::Channel;
+use crate::error::{IpcError, IpcErrorKind};
+use crate::{AsyncRedisConnection, OptionalTimeout, Timeout};
+use futures_core::Stream;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Pending blocking pop of the next queue message.
+type PendingPop<MessageContent> = Pin<
+    Box<dyn Future<Output = Result<Option<ReadQueueMessage<MessageContent>>, IpcError>> + Send>,
+>;
+
+/// Async variant of [`WriteQueue`](super::WriteQueue).
+///
+/// For reading use [`AsyncReadQueue`]
+#[derive(Clone)]
+pub struct AsyncWriteQueue<MessageContent: Serialize> {
+    /// redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    conn: AsyncRedisConnection,
+    /// queue name
+    name: Arc<String>,
+    /// strategy of generating message ids
+    id_strategy: IdStrategy,
+    /// phantom indicating message type of queue instance
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: Serialize> AsyncWriteQueue<MessageContent> {
+    /// Builds [`AsyncWriteQueue`] with given name
+    ///
+    /// # Arguments
+    ///
+    /// * conn - redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection), it may be
+    ///   shared with other structures
+    /// * name - queue name, will be used as redis list name
+    pub fn new(conn: AsyncRedisConnection, name: &str) -> Self {
+        Self {
+            conn,
+            name: Arc::new(name.to_string()),
+            id_strategy: IdStrategy::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds [`AsyncWriteQueue`] for given [`Channel`]. Channel name is used as queue name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(conn: AsyncRedisConnection) -> Self {
+        Self::new(conn, C::NAME)
+    }
+
+    /// See [`WriteQueue::with_id_strategy()`](super::WriteQueue::with_id_strategy).
+    pub fn with_id_strategy(mut self, id_strategy: IdStrategy) -> Self {
+        self.id_strategy = id_strategy;
+        self
+    }
+
+    /// See [`WriteQueue::publish()`](super::WriteQueue::publish).
+    pub async fn publish(&mut self, message_content: &MessageContent) -> Result<(), IpcError> {
+        let message = WriteQueueMessage::new(self.id_strategy.generate(), message_content);
+
+        self.push(&encode_message(&message)?).await
+    }
+
+    /// See [`WriteQueue::forward()`](super::WriteQueue::forward).
+    pub async fn forward(
+        &mut self,
+        message: &ReadQueueMessage<MessageContent>,
+    ) -> Result<(), IpcError> {
+        let message = WriteQueueMessage::new(message.get_uuid().to_string(), message.get_content())
+            .with_extra(message.get_extra().clone());
+
+        self.push(&encode_message(&message)?).await
+    }
+
+    /// Queue name getter.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    async fn push(&mut self, json: &str) -> Result<(), IpcError> {
+        self.conn.lpush::<&str, &str, ()>(&self.name, json).await?;
+
+        Ok(())
+    }
+}
+
+/// Async variant of [`ReadQueue`](super::ReadQueue). It implements [`Stream`], which yields
+/// results of [`AsyncReadQueue::b_next()`](AsyncReadQueue::b_next), so tasks may be processed
+/// with e.g. `StreamExt::for_each_concurrent`.
+///
+/// [`AsyncReadQueue::b_next()`](AsyncReadQueue::b_next) blocks the connection until message
+/// arrives, so multiplexed connection used by this queue shouldn't be shared with other
+/// structures.
+///
+/// For writing use [`AsyncWriteQueue`]
+pub struct AsyncReadQueue<MessageContent: DeserializeOwned> {
+    /// redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    conn: AsyncRedisConnection,
+    /// blocking requests timeout
+    timeout: Timeout,
+    /// queue name
+    name: Arc<String>,
+    /// Pop started by [`Stream::poll_next()`]
+    pending: Option<PendingPop<MessageContent>>,
+    /// phantom indicating message type of queue instance
+    phantom: PhantomData<MessageContent>,
+}
+
+// Implemented manually, pending pop can't be cloned.
+impl<MessageContent: DeserializeOwned> Clone for AsyncReadQueue<MessageContent> {
+    fn clone(&self) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            timeout: self.timeout,
+            name: Arc::clone(&self.name),
+            pending: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+// Pending pop is boxed, so it is never moved.
+impl<MessageContent: DeserializeOwned> Unpin for AsyncReadQueue<MessageContent> {}
+
+impl<MessageContent: DeserializeOwned> AsyncReadQueue<MessageContent> {
+    /// Builds a queue with given timeout and name.
+    ///
+    /// # Arguments
+    ///
+    /// * conn - dedicated redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    /// * name - queue name, will be used as redis list name
+    /// * timeout - blocking requests timeout or [`None`] for infinite timeout
+    pub fn new(conn: AsyncRedisConnection, name: &str, timeout: OptionalTimeout) -> Self {
+        // maps None as 0, because redis uses 0 as infinite timeout
+        let timeout = timeout.unwrap_or(Duration::ZERO);
+
+        Self {
+            conn,
+            timeout,
+            name: Arc::new(name.to_string()),
+            pending: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds [`AsyncReadQueue`] for given [`Channel`]. Channel name is used as queue name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        conn: AsyncRedisConnection,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(conn, C::NAME, timeout)
+    }
+
+    /// See [`ReadQueue::next()`](super::ReadQueue::next).
+    pub async fn next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        let res = self
+            .conn
+            .rpop::<&str, Option<Vec<String>>>(&self.name, NonZeroUsize::new(1))
+            .await?;
+
+        match res {
+            Some(res) => {
+                let msg = res.first().ok_or(IpcError::new(
+                    IpcErrorKind::InvalidData,
+                    "Invalid redis message.",
+                ))?;
+
+                Ok(Some(decode_message(msg)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Awaits next message from queue. Returns [`None`] when timeout exceeds, same as
+    /// [`ReadQueue::b_next()`](super::ReadQueue::b_next).
+    pub async fn b_next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        pop_next(self.conn.clone(), Arc::clone(&self.name), self.timeout).await
+    }
+}
+
+impl<MessageContent> Stream for AsyncReadQueue<MessageContent>
+where
+    MessageContent: DeserializeOwned + Send + 'static,
+{
+    type Item = Result<ReadQueueMessage<MessageContent>, IpcError>;
+
+    /// Yields results of [`AsyncReadQueue::b_next()`](AsyncReadQueue::b_next), reads are
+    /// repeated after timeout. Stream never ends.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let pending = this.pending.get_or_insert_with(|| {
+                Box::pin(pop_next(this.conn.clone(), Arc::clone(&this.name), this.timeout))
+            });
+
+            let res = match pending.as_mut().poll(cx) {
+                Poll::Ready(res) => res,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            this.pending = None;
+
+            // `None` means timeout, so the next read is started
+            if let Some(res) = res.transpose() {
+                return Poll::Ready(Some(res));
+            }
+        }
+    }
+}
+
+/// Awaits next message from queue. Returns [`None`] when timeout exceeds.
+async fn pop_next<MessageContent: DeserializeOwned>(
+    mut conn: AsyncRedisConnection,
+    name: Arc<String>,
+    timeout: Timeout,
+) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+    // reply is ["queue_name", "queue_elem"], empty on timeout
+    let res = conn
+        .brpop::<&str, Vec<String>>(&name, timeout.as_secs_f64())
+        .await?;
+
+    res.get(1).map(|msg| decode_message(msg)).transpose()
+}
@@ -0,0 +1,141 @@
+use super::{decode_message, ReadQueueMessage};
+use crate::channel::Channel;
+use crate::error::IpcError;
+use crate::{OptionalTimeout, RedisPool, Timeout};
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Suffix of the redis set, which stores tenants with pending messages.
+const TENANTS_SUFFIX: &str = ":tenants";
+/// Infix of per-tenant queue names, `<queue>:tenant:<tenant>`.
+const TENANT_INFIX: &str = ":tenant:";
+
+/// Removes tenant from the registry, if its queue is empty.
+const REMOVE_EMPTY_TENANT_SCRIPT: &str = r"
+if redis.call('LLEN', KEYS[2]) == 0 then
+    return redis.call('SREM', KEYS[1], ARGV[1])
+end
+return 0
+";
+
+/// Read queue, which consumes messages published with
+/// [`WriteQueue::publish_for_tenant()`](super::WriteQueue::publish_for_tenant). Every tenant has
+/// its own sub-queue and reads round-robin across tenants, so one tenant enqueueing many
+/// messages doesn't starve others sharing the same consumers.
+///
+/// Tenants are discovered with a registry set, tenants without pending messages are removed
+/// from it.
+pub struct FairReadQueue<MessageContent: DeserializeOwned> {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// blocking requests timeout
+    timeout: Timeout,
+    /// queue name
+    name: Arc<String>,
+    /// tenant, which was served last
+    last_tenant: Option<String>,
+    /// phantom indicating message type of queue instance
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: DeserializeOwned> FairReadQueue<MessageContent> {
+    /// Builds a queue with given timeout and name.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`r2d2::Pool`] with redis connection
+    /// * name - queue name, same as name of [`WriteQueue`](super::WriteQueue)
+    /// * timeout - blocking requests timeout or [`None`] for infinite timeout
+    pub fn new(pool: RedisPool, name: &str, timeout: OptionalTimeout) -> Self {
+        Self {
+            pool,
+            timeout: timeout.unwrap_or(Duration::ZERO),
+            name: Arc::new(name.to_string()),
+            last_tenant: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds [`FairReadQueue`] for given [`Channel`]. Channel name is used as queue name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        pool: RedisPool,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(pool, C::NAME, timeout)
+    }
+
+    /// Returns the next message of the next tenant (in round-robin order), which has pending
+    /// messages, or [`None`] if there are no messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when connection fails or decoding message fails.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let mut tenants = conn.smembers::<String, Vec<String>>(tenants_key(&self.name))?;
+        tenants.sort();
+
+        // tenants after the last served one go first
+        let split = match &self.last_tenant {
+            Some(last) => tenants.partition_point(|tenant| tenant <= last),
+            None => 0,
+        };
+        tenants.rotate_left(split);
+
+        for tenant in tenants {
+            let key = tenant_key(&self.name, &tenant);
+
+            if let Some(msg) = conn.rpop::<&str, Option<String>>(&key, None)? {
+                self.last_tenant = Some(tenant);
+                return Ok(Some(decode_message(&msg)?));
+            }
+
+            redis::Script::new(REMOVE_EMPTY_TENANT_SCRIPT)
+                .key(tenants_key(&self.name))
+                .key(&key)
+                .arg(&tenant)
+                .invoke::<u8>(&mut *conn)?;
+        }
+
+        Ok(None)
+    }
+
+    /// Blocking read of the next message, see [`FairReadQueue::next()`](FairReadQueue::next).
+    /// Waits indefinitely or returns [`None`] after timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or parsing failure.
+    pub fn b_next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        let start_time = Instant::now();
+        let sleep_duration = Duration::from_millis(50);
+
+        loop {
+            if let Some(msg) = self.next()? {
+                return Ok(Some(msg));
+            }
+
+            if !self.timeout.is_zero() && start_time.elapsed() >= self.timeout {
+                return Ok(None);
+            }
+
+            thread::sleep(sleep_duration);
+        }
+    }
+}
+
+/// Name of redis set, which stores tenants of given queue.
+pub(crate) fn tenants_key(queue: &str) -> String {
+    format!("{queue}{TENANTS_SUFFIX}")
+}
+
+/// Name of redis list, which stores messages of given tenant.
+pub(crate) fn tenant_key(queue: &str, tenant: &str) -> String {
+    format!("{queue}{TENANT_INFIX}{tenant}")
+}
@@ -0,0 +1,81 @@
+use crate::codec::ULID_ALPHABET;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// User-supplied generator of message ids.
+pub type IdGenerator = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// Strategy of generating ids of messages published to queue, see
+/// [`WriteQueue::with_id_strategy()`](super::WriteQueue::with_id_strategy).
+#[derive(Clone, Default)]
+pub enum IdStrategy {
+    /// Random UUID v4
+    #[default]
+    V4,
+    /// Time-ordered UUID v7. Ids are sorted by publish time, which makes log correlation and
+    /// dead letter triage easier.
+    V7,
+    /// Time-ordered [ULID](https://github.com/ulid/spec), which is shorter than UUID and sorted
+    /// by publish time at millisecond precision.
+    Ulid,
+    /// Ids returned by user-supplied generator. They should be unique, because they are used to
+    /// identify messages, e.g. in spill buffer deduplication.
+    Custom(IdGenerator),
+}
+
+impl IdStrategy {
+    /// Creates strategy, which uses given generator.
+    pub fn custom<F>(generator: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Self::Custom(Arc::new(generator))
+    }
+
+    /// Generates id of the next message.
+    pub fn generate(&self) -> String {
+        match self {
+            Self::V4 => Uuid::new_v4().to_string(),
+            Self::V7 => Uuid::now_v7().to_string(),
+            Self::Ulid => new_ulid(),
+            Self::Custom(generator) => generator(),
+        }
+    }
+}
+
+/// Generates ULID from current time and random bits of UUID v4.
+fn new_ulid() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or(0);
+
+    // 80 lowest bits of UUID v4 are used, only 2 of them (variant) aren't random
+    let random = Uuid::new_v4().as_u128() & ((1 << 80) - 1);
+    let value = u128::from(millis & ((1 << 48) - 1)) << 80 | random;
+
+    // 26 characters encode 130 bits, the first one encodes the highest 3 bits
+    (0..26)
+        .map(|index| ULID_ALPHABET[(value >> (125 - 5 * index)) as usize & 31] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_ids_by_strategy() {
+        let first = IdStrategy::V7.generate();
+        let second = IdStrategy::V7.generate();
+        assert!(first < second);
+
+        assert!(Uuid::parse_str(&IdStrategy::default().generate()).is_ok());
+
+        let ulid = IdStrategy::Ulid.generate();
+        assert_eq!(ulid.len(), 26);
+        assert!(crate::codec::id_timestamp(&ulid).is_some());
+        assert_eq!(IdStrategy::custom(|| String::from("id")).generate(), "id");
+    }
+}
@@ -0,0 +1,141 @@
+use super::{decode_message_with, DecodeMode, ReadQueueMessage};
+use crate::error::IpcError;
+use crate::{OptionalTimeout, RedisPool, Timeout};
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Message read by [`MultiReadQueue`] together with name of the queue it comes from.
+pub struct SourcedMessage<MessageContent> {
+    /// Name of the source queue
+    queue: Arc<String>,
+    /// Read message
+    message: ReadQueueMessage<MessageContent>,
+}
+
+impl<MessageContent> SourcedMessage<MessageContent> {
+    /// Returns name of the queue, which message comes from.
+    pub fn get_queue(&self) -> &str {
+        &self.queue
+    }
+
+    /// Returns read message.
+    pub fn get_message(&self) -> &ReadQueueMessage<MessageContent> {
+        &self.message
+    }
+
+    /// Returns message without source queue name.
+    pub fn into_message(self) -> ReadQueueMessage<MessageContent> {
+        self.message
+    }
+}
+
+/// Reads tasks from several queues with one blocking request, so one worker may serve several
+/// task types with a single connection. Messages are tagged with their source queue.
+///
+/// Queues are checked in order given to [`MultiReadQueue::new()`](MultiReadQueue::new), so
+/// earlier queues have priority, when several of them have pending tasks.
+pub struct MultiReadQueue<MessageContent: DeserializeOwned> {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Names of read queues
+    names: Vec<Arc<String>>,
+    /// blocking requests timeout, 0 if no timeout
+    timeout: Timeout,
+    /// Handling of unknown content fields
+    decode_mode: DecodeMode,
+    /// phantom indicating message type of queue instance
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: DeserializeOwned> MultiReadQueue<MessageContent> {
+    /// Builds reader of queues with given names.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`r2d2::Pool`] with redis connection
+    /// * names - names of read queues, in order of their priority
+    /// * timeout - blocking requests timeout or [`None`] for infinite timeout
+    pub fn new(pool: RedisPool, names: &[&str], timeout: OptionalTimeout) -> Self {
+        Self {
+            pool,
+            names: names.iter().map(|name| Arc::new(name.to_string())).collect(),
+            timeout: timeout.unwrap_or(Duration::ZERO),
+            decode_mode: DecodeMode::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// See [`ReadQueue::with_decode_mode()`](super::ReadQueue::with_decode_mode).
+    pub fn with_decode_mode(mut self, decode_mode: DecodeMode) -> Self {
+        self.decode_mode = decode_mode;
+        self
+    }
+
+    /// Returns names of read queues.
+    pub fn get_names(&self) -> Vec<&str> {
+        self.names.iter().map(|name| name.as_str()).collect()
+    }
+
+    /// Pops message from the first queue, which isn't empty, or returns [`None`] if all queues
+    /// are empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when connection fails or decoding message fails.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<SourcedMessage<MessageContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        for name in &self.names {
+            if let Some(msg) = conn.rpop::<&str, Option<String>>(name, None)? {
+                return self.sourced(name, &msg).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Blocking pop of message from any of the queues, with one `BRPOP` on all of them. Waits
+    /// indefinitely or returns [`None`] after timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when connection fails or decoding message fails.
+    pub fn b_next(&mut self) -> Result<Option<SourcedMessage<MessageContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let names: Vec<&str> = self.get_names();
+
+        // return type of redis blocking pop is ["queue_name", "queue_elem"]
+        let res = conn.brpop::<&[&str], Option<(String, String)>>(
+            &names,
+            self.timeout.as_secs_f64(),
+        )?;
+
+        let Some((queue, msg)) = res else {
+            return Ok(None);
+        };
+
+        let queue = match self.names.iter().find(|name| name.as_str() == queue) {
+            Some(name) => Arc::clone(name),
+            None => Arc::new(queue),
+        };
+
+        self.sourced(&queue, &msg).map(Some)
+    }
+
+    /// Decodes message popped from given queue.
+    fn sourced(
+        &self,
+        queue: &Arc<String>,
+        msg: &str,
+    ) -> Result<SourcedMessage<MessageContent>, IpcError> {
+        Ok(SourcedMessage {
+            queue: Arc::clone(queue),
+            message: decode_message_with(msg, self.decode_mode)?,
+        })
+    }
+}
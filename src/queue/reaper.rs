@@ -0,0 +1,83 @@
+use super::reliable::{inflight_key, parse_inflight_member, requeue_message, EXPIRED_SCRIPT};
+use crate::background::{self, BackgroundHandle};
+use crate::error::IpcError;
+use crate::RedisPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Requeues messages of [`ReliableReadQueue`](super::ReliableReadQueue), which weren't
+/// acknowledged within visibility timeout (see
+/// [`with_visibility_timeout()`](super::ReliableReadQueue::with_visibility_timeout)), so work of
+/// stalled consumers isn't stranded in their processing lists. Requeued messages are marked as
+/// redelivered.
+///
+/// Reaping may be done manually with [`QueueReaper::reap()`](QueueReaper::reap) or periodically
+/// in background thread started with [`QueueReaper::start()`](QueueReaper::start). Many reapers
+/// of the same queue may run at once, each message is requeued once.
+#[derive(Clone)]
+pub struct QueueReaper {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// queue name
+    name: Arc<String>,
+}
+
+impl QueueReaper {
+    /// Builds a reaper of given queue.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`r2d2::Pool`] with redis connection
+    /// * name - queue name, same as name of [`ReliableReadQueue`](super::ReliableReadQueue)
+    pub fn new(pool: RedisPool, name: &str) -> Self {
+        Self {
+            pool,
+            name: Arc::new(name.to_string()),
+        }
+    }
+
+    /// Moves messages with expired visibility timeout back to the queue. Returns number of
+    /// requeued messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn reap(&self) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let inflight = inflight_key(&self.name);
+        let expired = redis::Script::new(EXPIRED_SCRIPT)
+            .key(&inflight)
+            .invoke::<Vec<String>>(&mut *conn)?;
+
+        let mut moved = 0;
+
+        for member in expired {
+            match parse_inflight_member(&member) {
+                Some((consumer, uuid)) => {
+                    moved += requeue_message(&mut conn, &self.name, &consumer, &uuid)?;
+                }
+                None => {
+                    redis::cmd("ZREM")
+                        .arg(&inflight)
+                        .arg(&member)
+                        .query::<()>(&mut *conn)?;
+                }
+            }
+        }
+
+        Ok(moved)
+    }
+
+    /// Reaps the queue every `interval` in background thread, first time immediately. Failed
+    /// reaps are retried after next interval.
+    pub fn start(self, interval: Duration) -> ReaperHandle {
+        background::spawn_periodic(interval, move || {
+            let _ = self.reap();
+        })
+    }
+}
+
+/// Handle of reaping started with [`QueueReaper::start()`](QueueReaper::start). Reaping is
+/// stopped when handle is dropped.
+pub type ReaperHandle = BackgroundHandle;
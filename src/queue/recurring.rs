@@ -0,0 +1,275 @@
+use super::{encode_message, IdStrategy, WriteQueue, WriteQueueMessage};
+use crate::background::{self, BackgroundHandle};
+use crate::error::IpcError;
+use crate::lease::LeaderLease;
+use crate::RedisPool;
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Suffix of the redis hash, which stores definitions of recurring jobs.
+const JOBS_SUFFIX: &str = ":jobs";
+/// Suffix of the redis sorted set, which stores jobs scored by their next run time.
+const NEXT_RUN_SUFFIX: &str = ":next-run";
+/// Suffix of the redis key, which stores scheduler leader.
+const LEADER_SUFFIX: &str = ":leader";
+
+/// Stores definition `ARGV[2]` of job `ARGV[1]` and schedules its first run `ARGV[3]` ms from
+/// now, unless job is already scheduled.
+const REGISTER_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+redis.call('HSET', KEYS[1], ARGV[1], ARGV[2])
+redis.call('ZADD', KEYS[2], 'NX', now + tonumber(ARGV[3]), ARGV[1])
+return 1
+";
+
+/// Removes job definition and its schedule. Returns `1` when job was registered.
+const UNREGISTER_SCRIPT: &str = r"
+redis.call('ZREM', KEYS[2], ARGV[1])
+return redis.call('HDEL', KEYS[1], ARGV[1])
+";
+
+/// Returns due jobs with their scheduled run times.
+const DUE_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+return redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', now, 'WITHSCORES')
+";
+
+/// Pushes payload `ARGV[4]` to queue, if job `ARGV[1]` is still scheduled at `ARGV[2]`, and
+/// schedules its next run after `ARGV[3]` ms interval. Missed runs are skipped. Returns `1` when
+/// job was fired.
+const FIRE_SCRIPT: &str = r"
+local score = redis.call('ZSCORE', KEYS[1], ARGV[1])
+if not score or tonumber(score) ~= tonumber(ARGV[2]) then
+    return 0
+end
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local interval = tonumber(ARGV[3])
+local missed = math.floor((now - tonumber(score)) / interval)
+redis.call('ZADD', KEYS[1], tonumber(score) + (missed + 1) * interval, ARGV[1])
+redis.call('LPUSH', KEYS[2], ARGV[4])
+return 1
+";
+
+/// Hook called with name and raw definition of recurring jobs, which can't be decoded. See
+/// [`RecurringScheduler::with_invalid_job_hook()`](RecurringScheduler::with_invalid_job_hook).
+pub type InvalidJobHook = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Definition of recurring job stored in redis.
+#[derive(Serialize, Deserialize)]
+struct JobDefinition {
+    /// Name of the queue, which job messages are published to
+    queue: String,
+    /// Content of published messages
+    content: Value,
+    /// Interval between runs in ms
+    interval: u64,
+}
+
+/// Scheduler of recurring jobs, which publishes message of every job to its
+/// [`WriteQueue`](super::WriteQueue) every job interval, e.g. for periodic cleanups or reports.
+///
+/// Jobs are registered in redis, so they're shared by all scheduler instances with the same
+/// name. Jobs may be fired manually with [`RecurringScheduler::tick()`](RecurringScheduler::tick)
+/// or in background thread started with
+/// [`RecurringScheduler::start()`](RecurringScheduler::start), where only the elected leader
+/// fires jobs. Every run of a job is published once, even when many instances fire jobs at the
+/// same time. Runs missed while no scheduler was running are skipped, not published in a burst.
+#[derive(Clone)]
+pub struct RecurringScheduler {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// scheduler name
+    name: Arc<String>,
+    /// strategy of generating ids of published messages
+    id_strategy: IdStrategy,
+    /// Called with name and raw definition of jobs, which can't be decoded
+    invalid_job_hook: Option<InvalidJobHook>,
+}
+
+impl RecurringScheduler {
+    /// Builds a scheduler with given name. Instances with the same name share jobs.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`r2d2::Pool`] with redis connection
+    /// * name - scheduler name, used as prefix of its redis keys
+    pub fn new(pool: RedisPool, name: &str) -> Self {
+        Self {
+            pool,
+            name: Arc::new(name.to_string()),
+            id_strategy: IdStrategy::default(),
+            invalid_job_hook: None,
+        }
+    }
+
+    /// Sets strategy of generating ids of published messages.
+    pub fn with_id_strategy(mut self, id_strategy: IdStrategy) -> Self {
+        self.id_strategy = id_strategy;
+        self
+    }
+
+    /// Sets hook called with job name and raw definition of jobs, which can't be decoded (e.g.
+    /// stored by incompatible version of this crate), e.g. for logging. Such jobs are skipped
+    /// by [`RecurringScheduler::tick()`](RecurringScheduler::tick).
+    pub fn with_invalid_job_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &str) + Send + Sync + 'static,
+    {
+        self.invalid_job_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Registers job, which publishes `message_content` to `queue` every `interval` (at least
+    /// 1 ms). The first run is due `interval` from now. Registering already registered job
+    /// replaces its definition, but keeps its next run time, so all instances may register
+    /// their jobs on start.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn register<MessageContent: Serialize>(
+        &self,
+        job: &str,
+        queue: &WriteQueue<MessageContent>,
+        message_content: &MessageContent,
+        interval: Duration,
+    ) -> Result<(), IpcError> {
+        let interval = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX).max(1);
+
+        let definition = JobDefinition {
+            queue: queue.name.to_string(),
+            content: serde_json::to_value(message_content)?,
+            interval,
+        };
+
+        let mut conn = self.pool.get()?;
+
+        redis::Script::new(REGISTER_SCRIPT)
+            .key(jobs_key(&self.name))
+            .key(next_run_key(&self.name))
+            .arg(job)
+            .arg(serde_json::to_string(&definition)?)
+            .arg(interval)
+            .invoke::<()>(&mut *conn)?;
+
+        Ok(())
+    }
+
+    /// Removes job, so it isn't fired anymore. Returns `false` if job wasn't registered.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn unregister(&self, job: &str) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let removed = redis::Script::new(UNREGISTER_SCRIPT)
+            .key(jobs_key(&self.name))
+            .key(next_run_key(&self.name))
+            .arg(job)
+            .invoke::<u8>(&mut *conn)?;
+
+        Ok(removed == 1)
+    }
+
+    /// Returns time of the next run of given job or [`None`] if it isn't registered.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn next_run(&self, job: &str) -> Result<Option<SystemTime>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let score = conn.zscore::<String, &str, Option<f64>>(next_run_key(&self.name), job)?;
+
+        Ok(score.map(|score| UNIX_EPOCH + Duration::from_millis(score as u64)))
+    }
+
+    /// Publishes messages of due jobs and schedules their next runs. Returns number of fired
+    /// jobs. Jobs, whose definitions can't be decoded, are skipped and reported to the hook set
+    /// with [`with_invalid_job_hook()`](RecurringScheduler::with_invalid_job_hook), so they don't
+    /// stop other jobs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn tick(&self) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let due = redis::Script::new(DUE_SCRIPT)
+            .key(next_run_key(&self.name))
+            .invoke::<Vec<(String, String)>>(&mut *conn)?;
+
+        let mut fired = 0;
+
+        for (job, score) in due {
+            let raw = conn.hget::<String, &str, Option<String>>(jobs_key(&self.name), &job)?;
+
+            let Some(raw) = raw else {
+                // job was unregistered in the meantime
+                conn.zrem::<String, &str, ()>(next_run_key(&self.name), &job)?;
+                continue;
+            };
+            let Ok(definition) = serde_json::from_str::<JobDefinition>(&raw) else {
+                if let Some(hook) = &self.invalid_job_hook {
+                    hook(&job, &raw);
+                }
+                continue;
+            };
+
+            let message = WriteQueueMessage::new(self.id_strategy.generate(), &definition.content);
+
+            fired += redis::Script::new(FIRE_SCRIPT)
+                .key(next_run_key(&self.name))
+                .key(&definition.queue)
+                .arg(&job)
+                .arg(&score)
+                .arg(definition.interval)
+                .arg(encode_message(&message)?)
+                .invoke::<usize>(&mut *conn)?;
+        }
+
+        Ok(fired)
+    }
+
+    /// Fires due jobs every `interval` in background thread, first time immediately. Only one
+    /// of instances with the same name (the leader) fires jobs. Leadership is stored in redis
+    /// key `<name>:leader` and expires after three intervals, so other instance takes over when
+    /// the leader dies.
+    ///
+    /// Failed ticks are retried after next interval.
+    pub fn start(self, interval: Duration) -> SchedulerHandle {
+        let lease = LeaderLease::new(
+            self.pool.clone(),
+            format!("{}{}", self.name, LEADER_SUFFIX),
+            interval.saturating_mul(3),
+        );
+
+        background::spawn_periodic(interval, move || {
+            if let Ok(true) = lease.acquire() {
+                let _ = self.tick();
+            }
+        })
+    }
+}
+
+/// Handle of scheduler started with [`RecurringScheduler::start()`](RecurringScheduler::start).
+/// Scheduler is stopped when handle is dropped and leadership is released, so other instance
+/// may take over immediately.
+pub type SchedulerHandle = BackgroundHandle;
+
+/// Name of redis hash, which stores job definitions of given scheduler.
+fn jobs_key(scheduler: &str) -> String {
+    format!("{scheduler}{JOBS_SUFFIX}")
+}
+
+/// Name of redis sorted set, which stores next run times of jobs of given scheduler.
+fn next_run_key(scheduler: &str) -> String {
+    format!("{scheduler}{NEXT_RUN_SUFFIX}")
+}
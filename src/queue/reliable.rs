@@ -0,0 +1,391 @@
+use super::{decode_message, ReadQueueMessage};
+use crate::channel::Channel;
+use crate::codec::REDELIVERED_FIELD;
+use crate::error::IpcError;
+use crate::{OptionalTimeout, RedisConnection, RedisPool, Timeout};
+use redis::{Commands, Direction};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Infix of processing list names, `<queue>:processing:<consumer>`.
+const PROCESSING_INFIX: &str = ":processing:";
+/// Suffix of the redis sorted set, which stores visibility deadlines of processed messages.
+const INFLIGHT_SUFFIX: &str = ":inflight";
+
+/// Removes the first message with uuid `ARGV[1]` from processing list and its deadline
+/// `ARGV[2]`. Returns `1` if message was found.
+const ACK_SCRIPT: &str = r"
+redis.call('ZREM', KEYS[2], ARGV[2])
+for _, payload in ipairs(redis.call('LRANGE', KEYS[1], 0, -1)) do
+    local ok, message = pcall(cjson.decode, payload)
+    if ok and type(message) == 'table' and message['uuid'] == ARGV[1] then
+        return redis.call('LREM', KEYS[1], 1, payload)
+    end
+end
+return 0
+";
+
+/// Moves payload `ARGV[1]` from processing list to queue as `ARGV[2]`, if it's still in
+/// processing list, and removes its deadline `ARGV[3]`. Returns `1` when it was moved.
+const REQUEUE_SCRIPT: &str = r"
+redis.call('ZREM', KEYS[3], ARGV[3])
+if redis.call('LREM', KEYS[1], 1, ARGV[1]) == 1 then
+    redis.call('RPUSH', KEYS[2], ARGV[2])
+    return 1
+end
+return 0
+";
+
+/// Sets deadline of message `ARGV[1]` to `ARGV[2]` ms from now. Existing deadline is updated
+/// only when `ARGV[3]` is `XX`. Returns `1` when deadline was set.
+const TRACK_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local deadline = now + tonumber(ARGV[2])
+if ARGV[3] == 'XX' then
+    return redis.call('ZADD', KEYS[1], 'XX', 'CH', deadline, ARGV[1])
+end
+return redis.call('ZADD', KEYS[1], deadline, ARGV[1])
+";
+
+/// Returns messages with expired deadline.
+pub(super) const EXPIRED_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+return redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', now)
+";
+
+/// Read queue with at-least-once delivery. Read messages aren't removed from redis, but moved
+/// atomically (`LMOVE`) to processing list of the consumer, where they stay until they're
+/// acknowledged with [`ReliableReadQueue::ack()`](ReliableReadQueue::ack). Messages left in
+/// processing list by crashed consumer are moved back to the queue by the next consumer with
+/// the same name on its first read, marked as redelivered (see
+/// [`ReadQueueMessage::is_redelivered()`](ReadQueueMessage::is_redelivered)).
+///
+/// Consumer name should be unique and stable for worker process. It consumes messages published
+/// with [`WriteQueue`](super::WriteQueue).
+pub struct ReliableReadQueue<MessageContent: DeserializeOwned> {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// blocking requests timeout
+    timeout: Timeout,
+    /// queue name
+    name: Arc<String>,
+    /// consumer name
+    consumer: Arc<String>,
+    /// name of processing list of the consumer
+    processing: Arc<String>,
+    /// max processing time, after which message may be requeued, [`None`] if it isn't limited
+    visibility_timeout: Option<Duration>,
+    /// set when messages left by previous run were requeued
+    recovered: bool,
+    /// phantom indicating message type of queue instance
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: DeserializeOwned> ReliableReadQueue<MessageContent> {
+    /// Builds a queue read by given consumer.
+    ///
+    /// # Arguments
+    ///
+    /// * pool - configured [`r2d2::Pool`] with redis connection
+    /// * name - queue name, same as name of [`WriteQueue`](super::WriteQueue)
+    /// * consumer - consumer name, unique within the queue
+    /// * timeout - blocking requests timeout or [`None`] for infinite timeout
+    pub fn new(pool: RedisPool, name: &str, consumer: &str, timeout: OptionalTimeout) -> Self {
+        Self {
+            pool,
+            timeout: timeout.unwrap_or(Duration::ZERO),
+            name: Arc::new(name.to_string()),
+            consumer: Arc::new(consumer.to_string()),
+            processing: Arc::new(processing_key(name, consumer)),
+            visibility_timeout: None,
+            recovered: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds [`ReliableReadQueue`] for given [`Channel`]. Channel name is used as queue name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        pool: RedisPool,
+        consumer: &str,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(pool, C::NAME, consumer, timeout)
+    }
+
+    /// Sets max processing time of read messages. Messages not acknowledged within it are moved
+    /// back to the queue by [`QueueReaper`](super::QueueReaper), so messages of stalled
+    /// consumer are processed by other ones. Processing may be extended with
+    /// [`ReliableReadQueue::touch()`](ReliableReadQueue::touch).
+    pub fn with_visibility_timeout(mut self, timeout: Duration) -> Self {
+        self.visibility_timeout = Some(timeout);
+        self
+    }
+
+    /// Returns name of redis list, which stores messages processed by this consumer.
+    pub fn get_processing_list(&self) -> &str {
+        &self.processing
+    }
+
+    /// Moves the next message to processing list and returns it or [`None`] if queue is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when connection fails or decoding message fails. Message,
+    /// which can't be decoded, stays in processing list.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+        self.recover(&mut conn)?;
+
+        // queue is consumed from the right side
+        let msg = conn.lmove::<&str, &str, Option<String>>(
+            &self.name,
+            &self.processing,
+            Direction::Right,
+            Direction::Left,
+        )?;
+
+        msg.map(|msg| self.track(&mut conn, &msg)).transpose()
+    }
+
+    /// Blocking read of the next message, which is moved to processing list. Waits indefinitely
+    /// or returns [`None`] after timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or parsing failure.
+    pub fn b_next(&mut self) -> Result<Option<ReadQueueMessage<MessageContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+        self.recover(&mut conn)?;
+
+        // timeout is in seconds, 0.0 is infinite
+        let msg = conn.blmove::<&str, &str, Option<String>>(
+            &self.name,
+            &self.processing,
+            Direction::Right,
+            Direction::Left,
+            self.timeout.as_secs_f64(),
+        )?;
+
+        msg.map(|msg| self.track(&mut conn, &msg)).transpose()
+    }
+
+    /// Decodes read message and sets its visibility deadline, if timeout is set.
+    fn track(
+        &self,
+        conn: &mut RedisConnection,
+        msg: &str,
+    ) -> Result<ReadQueueMessage<MessageContent>, IpcError> {
+        let message = decode_message::<MessageContent>(msg)?;
+
+        if let Some(timeout) = self.visibility_timeout {
+            set_deadline(conn, &self.name, &self.member(message.get_uuid()), timeout, false)?;
+        }
+
+        Ok(message)
+    }
+
+    /// Restarts visibility timeout of message with given uuid, e.g. periodically during long
+    /// processing. Returns `false` if message isn't processed with visibility timeout (e.g. it
+    /// was already acknowledged or requeued).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn touch(&self, uuid: &str) -> Result<bool, IpcError> {
+        let Some(timeout) = self.visibility_timeout else {
+            return Ok(false);
+        };
+
+        let mut conn = self.pool.get()?;
+
+        set_deadline(&mut conn, &self.name, &self.member(uuid), timeout, true)
+    }
+
+    /// Returns member of inflight set, which stores deadline of given message.
+    fn member(&self, uuid: &str) -> String {
+        inflight_member(&self.consumer, uuid)
+    }
+
+    /// Acknowledges message with given uuid, so it's removed from processing list. Returns
+    /// `false` if message isn't in processing list (e.g. it was already acknowledged).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn ack(&self, uuid: &str) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let removed = redis::Script::new(ACK_SCRIPT)
+            .key(self.processing.as_str())
+            .key(inflight_key(&self.name))
+            .arg(uuid)
+            .arg(self.member(uuid))
+            .invoke::<u8>(&mut *conn)?;
+
+        Ok(removed == 1)
+    }
+
+    /// Moves all messages from processing list of this consumer back to the queue, marked as
+    /// redelivered. They're read before other messages. Returns number of moved messages.
+    ///
+    /// It's done automatically on the first read, so messages of crashed consumer are
+    /// redelivered after its restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn requeue_unacked(&self) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        requeue(&mut conn, &self.name, &self.consumer)
+    }
+
+    /// Requeues messages left by previous run, if it wasn't done yet.
+    fn recover(&mut self, conn: &mut RedisConnection) -> Result<(), IpcError> {
+        if !self.recovered {
+            requeue(conn, &self.name, &self.consumer)?;
+            self.recovered = true;
+        }
+
+        Ok(())
+    }
+}
+
+/// Moves all messages from processing list of the consumer to the queue, marked as
+/// redelivered. Messages acknowledged in the meantime are skipped. Returns number of moved
+/// messages.
+fn requeue(conn: &mut RedisConnection, queue: &str, consumer: &str) -> Result<usize, IpcError> {
+    let payloads = conn.lrange::<String, Vec<String>>(processing_key(queue, consumer), 0, -1)?;
+
+    let mut moved = 0;
+
+    // the oldest message is at the right side and it's pushed last, so it's read first
+    for payload in payloads {
+        moved += requeue_payload(conn, queue, consumer, &payload)?;
+    }
+
+    Ok(moved)
+}
+
+/// Moves message with given uuid from processing list of the consumer to the queue. Returns
+/// number of moved messages, `0` if message isn't processed anymore.
+pub(super) fn requeue_message(
+    conn: &mut RedisConnection,
+    queue: &str,
+    consumer: &str,
+    uuid: &str,
+) -> Result<usize, IpcError> {
+    let payloads = conn.lrange::<String, Vec<String>>(processing_key(queue, consumer), 0, -1)?;
+
+    let payload = payloads.into_iter().find(|payload| {
+        matches!(
+            decode_message::<Value>(payload),
+            Ok(message) if message.get_uuid() == uuid
+        )
+    });
+
+    match payload {
+        Some(payload) => requeue_payload(conn, queue, consumer, &payload),
+        None => {
+            // message was acknowledged, only its deadline is left
+            conn.zrem::<String, String, ()>(inflight_key(queue), inflight_member(consumer, uuid))?;
+            Ok(0)
+        }
+    }
+}
+
+/// Moves payload from processing list of the consumer to the queue, if it's still there, and
+/// removes its deadline. Returns number of moved messages.
+fn requeue_payload(
+    conn: &mut RedisConnection,
+    queue: &str,
+    consumer: &str,
+    payload: &str,
+) -> Result<usize, IpcError> {
+    let uuid = decode_message::<Value>(payload)
+        .map(|message| message.get_uuid().to_string())
+        .unwrap_or_default();
+
+    let moved = redis::Script::new(REQUEUE_SCRIPT)
+        .key(processing_key(queue, consumer))
+        .key(queue)
+        .key(inflight_key(queue))
+        .arg(payload)
+        .arg(mark_redelivered(payload))
+        .arg(inflight_member(consumer, &uuid))
+        .invoke::<usize>(&mut **conn)?;
+
+    Ok(moved)
+}
+
+/// Sets visibility deadline of given inflight member. Returns `false` if `existing` is set and
+/// member has no deadline.
+fn set_deadline(
+    conn: &mut RedisConnection,
+    queue: &str,
+    member: &str,
+    timeout: Duration,
+    existing: bool,
+) -> Result<bool, IpcError> {
+    let set = redis::Script::new(TRACK_SCRIPT)
+        .key(inflight_key(queue))
+        .arg(member)
+        .arg(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX))
+        .arg(if existing { "XX" } else { "" })
+        .invoke::<u8>(&mut **conn)?;
+
+    Ok(set == 1)
+}
+
+/// Marks payload as redelivered. Payload, which isn't a JSON object, is returned unchanged.
+fn mark_redelivered(payload: &str) -> String {
+    match serde_json::from_str::<Value>(payload) {
+        Ok(Value::Object(mut message)) => {
+            message.insert(REDELIVERED_FIELD.to_string(), Value::Bool(true));
+            Value::Object(message).to_string()
+        }
+        _ => payload.to_string(),
+    }
+}
+
+/// Returns name of processing list of given consumer.
+fn processing_key(queue: &str, consumer: &str) -> String {
+    format!("{queue}{PROCESSING_INFIX}{consumer}")
+}
+
+/// Returns name of redis sorted set, which stores visibility deadlines of given queue.
+pub(super) fn inflight_key(queue: &str) -> String {
+    format!("{queue}{INFLIGHT_SUFFIX}")
+}
+
+/// Returns member of inflight set of given message, JSON array `[consumer, uuid]`.
+fn inflight_member(consumer: &str, uuid: &str) -> String {
+    serde_json::to_string(&[consumer, uuid]).unwrap_or_default()
+}
+
+/// Parses member of inflight set to consumer and uuid.
+pub(super) fn parse_inflight_member(member: &str) -> Option<(String, String)> {
+    serde_json::from_str(member).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_payload_as_redelivered() {
+        let payload = r#"{"uuid":"1","content":{"id":18446744073709551615}}"#;
+
+        let message = decode_message::<Value>(&mark_redelivered(payload)).unwrap();
+        assert!(message.is_redelivered());
+        assert_eq!(message.get_content()["id"], u64::MAX);
+
+        assert_eq!(mark_redelivered("not json"), "not json");
+    }
+}
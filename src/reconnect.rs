@@ -0,0 +1,207 @@
+//! Automatic reconnection of blocking reads. Without it, blocking read interrupted by redis
+//! restart or failover returns error, which every caller has to handle the same way.
+//!
+//! Structures configured with [`Reconnect`] (e.g. with
+//! [`ReadQueue::with_reconnect()`](crate::ReadQueue::with_reconnect)) retry blocking read until
+//! redis is reachable again and resume from their state: queue reader resumes `BRPOP`, stream
+//! reader re-issues `XREAD` from the last read id and group reader re-creates its group if it's
+//! missing. Hook set with [`Reconnect::with_resume_hook()`](Reconnect::with_resume_hook) is
+//! called when read is resumed.
+//!
+//! # Examples
+//! ```ignored
+//! let reconnect = Reconnect::new(Duration::from_secs(1)).with_resume_hook(|resumed| {
+//!     eprintln!("{} resumed after {:?}", resumed.get_consumer(), resumed.get_downtime());
+//! });
+//!
+//! let mut queue = ReadQueue::new(pool, "tasks", None).with_reconnect(reconnect);
+//! ```
+
+use crate::cancel::CancellationToken;
+use crate::error::IpcError;
+use redis::RedisError;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Hook called when blocking read is resumed after reconnection.
+pub type ResumeHook = Arc<dyn Fn(&Resumed) + Send + Sync>;
+
+/// Event of blocking read resumed after redis was unreachable.
+#[derive(Debug, Clone)]
+pub struct Resumed {
+    /// Name of the structure, which does the read
+    consumer: Arc<String>,
+    /// Number of failed attempts
+    attempts: u32,
+    /// Time since the first failed attempt
+    downtime: Duration,
+}
+
+impl Resumed {
+    /// Returns name of the structure (e.g. queue or stream), which does the read.
+    pub fn get_consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    /// Returns number of failed attempts.
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns time since the first failed attempt.
+    pub fn get_downtime(&self) -> Duration {
+        self.downtime
+    }
+}
+
+/// Reconnection policy of blocking reads. See [module documentation](self).
+#[derive(Clone)]
+pub struct Reconnect {
+    /// Time between attempts
+    retry_interval: Duration,
+    /// Hook called when read is resumed
+    hook: Option<ResumeHook>,
+}
+
+impl Reconnect {
+    /// Creates policy, which retries read every `retry_interval` until redis is reachable.
+    pub fn new(retry_interval: Duration) -> Self {
+        Self {
+            retry_interval,
+            hook: None,
+        }
+    }
+
+    /// Sets hook called when read is resumed.
+    pub fn with_resume_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Resumed) + Send + Sync + 'static,
+    {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Runs `read` until it returns result other than connection loss. Retries are interrupted,
+    /// when `cancellation` is cancelled.
+    pub(crate) fn run<T, F>(
+        &self,
+        consumer: &Arc<String>,
+        cancellation: Option<&CancellationToken>,
+        mut read: F,
+    ) -> Result<T, IpcError>
+    where
+        F: FnMut() -> Result<T, IpcError>,
+    {
+        let mut attempts = 0;
+        let mut since = None;
+
+        loop {
+            match read() {
+                Err(error) if is_disconnected(&error) => {
+                    attempts += 1;
+                    since.get_or_insert_with(Instant::now);
+
+                    if let Some(token) = cancellation {
+                        token.check()?;
+                    }
+
+                    thread::sleep(self.retry_interval);
+                }
+                res => {
+                    if let (Some(since), Some(hook)) = (since, &self.hook) {
+                        hook(&Resumed {
+                            consumer: Arc::clone(consumer),
+                            attempts,
+                            downtime: since.elapsed(),
+                        });
+                    }
+
+                    return res;
+                }
+            }
+        }
+    }
+}
+
+/// Checks if error means that redis is unreachable, e.g. so read should be retried or message
+/// should be spilled.
+pub(crate) fn is_unreachable(error: &RedisError) -> bool {
+    error.is_io_error()
+        || error.is_connection_refusal()
+        || error.is_connection_dropped()
+        || error.is_timeout()
+}
+
+/// Checks if error means that command surely wasn't delivered to redis, so message can be
+/// spilled. Timeouts and other io errors don't count, because command may have been executed.
+#[cfg(feature = "redis")]
+pub(crate) fn is_not_delivered(error: &RedisError) -> bool {
+    error.is_connection_refusal() || error.is_connection_dropped()
+}
+
+/// Checks if error means that redis is unreachable, so read should be retried.
+fn is_disconnected(error: &IpcError) -> bool {
+    let source = error.get_ref();
+
+    if let Some(error) = source.downcast_ref::<RedisError>() {
+        return is_unreachable(error);
+    }
+
+    // pool can't establish connection
+    source.is::<r2d2::Error>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::IpcErrorKind;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn retries_until_reconnected() {
+        let resumed = Arc::new(AtomicU32::new(0));
+
+        let reconnect = {
+            let resumed = Arc::clone(&resumed);
+            Reconnect::new(Duration::from_millis(1)).with_resume_hook(move |event| {
+                resumed.store(event.get_attempts(), Ordering::SeqCst);
+            })
+        };
+
+        let consumer = Arc::new(String::from("queue"));
+        let mut failures = 3;
+
+        let res = reconnect.run(&consumer, None, || {
+            if failures == 0 {
+                return Ok(1);
+            }
+            failures -= 1;
+
+            let error = RedisError::from(io::Error::from(io::ErrorKind::ConnectionReset));
+            Err(error.into())
+        });
+
+        assert_eq!(res.unwrap(), 1);
+        assert_eq!(resumed.load(Ordering::SeqCst), 3);
+
+        // other errors are returned immediately
+        let res = reconnect.run(&consumer, None, || -> Result<(), IpcError> {
+            Err(IpcError::new(IpcErrorKind::InvalidData, "Invalid data."))
+        });
+
+        assert!(matches!(res.unwrap_err().kind(), IpcErrorKind::InvalidData));
+    }
+
+    #[cfg(feature = "redis")]
+    #[test]
+    fn timeout_is_not_treated_as_undelivered() {
+        let refused = RedisError::from(io::Error::from(io::ErrorKind::ConnectionRefused));
+        let timeout = RedisError::from(io::Error::from(io::ErrorKind::TimedOut));
+
+        assert!(is_not_delivered(&refused));
+        assert!(is_unreachable(&timeout));
+        assert!(!is_not_delivered(&timeout));
+    }
+}
@@ -0,0 +1,116 @@
+//! Redaction of sensitive message fields in operational copies of messages. Dead letters and
+//! exported stream segments are often kept longer and read by more people than messages
+//! themselves, so fields like personal data may be masked there, while real consumers still
+//! receive them intact.
+//!
+//! [`Redactor`] is applied to JSON content of the message, when it's copied with structures
+//! configured with it (e.g. with
+//! [`DeadLetterQueue::with_redaction()`](crate::dlq::DeadLetterQueue::with_redaction)).
+//!
+//! # Examples
+//! ```ignored
+//! let redactor = Redactor::mask_fields(&["/email", "/card/number"]);
+//!
+//! let dlq = DeadLetterQueue::new(pool, "tasks:dlq").with_redaction(redactor);
+//! ```
+
+use crate::error::IpcError;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Value, which replaces masked strings.
+const MASK: &str = "[REDACTED]";
+
+/// Hook, which modifies JSON content of the message in place.
+pub type RedactHook = Arc<dyn Fn(&mut Value) + Send + Sync>;
+
+/// Redaction applied to operational copies of messages. See [module documentation](self).
+#[derive(Clone)]
+pub struct Redactor {
+    hook: RedactHook,
+}
+
+impl Redactor {
+    /// Creates redactor, which modifies message content with given hook.
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&mut Value) + Send + Sync + 'static,
+    {
+        Self {
+            hook: Arc::new(hook),
+        }
+    }
+
+    /// Creates redactor, which masks fields with given
+    /// [JSON pointers](https://datatracker.ietf.org/doc/html/rfc6901) (e.g. `/user/email`).
+    /// Strings are replaced with `[REDACTED]` and other values with empty value of the same
+    /// type, so redacted content may still be parsed to message type. Missing fields are
+    /// ignored.
+    pub fn mask_fields(pointers: &[&str]) -> Self {
+        let pointers: Vec<String> = pointers.iter().map(|pointer| pointer.to_string()).collect();
+
+        Self::new(move |content| {
+            for pointer in &pointers {
+                if let Some(value) = content.pointer_mut(pointer) {
+                    mask(value);
+                }
+            }
+        })
+    }
+
+    /// Returns redacted JSON content of the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when content can't be serialized.
+    pub fn redact<T: Serialize>(&self, content: &T) -> Result<Value, IpcError> {
+        let mut value = serde_json::to_value(content)?;
+        self.redact_value(&mut value);
+
+        Ok(value)
+    }
+
+    /// Redacts JSON content of the message in place.
+    pub(crate) fn redact_value(&self, content: &mut Value) {
+        (self.hook)(content);
+    }
+}
+
+/// Replaces value with mask or empty value of the same type.
+fn mask(value: &mut Value) {
+    *value = match value {
+        Value::String(_) => Value::String(MASK.to_string()),
+        Value::Number(_) => Value::from(0),
+        Value::Bool(_) => Value::Bool(false),
+        Value::Array(_) => Value::Array(Vec::new()),
+        Value::Object(_) => Value::Object(serde_json::Map::new()),
+        Value::Null => Value::Null,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn masks_fields() {
+        let redactor = Redactor::mask_fields(&["/email", "/card/number", "/missing"]);
+
+        let content = json!({
+            "email": "user@example.com",
+            "card": { "number": 4111, "owner": "user" },
+        });
+
+        let redacted = redactor.redact(&content).unwrap();
+
+        assert_eq!(
+            redacted,
+            json!({
+                "email": "[REDACTED]",
+                "card": { "number": 0, "owner": "user" },
+            })
+        );
+    }
+}
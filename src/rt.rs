@@ -0,0 +1,40 @@
+//! Minimal abstraction over async runtimes, so async structures don't depend on specific one.
+//! Tokio is used when both `tokio` and `async-std` features are enabled.
+
+use crate::AsyncRedisConnection;
+use redis::{AsyncConnectionConfig, Client, RedisResult};
+use std::time::Duration;
+
+#[cfg(not(any(feature = "tokio", feature = "async-std")))]
+compile_error!("`aio` feature requires `tokio` or `async-std` runtime feature.");
+
+/// Waits until `duration` elapses.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    async_std::task::sleep(duration).await;
+}
+
+/// Establishes multiplexed connection driven by the selected runtime.
+pub(crate) async fn connect(client: &Client) -> RedisResult<AsyncRedisConnection> {
+    #[cfg(feature = "tokio")]
+    {
+        client.get_multiplexed_tokio_connection().await
+    }
+
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    {
+        client.get_multiplexed_async_std_connection().await
+    }
+}
+
+/// Establishes multiplexed connection with given config. Runtime is selected by redis client,
+/// which also prefers tokio when both are enabled and tokio runtime is running.
+pub(crate) async fn connect_with_config(
+    client: &Client,
+    config: &AsyncConnectionConfig,
+) -> RedisResult<AsyncRedisConnection> {
+    client.get_multiplexed_async_connection_with_config(config).await
+}
@@ -0,0 +1,367 @@
+//! Contract testing helpers for message schemas.
+//!
+//! Schemas are inferred from sample payloads of registered message types. Incoming messages may
+//! be verified against them in a permissive mode: differences are reported as [`SchemaDrift`]
+//! instead of failing, so producer/consumer drift can be detected before it breaks
+//! deserialization.
+//!
+//! # Examples
+//! ```
+//! use redis_ipc::message::Message;
+//! use redis_ipc::schema::SchemaRegistry;
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct OrderCreated {
+//!     id: u64,
+//! }
+//!
+//! impl Message for OrderCreated {
+//!     const TYPE_NAME: &'static str = "order.created";
+//!     const SCHEMA_VERSION: u32 = 1;
+//!     const CHANNEL: &'static str = "orders";
+//! }
+//!
+//! let mut registry = SchemaRegistry::new();
+//! registry.register(&OrderCreated { id: 1 }).unwrap();
+//!
+//! let drift = registry.verify("order.created", r#"{"id": "1"}"#).unwrap();
+//! assert_eq!(drift.len(), 1);
+//! ```
+
+use crate::error::{IpcError, IpcErrorKind};
+use crate::message::Message;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Structural schema of JSON payload.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    /// Any value, used when type can't be inferred from sample (e.g. `null`).
+    Any,
+    Bool,
+    Number,
+    String,
+    /// Array with elements of given schema.
+    Array(Box<Schema>),
+    /// Object with given fields.
+    Object(BTreeMap<String, Schema>),
+}
+
+impl Schema {
+    /// Infers schema from serialized sample.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when sample can't be serialized.
+    pub fn from_sample<T: Serialize>(sample: &T) -> Result<Self, IpcError> {
+        Ok(Self::from_value(&serde_json::to_value(sample)?))
+    }
+
+    /// Infers schema from JSON value.
+    pub fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Null => Schema::Any,
+            Value::Bool(_) => Schema::Bool,
+            Value::Number(_) => Schema::Number,
+            Value::String(_) => Schema::String,
+            Value::Array(elements) => Schema::Array(Box::new(
+                elements.first().map(Self::from_value).unwrap_or(Schema::Any),
+            )),
+            Value::Object(fields) => Schema::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Self::from_value(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns [JSON schema](https://json-schema.org/) of this schema.
+    pub fn to_json_schema(&self) -> Value {
+        match self {
+            Schema::Any => json!({}),
+            Schema::Bool => json!({ "type": "boolean" }),
+            Schema::Number => json!({ "type": "number" }),
+            Schema::String => json!({ "type": "string" }),
+            Schema::Array(elements) => json!({
+                "type": "array",
+                "items": elements.to_json_schema(),
+            }),
+            Schema::Object(fields) => {
+                let properties: Map<String, Value> = fields
+                    .iter()
+                    .map(|(name, schema)| (name.clone(), schema.to_json_schema()))
+                    .collect();
+
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": fields.keys().collect::<Vec<_>>(),
+                })
+            }
+        }
+    }
+
+    /// Checks given value against this schema and returns every found difference. Empty result
+    /// means value matches schema.
+    pub fn check(&self, value: &Value) -> Vec<SchemaDrift> {
+        let mut drift = Vec::new();
+        self.check_path(value, "$", &mut drift);
+        drift
+    }
+
+    fn check_path(&self, value: &Value, path: &str, drift: &mut Vec<SchemaDrift>) {
+        match (self, value) {
+            (Schema::Any, _)
+            | (Schema::Bool, Value::Bool(_))
+            | (Schema::Number, Value::Number(_))
+            | (Schema::String, Value::String(_)) => {}
+            (Schema::Array(schema), Value::Array(elements)) => {
+                for (index, element) in elements.iter().enumerate() {
+                    schema.check_path(element, &format!("{path}[{index}]"), drift);
+                }
+            }
+            (Schema::Object(fields), Value::Object(values)) => {
+                for (name, schema) in fields {
+                    let field_path = format!("{path}.{name}");
+
+                    match values.get(name) {
+                        Some(value) => schema.check_path(value, &field_path, drift),
+                        None => drift.push(SchemaDrift::new(field_path, DriftKind::MissingField)),
+                    }
+                }
+
+                for name in values.keys().filter(|name| !fields.contains_key(*name)) {
+                    drift.push(SchemaDrift::new(
+                        format!("{path}.{name}"),
+                        DriftKind::UnknownField,
+                    ));
+                }
+            }
+            // null is accepted everywhere, because optional fields are serialized as null
+            (_, Value::Null) => {}
+            (schema, value) => drift.push(SchemaDrift::new(
+                path.to_string(),
+                DriftKind::TypeMismatch {
+                    expected: schema.type_name(),
+                    found: value_type_name(value),
+                },
+            )),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Schema::Any => "any",
+            Schema::Bool => "boolean",
+            Schema::Number => "number",
+            Schema::String => "string",
+            Schema::Array(_) => "array",
+            Schema::Object(_) => "object",
+        }
+    }
+}
+
+fn value_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Kind of difference between message and its schema.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriftKind {
+    /// Field is present in schema, but not in the message.
+    MissingField,
+    /// Field is present in the message, but not in schema.
+    UnknownField,
+    /// Value has different type than in schema.
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+/// Single difference between message and its schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDrift {
+    /// Path of drifted value, e.g. `$.items[0].id`
+    path: String,
+    /// Drift kind
+    kind: DriftKind,
+}
+
+impl SchemaDrift {
+    pub fn new(path: String, kind: DriftKind) -> Self {
+        Self { path, kind }
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get_kind(&self) -> &DriftKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DriftKind::MissingField => write!(f, "{}: missing field", self.path),
+            DriftKind::UnknownField => write!(f, "{}: unknown field", self.path),
+            DriftKind::TypeMismatch { expected, found } => {
+                write!(f, "{}: expected {expected}, found {found}", self.path)
+            }
+        }
+    }
+}
+
+/// Schema of registered message type.
+struct RegisteredSchema {
+    version: u32,
+    sample: Value,
+    schema: Schema,
+}
+
+/// Registry of message schemas, keyed by [`Message::TYPE_NAME`](Message::TYPE_NAME).
+#[derive(Default)]
+pub struct SchemaRegistry {
+    schemas: HashMap<String, RegisteredSchema>,
+}
+
+impl SchemaRegistry {
+    /// Creates empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers message type using given sample. Sample should contain every field (also
+    /// optional ones), because schema is inferred from it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when sample can't be serialized.
+    pub fn register<T: Message + Serialize>(&mut self, sample: &T) -> Result<(), IpcError> {
+        let sample = serde_json::to_value(sample)?;
+
+        self.schemas.insert(
+            T::TYPE_NAME.to_string(),
+            RegisteredSchema {
+                version: T::SCHEMA_VERSION,
+                schema: Schema::from_value(&sample),
+                sample,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Returns schema of registered type.
+    pub fn schema(&self, type_name: &str) -> Option<&Schema> {
+        self.schemas.get(type_name).map(|registered| &registered.schema)
+    }
+
+    /// Returns JSON schema of registered type, with schema version stored as `version` field.
+    pub fn json_schema(&self, type_name: &str) -> Option<Value> {
+        self.schemas.get(type_name).map(|registered| {
+            let mut schema = registered.schema.to_json_schema();
+
+            if let Value::Object(fields) = &mut schema {
+                fields.insert("title".to_string(), json!(type_name));
+                fields.insert("version".to_string(), json!(registered.version));
+            }
+
+            schema
+        })
+    }
+
+    /// Returns sample payload of registered type.
+    pub fn sample(&self, type_name: &str) -> Option<&Value> {
+        self.schemas.get(type_name).map(|registered| &registered.sample)
+    }
+
+    /// Verifies raw JSON payload against schema of registered type. Verification is permissive,
+    /// drift is returned instead of error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when type isn't registered or payload isn't valid JSON.
+    pub fn verify(&self, type_name: &str, payload: &str) -> Result<Vec<SchemaDrift>, IpcError> {
+        let value = serde_json::from_str::<Value>(payload)?;
+
+        self.verify_value(type_name, &value)
+    }
+
+    /// Same as [`SchemaRegistry::verify()`](SchemaRegistry::verify), but for parsed payload.
+    pub fn verify_value(
+        &self,
+        type_name: &str,
+        value: &Value,
+    ) -> Result<Vec<SchemaDrift>, IpcError> {
+        let schema = self.schema(type_name).ok_or_else(|| {
+            IpcError::new(
+                IpcErrorKind::InvalidData,
+                format!("Schema of type \"{type_name}\" is not registered."),
+            )
+        })?;
+
+        Ok(schema.check(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_value_has_no_drift() {
+        let schema = Schema::from_value(&json!({ "id": 1, "tags": ["a"] }));
+
+        assert!(schema.check(&json!({ "id": 2, "tags": [] })).is_empty());
+    }
+
+    #[test]
+    fn reports_drift() {
+        let schema = Schema::from_value(&json!({ "id": 1, "name": "a" }));
+
+        let drift = schema.check(&json!({ "id": "1", "extra": true }));
+
+        assert_eq!(
+            drift,
+            vec![
+                SchemaDrift::new(
+                    "$.id".to_string(),
+                    DriftKind::TypeMismatch {
+                        expected: "number",
+                        found: "string"
+                    }
+                ),
+                SchemaDrift::new("$.name".to_string(), DriftKind::MissingField),
+                SchemaDrift::new("$.extra".to_string(), DriftKind::UnknownField),
+            ]
+        );
+    }
+
+    #[test]
+    fn emits_json_schema() {
+        let schema = Schema::from_value(&json!({ "id": 1 }));
+
+        assert_eq!(
+            schema.to_json_schema(),
+            json!({
+                "type": "object",
+                "properties": { "id": { "type": "number" } },
+                "required": ["id"],
+            })
+        );
+    }
+}
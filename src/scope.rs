@@ -0,0 +1,145 @@
+//! Ownership of background consumers, see [`IpcScope`].
+//!
+//! # Examples
+//! ```ignored
+//! let mut scope = IpcScope::new();
+//!
+//! for worker in 0..4 {
+//!     let queue = ReadQueue::<Task>::new(pool.clone(), "tasks", None)
+//!         .with_cancellation(scope.get_token());
+//!     scope.spawn(move |_| {
+//!         for task in queue {
+//!             handle(worker, task);
+//!         }
+//!     });
+//! }
+//! scope.adopt(cache.auto_refresh("config", Duration::from_secs(30), load_config));
+//!
+//! // cancels workers, stops refresh and waits for all of them
+//! drop(scope);
+//! ```
+
+use crate::cancel::CancellationToken;
+use std::any::Any;
+use std::thread::{self, JoinHandle};
+
+/// Owner of consumer threads and background handles (e.g.
+/// [`RefreshHandle`](crate::cache::RefreshHandle) or [`StatusHandle`](crate::status::StatusHandle))
+/// of one part of application. When scope is dropped, its [`CancellationToken`] is cancelled,
+/// adopted handles are dropped (which stops their threads) and spawned threads are joined, so
+/// no background loop outlives the scope, e.g. when consumers of removed tenant are shut down.
+///
+/// Spawned threads must end on cancellation, e.g. by reading with structures using
+/// [`IpcScope::get_token()`](IpcScope::get_token) or by checking the token passed to them.
+/// Otherwise dropping scope blocks until they end.
+#[derive(Default)]
+pub struct IpcScope {
+    /// Token cancelled when scope is dropped
+    token: CancellationToken,
+    /// Spawned threads
+    threads: Vec<JoinHandle<()>>,
+    /// Adopted handles, they are dropped in reverse order of adoption
+    handles: Vec<Box<dyn Any + Send>>,
+}
+
+impl IpcScope {
+    /// Creates empty scope.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns token, which is cancelled when scope is dropped. It should be passed to blocking
+    /// readers used by spawned threads, e.g. with
+    /// [`ReadQueue::with_cancellation()`](crate::ReadQueue::with_cancellation).
+    pub fn get_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Checks if scope was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Spawns thread owned by the scope. It gets scope token, which should end it once it's
+    /// cancelled.
+    pub fn spawn<F>(&mut self, consumer: F)
+    where
+        F: FnOnce(CancellationToken) + Send + 'static,
+    {
+        let token = self.get_token();
+
+        // finished threads are removed, so long-lived scope doesn't grow
+        self.threads.retain(|thread| !thread.is_finished());
+        self.threads.push(thread::spawn(move || consumer(token)));
+    }
+
+    /// Takes ownership of handle of background work, which stops when handle is dropped.
+    pub fn adopt<H: Send + 'static>(&mut self, handle: H) {
+        self.handles.push(Box::new(handle));
+    }
+
+    /// Returns number of spawned threads, which are still running.
+    pub fn running(&self) -> usize {
+        self.threads.iter().filter(|thread| !thread.is_finished()).count()
+    }
+
+    /// Cancels scope and waits until all its threads and handles are stopped.
+    pub fn shutdown(self) {
+        // done by drop
+    }
+}
+
+impl Drop for IpcScope {
+    fn drop(&mut self) {
+        self.token.cancel();
+
+        while let Some(handle) = self.handles.pop() {
+            drop(handle);
+        }
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Handle, which counts its drops.
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn drop_stops_threads_and_handles() {
+        let stopped = Arc::new(AtomicUsize::new(0));
+        let mut scope = IpcScope::new();
+
+        for _ in 0..3 {
+            let stopped = Arc::clone(&stopped);
+            scope.spawn(move |token| {
+                while !token.is_cancelled() {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                stopped.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        scope.adopt(Counted(Arc::clone(&stopped)));
+
+        assert_eq!(scope.running(), 3);
+        assert_eq!(stopped.load(Ordering::SeqCst), 0);
+
+        scope.shutdown();
+
+        assert_eq!(stopped.load(Ordering::SeqCst), 4);
+    }
+}
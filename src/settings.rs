@@ -0,0 +1,154 @@
+//! Settings, which may be changed while structures are running, see [`Settings`].
+//!
+//! # Examples
+//! ```ignored
+//! let settings = Settings::new();
+//!
+//! let stream = ReadStream::<Event>::new(pool.clone(), "events", None)
+//!     .with_settings(settings.clone());
+//! let queue = ReadQueue::<Task>::new(pool, "tasks", None).with_settings(settings.clone());
+//!
+//! // e.g. from admin endpoint, next reads use new values
+//! settings.set_timeout(Some(Duration::from_secs(1)));
+//! settings.set_prefetch(Some(50));
+//! ```
+
+use crate::stream::Retention;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Values of settings, [`None`] means that value configured on structure is used.
+#[derive(Debug, Clone, Copy, Default)]
+struct Values {
+    timeout: Option<Duration>,
+    prefetch: Option<usize>,
+    retention: Option<Retention>,
+    linger: Option<Duration>,
+    max_batch: Option<usize>,
+}
+
+/// Shared handle of settings, which can be tuned at runtime (e.g. under load) without rebuilding
+/// structures. Structures attached with `with_settings()` read current values on every operation,
+/// so changes apply to the next read or publish. Settings, which weren't set, fall back to values
+/// configured on structure.
+///
+/// Clones share the same values, so one handle may tune many structures.
+///
+/// | setting     | used by                                                  |
+/// |-------------|----------------------------------------------------------|
+/// | `timeout`   | `ReadStream`, `GroupReadStream`, `ReadQueue`             |
+/// | `prefetch`  | `ReadQueue`                                              |
+/// | `retention` | `WriteStream`, `BufferedWriteStream`                     |
+/// | `linger`    | `BufferedWriteStream`                                    |
+/// | `max_batch` | `BufferedWriteStream`                                    |
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    values: Arc<RwLock<Values>>,
+}
+
+impl Settings {
+    /// Creates settings with no values set, so structures use their own configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets timeout of blocking reads. `Some(Duration::ZERO)` blocks indefinitely, [`None`]
+    /// restores timeout configured on structure.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        self.update(|values| values.timeout = timeout);
+    }
+
+    /// Returns timeout of blocking reads, if it was set.
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.read().timeout
+    }
+
+    /// Sets number of messages fetched by queue with one request, see
+    /// [`ReadQueue::with_prefetch()`](crate::ReadQueue::with_prefetch).
+    pub fn set_prefetch(&self, prefetch: Option<usize>) {
+        self.update(|values| values.prefetch = prefetch);
+    }
+
+    /// Returns number of prefetched messages, if it was set.
+    pub fn get_prefetch(&self) -> Option<usize> {
+        self.read().prefetch
+    }
+
+    /// Sets retention policy applied by stream on publish.
+    pub fn set_retention(&self, retention: Option<Retention>) {
+        self.update(|values| values.retention = retention);
+    }
+
+    /// Returns retention policy, if it was set.
+    pub fn get_retention(&self) -> Option<Retention> {
+        self.read().retention
+    }
+
+    /// Sets max time message waits in the batch of buffered stream.
+    pub fn set_linger(&self, linger: Option<Duration>) {
+        self.update(|values| values.linger = linger);
+    }
+
+    /// Returns max time message waits in the batch, if it was set.
+    pub fn get_linger(&self) -> Option<Duration> {
+        self.read().linger
+    }
+
+    /// Sets batch size, which triggers flush of buffered stream.
+    pub fn set_max_batch(&self, max_batch: Option<usize>) {
+        self.update(|values| values.max_batch = max_batch);
+    }
+
+    /// Returns batch size, which triggers flush, if it was set.
+    pub fn get_max_batch(&self) -> Option<usize> {
+        self.read().max_batch
+    }
+
+    /// Returns copy of current values. Values are plain data, so poisoned lock is ignored.
+    fn read(&self) -> Values {
+        match self.values.read() {
+            Ok(values) => *values,
+            Err(poisoned) => *poisoned.into_inner(),
+        }
+    }
+
+    /// Changes values with given function.
+    fn update(&self, change: impl FnOnce(&mut Values)) {
+        match self.values.write() {
+            Ok(mut values) => change(&mut values),
+            Err(poisoned) => change(&mut poisoned.into_inner()),
+        }
+    }
+}
+
+/// Returns value of setting or configured value if setting isn't set or there are no settings.
+pub(crate) fn resolve<T>(
+    settings: Option<&Settings>,
+    setting: impl FnOnce(&Settings) -> Option<T>,
+    configured: T,
+) -> T {
+    settings.and_then(setting).unwrap_or(configured)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_values() {
+        let settings = Settings::new();
+        let clone = settings.clone();
+
+        assert_eq!(resolve(Some(&clone), Settings::get_prefetch, 1), 1);
+
+        settings.set_prefetch(Some(20));
+        settings.set_timeout(Some(Duration::ZERO));
+
+        assert_eq!(resolve(Some(&clone), Settings::get_prefetch, 1), 20);
+        assert_eq!(clone.get_timeout(), Some(Duration::ZERO));
+        assert_eq!(resolve(None, Settings::get_prefetch, 1), 1);
+
+        settings.set_prefetch(None);
+        assert_eq!(clone.get_prefetch(), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! Sharding of structures across independent redis instances, see [`ShardedPool`].
+//!
+//! # Examples
+//! ```ignored
+//! let pools = ShardedPool::new(vec![("redis-a", pool_a), ("redis-b", pool_b)]);
+//!
+//! let name = format!("tasks:{tenant}");
+//! let queue = WriteQueue::<Task>::new(pools.get_pool(&name).unwrap().clone(), &name);
+//! ```
+
+use crate::helpers::stable_hash;
+use crate::RedisPool;
+use std::sync::Arc;
+
+/// Default number of points of every shard on hash ring.
+const DEFAULT_REPLICAS: u32 = 160;
+
+/// Set of independent redis pools, which structure names (or other partition keys) are assigned
+/// to with consistent hashing. It lets structures scale across several redis instances without
+/// redis cluster.
+///
+/// Every shard is placed on hash ring in many points derived from its name, so shards keep their
+/// keys when pools are reordered and adding or removing shard moves only about `1 / shards` of
+/// keys. Moved structures aren't migrated, their data stays on the previous shard.
+///
+/// All clients must use the same shard names and number of replicas. Clones share the same ring.
+#[derive(Clone)]
+pub struct ShardedPool {
+    /// Shard names and pools
+    shards: Arc<Vec<(String, RedisPool)>>,
+    /// Points of hash ring with index of their shard, sorted by hash
+    ring: Arc<Vec<(u64, usize)>>,
+}
+
+impl ShardedPool {
+    /// Builds sharded pool from pools with their stable names (e.g. redis host names).
+    pub fn new(shards: Vec<(&str, RedisPool)>) -> Self {
+        Self::with_replicas(shards, DEFAULT_REPLICAS)
+    }
+
+    /// Same as [`ShardedPool::new()`](ShardedPool::new), but places every shard on hash ring in
+    /// given number of points (at least one). More points spread keys more evenly.
+    pub fn with_replicas(shards: Vec<(&str, RedisPool)>, replicas: u32) -> Self {
+        let shards: Vec<(String, RedisPool)> = shards
+            .into_iter()
+            .map(|(name, pool)| (name.to_string(), pool))
+            .collect();
+
+        let mut ring: Vec<(u64, usize)> = shards
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (name, _))| {
+                (0..replicas.max(1)).map(move |replica| (point(name, replica), index))
+            })
+            .collect();
+        ring.sort_unstable();
+
+        Self {
+            shards: Arc::new(shards),
+            ring: Arc::new(ring),
+        }
+    }
+
+    /// Returns pool of the shard, which given structure name or partition key is assigned to, or
+    /// [`None`] if there are no shards.
+    pub fn get_pool(&self, key: &str) -> Option<&RedisPool> {
+        self.shard_index(key).map(|index| &self.shards[index].1)
+    }
+
+    /// Returns name of the shard, which given key is assigned to, or [`None`] if there are no
+    /// shards.
+    pub fn get_shard(&self, key: &str) -> Option<&str> {
+        self.shard_index(key).map(|index| self.shards[index].0.as_str())
+    }
+
+    /// Returns all shards with their names, e.g. to run maintenance on every instance.
+    pub fn get_shards(&self) -> impl Iterator<Item = (&str, &RedisPool)> {
+        self.shards.iter().map(|(name, pool)| (name.as_str(), pool))
+    }
+
+    /// Returns index of shard owning the first ring point at or after hash of the key.
+    fn shard_index(&self, key: &str) -> Option<usize> {
+        let hash = ring_hash(key);
+
+        let position = self.ring.partition_point(|(point, _)| *point < hash);
+
+        // ring wraps around to the first point
+        self.ring
+            .get(position)
+            .or(self.ring.first())
+            .map(|(_, index)| *index)
+    }
+}
+
+/// Returns position of given replica of shard on hash ring.
+fn point(shard: &str, replica: u32) -> u64 {
+    ring_hash(&format!("{shard}#{replica}"))
+}
+
+/// Returns position of value on hash ring. FNV-1a hash is mixed with murmur3 finalizer, because
+/// hashes of similar values (e.g. differing only in the last character) are close to each other
+/// otherwise.
+fn ring_hash(value: &str) -> u64 {
+    let mut hash = stable_hash(value);
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use r2d2::Pool;
+    use redis::Client;
+
+    fn build_pool() -> RedisPool {
+        // pool doesn't connect until connection is requested
+        let client = Client::open("redis://127.0.0.1/").unwrap();
+        Pool::builder().build_unchecked(client)
+    }
+
+    fn build_sharded(names: &[&str]) -> ShardedPool {
+        ShardedPool::new(names.iter().map(|name| (*name, build_pool())).collect())
+    }
+
+    #[test]
+    fn spreads_keys_evenly() {
+        let pools = build_sharded(&["a", "b", "c"]);
+
+        let mut counts = [0; 3];
+        for key in 0..3000 {
+            match pools.get_shard(&format!("queue:{key}")).unwrap() {
+                "a" => counts[0] += 1,
+                "b" => counts[1] += 1,
+                _ => counts[2] += 1,
+            }
+        }
+
+        assert!(counts.iter().all(|count| *count > 700), "{counts:?}");
+    }
+
+    #[test]
+    fn adding_shard_moves_only_its_keys() {
+        let before = build_sharded(&["a", "b", "c"]);
+        let after = build_sharded(&["c", "a", "b", "d"]);
+
+        for key in 0..1000 {
+            let key = format!("queue:{key}");
+            let shard = after.get_shard(&key).unwrap();
+
+            assert!(shard == "d" || shard == before.get_shard(&key).unwrap());
+        }
+
+        assert!(build_sharded(&[]).get_pool("queue").is_none());
+    }
+}
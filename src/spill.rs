@@ -0,0 +1,248 @@
+//! Local spill buffer, which keeps queue messages published while redis is unreachable.
+//!
+//! Messages are appended to a local append-only file (one JSON line per message). They are
+//! pushed to their queues by [`SpillBuffer::replay()`](SpillBuffer::replay) or
+//! [`SpillBuffer::recover()`](SpillBuffer::recover) once connectivity returns.
+//!
+//! Delivery is at-least-once. Replay skips duplicated uuids in the file, but message, which was
+//! pushed just before replay failed (e.g. timed out), is pushed again by the next replay.
+//! Consumers should deduplicate messages by uuid when needed.
+//!
+//! # Examples
+//! ```ignored
+//! let spill = SpillBuffer::new("/var/lib/app/queue.spill");
+//! let mut queue = WriteQueue::new(pool.clone(), "tasks").with_spill(spill.clone());
+//!
+//! // recovery task
+//! thread::spawn(move || spill.recover(&pool, Duration::from_secs(1)));
+//! ```
+
+use crate::error::{IpcError, IpcErrorKind};
+use crate::RedisPool;
+use redis::Commands;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Single spilled message.
+#[derive(Serialize, Deserialize)]
+struct SpillEntry {
+    /// Name of the queue, which message should be pushed to
+    queue: String,
+    /// Message uuid, used for deduplication
+    uuid: String,
+    /// Encoded message envelope
+    payload: String,
+}
+
+/// Append-only file buffer for messages, which couldn't be published because redis was
+/// unreachable. See [`WriteQueue::with_spill()`](crate::WriteQueue::with_spill).
+///
+/// Clones share the same file and lock.
+#[derive(Clone)]
+pub struct SpillBuffer {
+    /// Path of the spill file
+    path: Arc<PathBuf>,
+    /// Guards spill file, so appends don't interleave with replay
+    lock: Arc<Mutex<()>>,
+}
+
+impl SpillBuffer {
+    /// Creates buffer stored in file with given path. File is created on first spilled message.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Arc::new(path.into()),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Appends message envelope to the spill file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when file can't be written.
+    pub(crate) fn append(&self, queue: &str, uuid: &str, payload: &str) -> Result<(), IpcError> {
+        let entry = SpillEntry {
+            queue: queue.to_string(),
+            uuid: uuid.to_string(),
+            payload: payload.to_string(),
+        };
+        let line = serde_json::to_string(&entry)?;
+
+        let _guard = self.lock.lock()?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.as_ref())?;
+        writeln!(file, "{line}")?;
+        file.sync_data()?;
+
+        Ok(())
+    }
+
+    /// Returns number of spilled messages waiting for replay.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when file can't be read.
+    pub fn len(&self) -> Result<usize, IpcError> {
+        let _guard = self.lock.lock()?;
+
+        Ok(self.read_entries()?.len())
+    }
+
+    /// Checks if there are no spilled messages.
+    pub fn is_empty(&self) -> Result<bool, IpcError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Pushes spilled messages to their queues in the order they were spilled. Messages with
+    /// already replayed uuid are skipped. When redis becomes unreachable again, replay stops and
+    /// messages not replayed yet are kept in the file.
+    ///
+    /// Returns number of replayed messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on file access or connection failure.
+    pub fn replay(&self, pool: &RedisPool) -> Result<usize, IpcError> {
+        let mut replayed = 0;
+
+        self.drain(pool, &mut replayed)?;
+
+        Ok(replayed)
+    }
+
+    /// Blocking recovery task. Retries [`SpillBuffer::replay()`](SpillBuffer::replay) every
+    /// `retry_interval` until all spilled messages are pushed. May be run in a separate thread.
+    ///
+    /// Returns number of replayed messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on failure other than unreachable redis, e.g. when file
+    /// can't be read.
+    pub fn recover(&self, pool: &RedisPool, retry_interval: Duration) -> Result<usize, IpcError> {
+        let mut replayed = 0;
+
+        loop {
+            match self.drain(pool, &mut replayed) {
+                Ok(()) => return Ok(replayed),
+                Err(error) if matches!(error.kind(), IpcErrorKind::ConnectionFailure) => {
+                    thread::sleep(retry_interval);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Replays spilled messages and increments `replayed` for every pushed one. Messages not
+    /// replayed because of failure are kept in the file.
+    fn drain(&self, pool: &RedisPool, replayed: &mut usize) -> Result<(), IpcError> {
+        let _guard = self.lock.lock()?;
+
+        let entries = self.read_entries()?;
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut seen = HashSet::new();
+
+        for (index, entry) in entries.iter().enumerate() {
+            if seen.contains(entry.uuid.as_str()) {
+                continue;
+            }
+
+            if let Err(error) = push(pool, &entry.queue, &entry.payload) {
+                let remaining: Vec<&SpillEntry> = entries[index..]
+                    .iter()
+                    .filter(|entry| !seen.contains(entry.uuid.as_str()))
+                    .collect();
+                self.write_entries(&remaining)?;
+                return Err(error);
+            }
+
+            seen.insert(entry.uuid.as_str());
+            *replayed += 1;
+        }
+
+        fs::remove_file(self.path.as_ref())?;
+
+        Ok(())
+    }
+
+    /// Reads spilled entries. Missing file means no entries, broken lines (e.g. partially written
+    /// during crash) are skipped.
+    fn read_entries(&self) -> Result<Vec<SpillEntry>, io::Error> {
+        let file = match File::open(self.path.as_ref()) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+
+        let mut entries = Vec::new();
+
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str::<SpillEntry>(&line?) {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Atomically replaces spill file content with given entries.
+    fn write_entries(&self, entries: &[&SpillEntry]) -> Result<(), IpcError> {
+        let tmp_path = self.path.with_extension("tmp");
+
+        let mut file = File::create(&tmp_path)?;
+        for entry in entries {
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+        file.sync_data()?;
+
+        fs::rename(tmp_path, self.path.as_ref())?;
+
+        Ok(())
+    }
+}
+
+/// Pushes encoded envelope to the queue.
+fn push(pool: &RedisPool, queue: &str, payload: &str) -> Result<(), IpcError> {
+    let mut conn = pool.get()?;
+
+    conn.lpush::<&str, &str, ()>(queue, payload)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appended_entries_are_counted() {
+        let path = std::env::temp_dir().join(format!("redis-ipc-{}.spill", std::process::id()));
+        let spill = SpillBuffer::new(&path);
+
+        assert!(spill.is_empty().unwrap());
+
+        spill.append("queue", "1", "{}").unwrap();
+        spill.append("queue", "2", "{}").unwrap();
+
+        assert_eq!(spill.len().unwrap(), 2);
+
+        spill.write_entries(&[]).unwrap();
+
+        assert!(spill.is_empty().unwrap());
+
+        fs::remove_file(path).unwrap();
+    }
+}
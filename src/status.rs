@@ -0,0 +1,183 @@
+//! Convention of reporting status of services. Every instance periodically publishes typed
+//! heartbeat ([`StatusReport`]) on well-known stream `<namespace>:status` with
+//! [`StatusPublisher`] and [`FleetStatus`] aggregates the latest report of every instance.
+//!
+//! # Examples
+//! ```ignored
+//! let publisher = StatusPublisher::new(pool.clone(), "shop", "billing", &hostname);
+//! let handle = publisher.start(Duration::from_secs(5), || (true, queue_len()));
+//!
+//! let fleet = FleetStatus::<usize>::new(pool, "shop", Duration::from_secs(15));
+//! for instance in fleet.current()? {
+//!     println!("{} {}", instance.get_instance(), instance.is_healthy());
+//! }
+//! ```
+
+use crate::background::{self, BackgroundHandle};
+use crate::error::IpcError;
+use crate::stream::{ReadStream, StreamId, WriteStream};
+use crate::RedisPool;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Suffix of the status stream name.
+const STATUS_SUFFIX: &str = ":status";
+
+/// Max number of reports kept in status stream.
+const STATUS_MAX_LEN: u32 = 10_000;
+
+/// Returns name of the status stream of given namespace.
+pub fn status_stream_name(namespace: &str) -> String {
+    format!("{namespace}{STATUS_SUFFIX}")
+}
+
+/// Status of single service instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusReport<Details> {
+    /// Service name
+    service: String,
+    /// Instance name, unique within service
+    instance: String,
+    /// Whether instance works properly
+    healthy: bool,
+    /// Service specific details
+    details: Details,
+    /// Time of the report, set when report is read
+    #[serde(skip)]
+    sent_at: Option<SystemTime>,
+}
+
+impl<Details> StatusReport<Details> {
+    /// Returns service name.
+    pub fn get_service(&self) -> &str {
+        &self.service
+    }
+
+    /// Returns instance name.
+    pub fn get_instance(&self) -> &str {
+        &self.instance
+    }
+
+    /// Checks if instance reported itself as healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy
+    }
+
+    /// Returns service specific details.
+    pub fn get_details(&self) -> &Details {
+        &self.details
+    }
+
+    /// Returns time of the report. It's [`None`] for reports, which weren't read from stream.
+    pub fn sent_at(&self) -> Option<SystemTime> {
+        self.sent_at
+    }
+}
+
+/// Publishes status of service instance on status stream.
+#[derive(Clone)]
+pub struct StatusPublisher<Details: Serialize> {
+    /// Status stream
+    stream: WriteStream<StatusReport<Details>>,
+    /// Service name
+    service: String,
+    /// Instance name
+    instance: String,
+}
+
+impl<Details: Serialize> StatusPublisher<Details> {
+    /// Creates publisher of status of given service instance in given namespace.
+    pub fn new(pool: RedisPool, namespace: &str, service: &str, instance: &str) -> Self {
+        Self {
+            stream: WriteStream::new(pool, &status_stream_name(namespace), STATUS_MAX_LEN),
+            service: service.to_string(),
+            instance: instance.to_string(),
+        }
+    }
+
+    /// Publishes single status report.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn publish(&self, healthy: bool, details: Details) -> Result<StreamId, IpcError> {
+        self.stream.publish(&StatusReport {
+            service: self.service.clone(),
+            instance: self.instance.clone(),
+            healthy,
+            details,
+            sent_at: None,
+        })
+    }
+}
+
+impl<Details: Serialize + Send + 'static> StatusPublisher<Details> {
+    /// Publishes status returned by `probe` (health and details) immediately and then every
+    /// `interval` in background thread, until returned handle is dropped. Failed publishes are
+    /// skipped.
+    pub fn start<F>(self, interval: Duration, probe: F) -> StatusHandle
+    where
+        F: Fn() -> (bool, Details) + Send + 'static,
+    {
+        background::spawn_periodic(interval, move || {
+            let (healthy, details) = probe();
+            let _ = self.publish(healthy, details);
+        })
+    }
+}
+
+/// Handle of reporting started with [`StatusPublisher::start()`](StatusPublisher::start).
+/// Reporting is stopped when handle is dropped.
+pub type StatusHandle = BackgroundHandle;
+
+/// Aggregates status of all service instances of namespace.
+#[derive(Clone)]
+pub struct FleetStatus<Details: DeserializeOwned> {
+    /// Status stream
+    stream: ReadStream<StatusReport<Details>>,
+    /// Age, after which instance without newer report is considered gone
+    stale_after: Duration,
+}
+
+impl<Details: DeserializeOwned> FleetStatus<Details> {
+    /// Creates aggregator of status of given namespace. Instances, which didn't report for
+    /// `stale_after` (usually a few reporting intervals), are considered gone.
+    pub fn new(pool: RedisPool, namespace: &str, stale_after: Duration) -> Self {
+        Self {
+            stream: ReadStream::new(pool, &status_stream_name(namespace), None),
+            stale_after,
+        }
+    }
+
+    /// Returns the latest report of every instance, which reported within `stale_after`, sorted
+    /// by service and instance name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or report decoding error.
+    pub fn current(&self) -> Result<Vec<StatusReport<Details>>, IpcError> {
+        let since = SystemTime::now()
+            .checked_sub(self.stale_after)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let mut latest: HashMap<(String, String), StatusReport<Details>> = HashMap::new();
+
+        // reports are read oldest first, so newer ones replace older ones
+        for message in self.stream.read_since(since) {
+            let message = message?;
+            let sent_at = message.sent_at();
+
+            let mut report = message.into_content();
+            report.sent_at = Some(sent_at);
+
+            latest.insert((report.service.clone(), report.instance.clone()), report);
+        }
+
+        let mut reports: Vec<StatusReport<Details>> = latest.into_values().collect();
+        reports.sort_by(|a, b| (&a.service, &a.instance).cmp(&(&b.service, &b.instance)));
+
+        Ok(reports)
+    }
+}
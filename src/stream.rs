@@ -1,77 +1,225 @@
+use crate::batch::BatchResult;
+use crate::channel::Channel;
+use crate::codec::{
+    encode_stream_content, encode_stream_message_with, AMENDS_FIELD, CONTENT_FIELD, TOMBSTONE_FIELD,
+};
 use crate::error::{IpcError, IpcErrorKind};
-use crate::{OptionalTimeout, RedisPool, Timeout};
-use redis::streams::{StreamMaxlen, StreamRangeReply, StreamReadOptions, StreamReadReply, StreamId as RedisStreamMessage};
+use crate::reconnect::Reconnect;
+use crate::redact::Redactor;
+use crate::settings::{resolve, Settings};
+use crate::watchdog::Watchdog;
+use crate::{OptionalTimeout, RedisConnection, RedisPool, Timeout};
+use redis::streams::{StreamRangeReply, StreamReadOptions, StreamReadReply, StreamId as RedisStreamMessage};
 use redis::Commands;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use std::io;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::sync::{Arc, Mutex};
-use std::time;
-
-/// Actual message content in redis streams is send in only one field as a string, this is the name
-/// of this field.
-const CONTENT_FIELD: &str = "content";
-
-/// Lighter and more robust way of storing rust stream message id.
-///
-/// According to [official redis docs](https://redis.io/docs/latest/develop/data-types/streams/)
-/// id is stored in format: `<millisecondsTime>-<sequenceNumber>`, where `<millisecondsTime>`
-/// and `<sequenceNumber>` are unsigned 64-bit integers.
-pub type StreamId = (u64, u64);
-
-/// Stream message wrapper object (dto)
-pub struct StreamMessage<MessageContent> {
-    /// Message id
-    id: StreamId,
-    /// Custom message content
-    content: MessageContent,
-}
-
-impl<MessageContent> StreamMessage<MessageContent> {
-    pub fn new(id: StreamId, content: MessageContent) -> Self {
-        Self { id, content }
-    }
-
-    pub fn get_content(&self) -> &MessageContent {
-        &self.content
-    }
-
-    pub fn get_id(&self) -> StreamId {
-        self.id
-    }
-}
+use std::time::{self, SystemTime};
+
+#[cfg(feature = "aio")]
+mod aio;
+mod archive;
+#[cfg(feature = "tokio")]
+mod async_bridge;
+mod buffered;
+mod checkpoint;
+mod group;
+mod info;
+mod lag;
+mod multi;
+mod progress;
+mod range;
+mod replay;
+mod retention;
+
+#[cfg(feature = "aio")]
+pub use aio::{AsyncReadStream, AsyncWriteStream};
+#[cfg(feature = "tokio")]
+pub use async_bridge::ReadStreamBridge;
+pub use buffered::BufferedWriteStream;
+use buffered::add_entries;
+use checkpoint::load_checkpoint;
+pub use group::{GroupReadStream, PendingMessage, PendingSummary};
+pub use info::{ConsumerInfo, GroupInfo, StreamInfo};
+pub use lag::ConsumerLag;
+pub use multi::{MultiReadStream, SourcedMessage};
+pub use progress::{CatchUpProgress, CaughtUpHook};
+use progress::CatchUp;
+pub use range::StreamRange;
+pub use replay::StreamReplay;
+pub use retention::Retention;
+pub use crate::codec::{
+    decode_stream_message as decode_message, decode_stream_message_with as decode_message_with,
+    encode_stream_message as encode_message, parse_id, stringify_id, DecodeMode, FieldEncoding,
+    StreamId, StreamMessage,
+};
+
+/// Creates empty stream, if it doesn't exist, by adding and deleting entry. Returns `1` if stream
+/// was created.
+const CREATE_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    return 0
+end
+local id = redis.call('XADD', KEYS[1], '*', 'created', '1')
+redis.call('XDEL', KEYS[1], id)
+return 1
+";
 
 /// Structured projected in order to read messages from stream synchronously one by one.
 /// Messages are cached, connection is not blocked unless `b_next()` is called.
-#[derive(Clone)]
 pub struct ReadStream<MessageContent: DeserializeOwned> {
     /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
     pool: RedisPool,
+    /// Pool used for blocking reads, if it's [`None`] `pool` is used
+    blocking_pool: Option<RedisPool>,
     /// Stream name, used in redis stream
     name: Arc<String>,
     /// Timeout duration, 0 if no timeout
     timeout: Timeout,
-    /// Id of the last read message
-    last_id: Arc<Mutex<StreamId>>,
+    /// Id of the last read message, [`None`] means that only new messages are read
+    last_id: Arc<Mutex<Option<StreamId>>>,
+    /// Watchdog, which tracks blocking reads
+    watchdog: Option<Watchdog>,
+    /// Reconnection policy of blocking reads
+    reconnect: Option<Reconnect>,
+    /// Progress of reading stream backlog
+    catch_up: Arc<Mutex<CatchUp>>,
+    /// Hook called when backlog is read
+    caught_up_hook: Option<CaughtUpHook>,
+    /// Redaction of exported messages
+    redactor: Option<Redactor>,
+    /// Handling of unknown content fields
+    decode_mode: DecodeMode,
+    /// Redis key, which stores reader position
+    checkpoint_key: Option<Arc<String>>,
+    /// Settings tuned at runtime
+    settings: Option<Settings>,
     /// Phantom for message type
     phantom: PhantomData<MessageContent>,
 }
 
+// Implemented manually, because derive would require `MessageContent: Clone`.
+impl<MessageContent: DeserializeOwned> Clone for ReadStream<MessageContent> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            blocking_pool: self.blocking_pool.clone(),
+            name: Arc::clone(&self.name),
+            timeout: self.timeout,
+            last_id: Arc::clone(&self.last_id),
+            watchdog: self.watchdog.clone(),
+            reconnect: self.reconnect.clone(),
+            catch_up: Arc::clone(&self.catch_up),
+            caught_up_hook: self.caught_up_hook.clone(),
+            redactor: self.redactor.clone(),
+            decode_mode: self.decode_mode,
+            checkpoint_key: self.checkpoint_key.clone(),
+            settings: self.settings.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
 impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
     pub fn new(pool: RedisPool, name: &str, timeout: OptionalTimeout) -> Self {
-        let last_id = Arc::new(Mutex::new((0, 0)));
+        let last_id = Arc::new(Mutex::new(None));
         let timeout = timeout.unwrap_or(time::Duration::ZERO);
 
         Self {
             name: Arc::new(name.to_string()),
             pool,
+            blocking_pool: None,
             last_id,
             timeout,
+            watchdog: None,
+            reconnect: None,
+            catch_up: Arc::new(Mutex::new(CatchUp::default())),
+            caught_up_hook: None,
+            redactor: None,
+            decode_mode: DecodeMode::default(),
+            checkpoint_key: None,
+            settings: None,
             phantom: PhantomData,
         }
     }
 
+    /// Builds [`ReadStream`] for given [`Channel`]. Channel name is used as stream name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        pool: RedisPool,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(pool, C::NAME, timeout)
+    }
+
+    /// Sets separate pool for blocking reads ([`ReadStream::b_next()`](ReadStream::b_next)), so
+    /// long blocking requests can't starve short commands sharing the main pool.
+    pub fn with_blocking_pool(mut self, blocking_pool: RedisPool) -> Self {
+        self.blocking_pool = Some(blocking_pool);
+        self
+    }
+
+    /// Sets watchdog, which reports blocking reads stuck far beyond the timeout.
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Sets reader position, so [`ReadStream::b_next()`](ReadStream::b_next) returns messages
+    /// added after message with given id (e.g. checkpoint saved before restart) instead of only
+    /// new ones.
+    pub fn read_from(mut self, id: StreamId) -> Self {
+        // clones made before keep their position and progress
+        self.last_id = Arc::new(Mutex::new(Some(id)));
+        self.catch_up = Arc::new(Mutex::new(CatchUp::default()));
+        self
+    }
+
+    /// Sets reader position to the beginning of stream, so all messages kept in stream are
+    /// replayed.
+    pub fn from_beginning(self) -> Self {
+        self.read_from(StreamId::ZERO)
+    }
+
+    /// Sets reader position, so messages added to stream at or after given time are replayed.
+    pub fn from_time(self, time: SystemTime) -> Self {
+        let id = StreamId::from_system_time(time);
+
+        // reads are exclusive, so position is set to the last possible id before `time`
+        match id.get_timestamp().checked_sub(1) {
+            Some(timestamp) => self.read_from(StreamId::new(timestamp, u64::MAX)),
+            None => self.from_beginning(),
+        }
+    }
+
+    /// Sets reconnection policy, so [`ReadStream::b_next()`](ReadStream::b_next) waits until
+    /// redis is reachable again and re-reads from the last read message instead of returning
+    /// connection error.
+    pub fn with_reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = Some(reconnect);
+        self
+    }
+
+    /// Sets handling of message content fields unknown to `MessageContent`. They are ignored by
+    /// default.
+    pub fn with_decode_mode(mut self, decode_mode: DecodeMode) -> Self {
+        self.decode_mode = decode_mode;
+        self
+    }
+
+    /// Attaches [`Settings`], whose timeout overrides timeout given to
+    /// [`ReadStream::new()`](ReadStream::new) from the next read.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// Returns timeout of the next blocking read.
+    fn current_timeout(&self) -> Timeout {
+        resolve(self.settings.as_ref(), Settings::get_timeout, self.timeout)
+    }
+
     /// Returns current length of the stream or error when it can't be read.
     pub fn len(&self) -> Result<u32, IpcError> {
         let mut conn = self.pool.get()?;
@@ -81,6 +229,11 @@ impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
         Ok(res)
     }
 
+    /// Checks if stream is empty or error when it can't be read.
+    pub fn is_empty(&self) -> Result<bool, IpcError> {
+        Ok(self.len()? == 0)
+    }
+
     /// Returns last message in stream. If no message can be found [`None`](None) is returned.
     ///
     /// # Errors
@@ -92,7 +245,7 @@ impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
         let res = conn
             .xrevrange_count::<&str, &str, &str, u8, StreamRangeReply>(&self.name, "+", "-", 1)?;
 
-        let res = res.ids.get(0);
+        let res = res.ids.first();
 
         // no last message available
         if res.is_none() {
@@ -101,44 +254,166 @@ impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
 
         let res = res.unwrap();
 
-        let parsed = parse_redis_stream_single_message::<MessageContent>(res)?;
+        let parsed = parse_redis_stream_single_message::<MessageContent>(res, self.decode_mode)?;
 
         Ok(Some(parsed))
     }
 
     /// Reads next message in stream. Blocks thread if not available. Waits indefinitely
-    //// or returns error after [`ReadStream::timeout`](ReadStream::timeout) if it was set.
+    /// or returns [`None`] after timeout if it was set.
     ///
     /// Message is queried based on last id read or if not available first message added after this method call
-    /// will be returned.
-    pub fn b_next(&self) -> Result<StreamMessage<MessageContent>, IpcError> {
-        let mut conn = self.pool.get()?;
+    /// will be returned. Reader position may be set with
+    /// [`ReadStream::read_from()`](ReadStream::read_from).
+    pub fn b_next(&self) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+        Ok(self.b_read_many(1)?.into_iter().next())
+    }
+
+    /// Reads up to `max` next messages in stream with one request. Blocks thread until at least
+    /// one message is available. Waits indefinitely or returns empty vector after timeout if it
+    /// was set.
+    ///
+    /// It reduces round trips of high-throughput consumers, see
+    /// [`ReadStream::b_next()`](ReadStream::b_next) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or message decoding error.
+    pub fn b_next_many(&self, max: usize) -> Result<Vec<StreamMessage<MessageContent>>, IpcError> {
+        self.b_read_many(max.max(1))
+    }
+
+    /// Reads next message in stream without blocking. Returns [`None`] when no new message is
+    /// available, e.g. for event loops polling several sources. Position is shared with
+    /// [`ReadStream::b_next()`](ReadStream::b_next), reader of new messages returns messages
+    /// added after its first read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or message decoding error.
+    pub fn try_next(&self) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+        Ok(self.read_once(1, false)?.into_iter().next())
+    }
 
-        let id = {
-            let last_id = self.last_id.lock()?;
+    /// Reads up to `count` messages, returns empty vector on timeout.
+    fn b_read_many(&self, count: usize) -> Result<Vec<StreamMessage<MessageContent>>, IpcError> {
+        // call is tracked until guard is dropped
+        let _watch = self
+            .watchdog
+            .as_ref()
+            .map(|watchdog| watchdog.watch(&self.name, self.current_timeout()));
+
+        match &self.reconnect {
+            Some(reconnect) => reconnect.run(&self.name, None, || self.read_once(count, true)),
+            None => self.read_once(count, true),
+        }
+    }
 
-            if *last_id == (0, 0) {
+    /// Reads up to `count` messages after the last read one. Returns empty vector on timeout or,
+    /// when `block` isn't set, if no message is available.
+    fn read_once(
+        &self,
+        count: usize,
+        block: bool,
+    ) -> Result<Vec<StreamMessage<MessageContent>>, IpcError> {
+        let mut conn = match block {
+            true => self.blocking_pool.as_ref().unwrap_or(&self.pool).get()?,
+            false => self.pool.get()?,
+        };
+
+        let (id, new_only) = {
+            let mut last_id = self.last_id.lock()?;
+
+            if let (None, Some(key)) = (*last_id, &self.checkpoint_key) {
+                *last_id = load_checkpoint(&mut conn, key)?;
+            }
+
+            match *last_id {
+                Some(last_id) => (stringify_id(&last_id), false),
                 // "$" is redis symbol, for first message after xread()
-                String::from("$")
-            } else {
-                stringify_id(&last_id)
+                None if block => (String::from("$"), true),
+                // "$" makes sense only for blocking read, so reader starts after the last message
+                None => {
+                    let head = head_id(&mut conn, &self.name)?;
+                    *last_id = Some(head);
+                    (stringify_id(&head), true)
+                }
             }
         };
 
-        let timeout = usize::try_from(self.timeout.as_millis()).unwrap_or(usize::MAX);
+        if new_only {
+            // reader of new messages has no backlog
+            self.mark_caught_up();
+        }
 
-        let opts = StreamReadOptions::default().count(1).block(timeout);
+        let mut opts = StreamReadOptions::default().count(count);
 
-        let res =
-            conn.xread_options::<&str, &str, StreamReadReply>(&[&self.name], &[&id], &opts)?;
+        if block {
+            let timeout = self.current_timeout().as_millis();
+            opts = opts.block(usize::try_from(timeout).unwrap_or(usize::MAX));
+        }
 
-        let msg = parse_fist_read_reply(&res)?;
+        let res = match &self.checkpoint_key {
+            // previous message is handled, when the next one is requested
+            Some(key) if !new_only => {
+                redis::pipe()
+                    .set(key.as_str(), &id)
+                    .ignore()
+                    .xread_options(&[self.name.as_str()], &[&id], &opts)
+                    .query::<(StreamReadReply,)>(&mut *conn)?
+                    .0
+            }
+            _ => conn.xread_options::<&str, &str, StreamReadReply>(&[&self.name], &[&id], &opts)?,
+        };
+
+        let messages = parse_read_reply(&res, self.decode_mode)?;
+
+        let Some(last) = messages.last().map(StreamMessage::get_id) else {
+            return Ok(messages);
+        };
 
         if let Ok(mut last_id) = self.last_id.lock() {
-            *last_id = msg.get_id();
+            *last_id = Some(last);
         }
 
-        Ok(msg)
+        self.track_read(&mut conn, last, messages.len());
+
+        Ok(messages)
+    }
+}
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Returns blocking iterator over next messages in stream, see [`StreamIter`].
+    pub fn iter(&self) -> StreamIter<'_, MessageContent> {
+        StreamIter { stream: self }
+    }
+}
+
+/// Blocking iterator over messages of [`ReadStream`], which reads them with
+/// [`ReadStream::b_next()`](ReadStream::b_next). Unlike [`ReadQueue`](crate::ReadQueue)
+/// iterator, errors are returned instead of being retried, so caller decides whether to continue.
+///
+/// Iteration ends, when stream timeout elapses without new message. With no timeout it blocks
+/// indefinitely.
+///
+/// # Examples
+///
+/// ```ignored
+/// for message in stream.iter() {
+///     handle(message?);
+/// }
+/// ```
+pub struct StreamIter<'a, MessageContent: DeserializeOwned> {
+    stream: &'a ReadStream<MessageContent>,
+}
+
+impl<MessageContent: DeserializeOwned> Iterator for StreamIter<'_, MessageContent> {
+    type Item = Result<StreamMessage<MessageContent>, IpcError>;
+
+    ///  **This is a blocking method!**. Returns next message, error of the read or [`None`] on
+    /// timeout.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stream.b_next().transpose()
     }
 }
 
@@ -151,8 +426,14 @@ pub struct WriteStream<MessageContent: Serialize> {
     pool: RedisPool,
     /// Stream name, used in redis stream
     name: Arc<String>,
-    /// Max size of stream. Stream will be trimmed to this size
-    max_size: usize,
+    /// Retention policy, stream is trimmed according to it on publish
+    retention: Retention,
+    /// Encoding of message content into stream entry fields
+    encoding: FieldEncoding,
+    /// Whether publish creates missing stream
+    auto_create: bool,
+    /// Settings tuned at runtime
+    settings: Option<Settings>,
     /// Phantom for message content type
     phantom: PhantomData<MessageContent>,
 }
@@ -162,69 +443,304 @@ impl<MessageContent: Serialize> WriteStream<MessageContent> {
         Self {
             name: Arc::new(name.to_string()),
             pool,
-            max_size: max_size as usize,
+            retention: Retention::MaxLen(max_size as usize),
+            encoding: FieldEncoding::default(),
+            auto_create: true,
+            settings: None,
             phantom: PhantomData,
         }
     }
 
+    /// Builds [`WriteStream`] for given [`Channel`]. Channel name is used as stream name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        pool: RedisPool,
+        max_size: u32,
+    ) -> Self {
+        Self::new(pool, C::NAME, max_size)
+    }
+
+    /// Sets encoding of message content into stream entry fields. With
+    /// [`FieldEncoding::Flat`] every content field is stored in separate entry field, so stream
+    /// is readable by non-Rust consumers. Readers detect encoding on their own.
+    pub fn with_field_encoding(mut self, encoding: FieldEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets whether publishing creates missing stream (default). When disabled, publishing to
+    /// missing stream fails with [`NotFound`](IpcErrorKind::NotFound) error, e.g. in deployments,
+    /// where topology has to be provisioned explicitly with
+    /// [`WriteStream::create()`](WriteStream::create).
+    pub fn with_auto_create(mut self, auto_create: bool) -> Self {
+        self.auto_create = auto_create;
+        self
+    }
+
+    /// Attaches [`Settings`], whose retention overrides configured retention policy from the
+    /// next publish. Buffered stream built from this one also takes linger and batch size from
+    /// them.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// Returns retention policy of the next publish.
+    fn current_retention(&self) -> Retention {
+        resolve(self.settings.as_ref(), Settings::get_retention, self.retention)
+    }
+
+    /// Checks if stream exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn exists(&self) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        Ok(conn.exists::<&str, bool>(&self.name)?)
+    }
+
+    /// Creates empty stream, unless it already exists. Returns `false` if it existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn create(&self) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let created = redis::Script::new(CREATE_SCRIPT)
+            .key(self.name.as_str())
+            .invoke::<bool>(&mut *conn)?;
+
+        Ok(created)
+    }
+
+    /// Switches to buffered producer mode, which coalesces published messages into pipelined
+    /// batches. See [`BufferedWriteStream`].
+    ///
+    /// # Arguments
+    ///
+    /// * linger - max time message waits in the batch before it's flushed
+    /// * max_batch - batch size, which triggers immediate flush
+    pub fn buffered(
+        self,
+        linger: time::Duration,
+        max_batch: usize,
+    ) -> BufferedWriteStream<MessageContent> {
+        BufferedWriteStream::new(self, linger, max_batch)
+    }
+
     /// Publishes message on stream. Returns message id or error if publishing was unsuccessful
     /// or result is unknown.
     pub fn publish(&self, message: &MessageContent) -> Result<StreamId, IpcError> {
-        let json = serde_json::to_string(message)?;
+        let fields = encode_stream_content(message, self.encoding)?;
 
+        self.add(&borrow_fields(&fields))
+    }
+
+    /// Publishes message on stream with given id instead of id generated by redis, e.g. when data
+    /// is migrated or id is derived from message for idempotency. Returns message id.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) with [`Conflict`](IpcErrorKind::Conflict) kind when id
+    /// isn't greater than id of the last message in stream (e.g. message was already published)
+    /// or other kind on connection or encoding failure.
+    pub fn publish_with_id(
+        &self,
+        id: StreamId,
+        message: &MessageContent,
+    ) -> Result<StreamId, IpcError> {
+        let fields = encode_stream_content(message, self.encoding)?;
+
+        self.add_with_id(&stringify_id(&id), &borrow_fields(&fields))
+    }
+
+    /// Publishes messages on stream with one pipelined request, so bursts of messages don't pay
+    /// a round trip each. Returns id or error of every message with its index in `messages`, so
+    /// only failed messages may be retried. Messages, which can't be encoded, are reported as
+    /// failed and the rest is published.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when the whole request failed, e.g. on connection failure.
+    /// Pipeline isn't atomic, so some messages may be published even then.
+    pub fn publish_many(
+        &self,
+        messages: &[MessageContent],
+    ) -> Result<BatchResult<StreamId>, IpcError> {
+        let mut result = BatchResult::new();
+        let mut indices = Vec::with_capacity(messages.len());
+        let mut entries = Vec::with_capacity(messages.len());
+
+        for (index, message) in messages.iter().enumerate() {
+            match encode_stream_content(message, self.encoding) {
+                Ok(fields) => {
+                    indices.push(index);
+                    entries.push(fields);
+                }
+                Err(error) => result.push_failure(index, error),
+            }
+        }
+
+        let retention = self.current_retention();
+        let ids = add_entries(&self.pool, &self.name, retention, self.auto_create, &entries)?;
+
+        for (index, id) in indices.into_iter().zip(ids) {
+            match id {
+                Ok(id) => result.push_success(index, id),
+                Err(error) => result.push_failure(index, error),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Publishes already received message on this stream. Stream entry fields other than content
+    /// are preserved, so forwarding doesn't drop metadata added by newer producers. New id is
+    /// generated by redis.
+    pub fn forward(&self, message: &StreamMessage<MessageContent>) -> Result<StreamId, IpcError> {
+        let fields = encode_stream_message_with(message, self.encoding)?;
+
+        self.add(&borrow_fields(&fields))
+    }
+
+    /// Publishes correction of message with given id, which replaces its content, when range is
+    /// read with [`StreamRange::resolve_amendments()`](StreamRange::resolve_amendments). The
+    /// original message is kept, so stream remains an append-only log. Returns correction id.
+    ///
+    /// Readers, which don't resolve amendments, receive correction as a regular message with
+    /// [`StreamMessage::get_amended_id()`](StreamMessage::get_amended_id) set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or encoding failure.
+    pub fn publish_correction(
+        &self,
+        original: StreamId,
+        message: &MessageContent,
+    ) -> Result<StreamId, IpcError> {
+        let mut fields = encode_stream_content(message, self.encoding)?;
+        fields.push((AMENDS_FIELD.to_string(), stringify_id(&original)));
+
+        self.add(&borrow_fields(&fields))
+    }
+
+    /// Publishes tombstone of message with given id, which removes it, when range is read with
+    /// [`StreamRange::resolve_amendments()`](StreamRange::resolve_amendments). Returns tombstone
+    /// id.
+    ///
+    /// Tombstone has `null` content, so readers, which don't resolve amendments, need message
+    /// type accepting it (e.g. [`Option`]) or have to skip messages, which can't be decoded.
+    /// With [`FieldEncoding::Flat`] tombstone has no content fields, so it's decoded only by
+    /// message types, whose fields are all optional.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn publish_tombstone(&self, original: StreamId) -> Result<StreamId, IpcError> {
+        let original = stringify_id(&original);
+
+        let mut fields = vec![(AMENDS_FIELD, original.as_str()), (TOMBSTONE_FIELD, "true")];
+
+        if self.encoding == FieldEncoding::Content {
+            fields.insert(0, (CONTENT_FIELD, "null"));
+        }
+
+        self.add(&fields)
+    }
+
+    /// Deletes message with given id from stream. Returns `false` if there was no such message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn delete(&self, id: StreamId) -> Result<bool, IpcError> {
         let mut conn = self.pool.get()?;
 
-        let res = conn.xadd_maxlen::<&str, u8, &str, &str, String>(
-            &self.name,
-            StreamMaxlen::Approx(self.max_size),
-            b'*',
-            &[(CONTENT_FIELD, &json)],
-        )?;
+        let deleted = conn.xdel::<&str, String, u32>(&self.name, &[stringify_id(&id)])?;
+
+        Ok(deleted > 0)
+    }
+
+    /// Adds entry with given fields to the stream and returns its id.
+    fn add(&self, fields: &[(&str, &str)]) -> Result<StreamId, IpcError> {
+        // "*" lets redis generate id
+        self.add_with_id("*", fields)
+    }
+
+    /// Adds entry with given id and fields to the stream and returns its id.
+    fn add_with_id(&self, id: &str, fields: &[(&str, &str)]) -> Result<StreamId, IpcError> {
+        let mut conn = self.pool.get()?;
 
-        let id = parse_id(&res)?;
+        let res = conn
+            .xadd_options::<&str, &str, &[(&str, &str)], Option<String>>(
+                &self.name,
+                id,
+                fields,
+                &self.current_retention().add_options(self.auto_create),
+            )
+            .map_err(|error| match error.detail() {
+                // redis error is kept as source, so its code may be inspected
+                Some(detail) if detail.contains("equal or smaller") => {
+                    IpcError::new(IpcErrorKind::Conflict, error)
+                }
+                _ => error.into(),
+            })?;
+
+        let id = parse_id(&res.ok_or_else(stream_not_found)?)?;
 
         Ok(id)
     }
 }
 
-/// Stringifies redis id tuple to format `<millisecondsTime>-<sequenceNumber>`. See [`StreamId`].
-fn stringify_id(id: &StreamId) -> String {
-    format!("{}-{}", id.0, id.1)
+/// Returns error of publishing to missing stream, which isn't created automatically.
+fn stream_not_found() -> IpcError {
+    IpcError::new(IpcErrorKind::NotFound, "Stream doesn't exist.")
 }
 
-/// Parses redis stream id (stored in [`String`](String)) from `&str` to tuple.
-/// See [`StreamId`](StreamId) for more information about returned format.
-fn parse_id(id_str: &str) -> Result<StreamId, io::Error> {
-    let parts = id_str.split('-');
+/// Returns id of the last message in stream or [`StreamId::ZERO`] if stream is empty.
+fn head_id(conn: &mut RedisConnection, name: &str) -> Result<StreamId, IpcError> {
+    // "+" and "-" are redis symbols for the last and the first id
+    let res = conn.xrevrange_count::<&str, &str, &str, u8, StreamRangeReply>(name, "+", "-", 1)?;
 
-    let values: Vec<&str> = parts.take(2).collect();
-
-    // Id should have only two parts
-    if let (Ok(timestamp), Ok(seq)) = (values[0].parse(), values[1].parse()) {
-        return Ok((timestamp, seq));
+    match res.ids.first() {
+        Some(head) => Ok(parse_id(&head.id)?),
+        None => Ok(StreamId::ZERO),
     }
+}
 
-    Err(io::Error::new(
-        io::ErrorKind::InvalidInput,
-        "Invalid id string. Please provide \"<millisecondsTime>-<sequenceNumber>\".",
-    ))
+/// Returns stream entry fields as string slices.
+fn borrow_fields(fields: &[(String, String)]) -> Vec<(&str, &str)> {
+    fields
+        .iter()
+        .map(|(field, value)| (field.as_str(), value.as_str()))
+        .collect()
 }
 
-/// Parses [`StreamReadReply`](StreamReadReply) first entry into message.
-fn parse_fist_read_reply<MessageContent: DeserializeOwned>(
+/// Parses all entries of [`StreamReadReply`](StreamReadReply) into messages.
+fn parse_read_reply<MessageContent: DeserializeOwned>(
     rep: &StreamReadReply,
-) -> Result<StreamMessage<MessageContent>, IpcError> {
-    let stream_key = rep.keys.get(0).cloned().ok_or(IpcError::new(
-        IpcErrorKind::InvalidData,
-        "Redis message empty.",
-    ))?;
-
-    let message = stream_key.ids.get(0).cloned().ok_or(IpcError::new(
-        IpcErrorKind::InvalidData,
-        "Redis message has no ids.",
-    ))?;
+    mode: DecodeMode,
+) -> Result<Vec<StreamMessage<MessageContent>>, IpcError> {
+    rep.keys
+        .iter()
+        .flat_map(|key| key.ids.iter())
+        .map(|message| parse_redis_stream_single_message(message, mode))
+        .collect()
+}
 
-    parse_redis_stream_single_message(&message)
+/// Parses [`StreamReadReply`](StreamReadReply) first entry into message. Returns [`None`] when
+/// reply is empty, e.g. after timeout.
+pub(crate) fn parse_first_read_reply<MessageContent: DeserializeOwned>(
+    rep: &StreamReadReply,
+    mode: DecodeMode,
+) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+    rep.keys
+        .first()
+        .and_then(|key| key.ids.first())
+        .map(|message| parse_redis_stream_single_message(message, mode))
+        .transpose()
 }
 
 /// Parses [`RedisStreamMessage` (originally named `StreamId`)](RedisStreamMessage) to crate custom
@@ -233,55 +749,24 @@ fn parse_fist_read_reply<MessageContent: DeserializeOwned>(
 /// # Errors
 ///
 /// Returns [`IpcError`](IpcError) when message id is improper, message doesn't have `content` field
-/// or string in this field can't be parsed to `MessageContent`.
+/// or string in this field can't be parsed to `MessageContent` according to `mode`.
 fn parse_redis_stream_single_message<MessageContent: DeserializeOwned>(
     redis_message: &RedisStreamMessage,
+    mode: DecodeMode,
 ) -> Result<StreamMessage<MessageContent>, IpcError> {
 
-    let id = parse_id(&redis_message.id)?;
-
-    let content: String = redis_message
-        .get(CONTENT_FIELD)
-        .ok_or(IpcError::new(IpcErrorKind::InvalidData, "Invalid message."))?;
-
-    let content = serde_json::from_str::<MessageContent>(&content).map_err(|_| {
-        IpcError::new(
-            IpcErrorKind::InvalidData,
-            "Message content can't be parsed.",
-        )
-    })?;
-
-    Ok(StreamMessage::new(id, content))
+    decode_message_with(&redis_message.id, &entry_fields(redis_message), mode)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn stream_id_decoding() {
-        let example = "123456-789102";
-
-        let result = parse_id(example).unwrap();
-
-        assert_eq!(result, (123456, 789102));
-    }
-
-    #[test]
-    #[should_panic]
-    fn stream_id_decoding_fails_on_too_short() {
-        // improper id
-        let example = "123456";
-
-        let _ = parse_id(example).unwrap();
-    }
-
-    #[test]
-    #[should_panic]
-    fn stream_id_decoding_fails_on_too_bg_num() {
-        // to big number id
-        let example = "999999999999999999999999-123";
-
-        let _ = parse_id(example).unwrap();
-    }
+/// Returns fields of redis stream entry. Fields which can't be read as string are skipped.
+fn entry_fields(redis_message: &RedisStreamMessage) -> HashMap<String, String> {
+    redis_message
+        .map
+        .iter()
+        .filter_map(|(field, value)| {
+            redis::from_redis_value::<String>(value)
+                .ok()
+                .map(|value| (field.clone(), value))
+        })
+        .collect()
 }
@@ -0,0 +1,262 @@
+use super::{encode_message, parse_first_read_reply, parse_redis_stream_single_message, Retention};
+use crate::channel::Channel;
+use crate::codec::{parse_id, stringify_id, DecodeMode, StreamId, StreamMessage, CONTENT_FIELD};
+use crate::error::IpcError;
+use crate::{AsyncRedisConnection, OptionalTimeout, Timeout};
+use futures_core::Stream;
+use redis::streams::{StreamRangeReply, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Pending read of the next stream message.
+type PendingRead<MessageContent> =
+    Pin<Box<dyn Future<Output = Result<Option<StreamMessage<MessageContent>>, IpcError>> + Send>>;
+
+/// Async variant of [`ReadStream`](super::ReadStream). It implements [`Stream`], which yields
+/// results of [`AsyncReadStream::b_next()`](AsyncReadStream::b_next), so messages may be read
+/// with `while let Some(msg) = stream.next().await`.
+///
+/// Blocking reads block the connection until message arrives, so multiplexed connection used by
+/// this stream shouldn't be shared with other structures.
+pub struct AsyncReadStream<MessageContent: DeserializeOwned> {
+    /// redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    conn: AsyncRedisConnection,
+    /// Stream name, used in redis stream
+    name: Arc<String>,
+    /// Timeout duration, 0 if no timeout
+    timeout: Timeout,
+    /// Id of the last read message
+    last_id: StreamId,
+    /// Read started by [`Stream::poll_next()`]
+    pending: Option<PendingRead<MessageContent>>,
+    /// Handling of unknown content fields
+    decode_mode: DecodeMode,
+    /// Phantom for message type
+    phantom: PhantomData<MessageContent>,
+}
+
+// Pending read is boxed, so it is never moved.
+impl<MessageContent: DeserializeOwned> Unpin for AsyncReadStream<MessageContent> {}
+
+impl<MessageContent: DeserializeOwned> AsyncReadStream<MessageContent> {
+    pub fn new(conn: AsyncRedisConnection, name: &str, timeout: OptionalTimeout) -> Self {
+        Self {
+            conn,
+            name: Arc::new(name.to_string()),
+            timeout: timeout.unwrap_or(Duration::ZERO),
+            last_id: StreamId::ZERO,
+            pending: None,
+            decode_mode: DecodeMode::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds [`AsyncReadStream`] for given [`Channel`]. Channel name is used as stream name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        conn: AsyncRedisConnection,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(conn, C::NAME, timeout)
+    }
+
+    /// See [`ReadStream::with_decode_mode()`](super::ReadStream::with_decode_mode).
+    pub fn with_decode_mode(mut self, decode_mode: DecodeMode) -> Self {
+        self.decode_mode = decode_mode;
+        self
+    }
+
+    /// See [`ReadStream::len()`](super::ReadStream::len).
+    pub async fn len(&mut self) -> Result<u32, IpcError> {
+        Ok(self.conn.xlen::<&str, u32>(&self.name).await?)
+    }
+
+    /// See [`ReadStream::is_empty()`](super::ReadStream::is_empty).
+    pub async fn is_empty(&mut self) -> Result<bool, IpcError> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// See [`ReadStream::last()`](super::ReadStream::last).
+    pub async fn last(&mut self) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+        let res = self
+            .conn
+            .xrevrange_count::<&str, &str, &str, u8, StreamRangeReply>(&self.name, "+", "-", 1)
+            .await?;
+
+        res.ids
+            .first()
+            .map(|message| parse_redis_stream_single_message(message, self.decode_mode))
+            .transpose()
+    }
+
+    /// Awaits next message in stream. Returns [`None`] after timeout, if it was set.
+    ///
+    /// Message is queried based on last id read or if not available first message added after
+    /// this method call will be returned.
+    pub async fn b_next(&mut self) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+        let msg = read_next(
+            self.conn.clone(),
+            Arc::clone(&self.name),
+            self.last_id,
+            self.timeout,
+            self.decode_mode,
+        )
+        .await?;
+
+        if let Some(msg) = &msg {
+            self.last_id = msg.get_id();
+        }
+
+        Ok(msg)
+    }
+}
+
+impl<MessageContent> Stream for AsyncReadStream<MessageContent>
+where
+    MessageContent: DeserializeOwned + Send + 'static,
+{
+    type Item = Result<StreamMessage<MessageContent>, IpcError>;
+
+    /// Yields results of [`AsyncReadStream::b_next()`](AsyncReadStream::b_next), reads are
+    /// repeated after timeout. Stream never ends.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let pending = this.pending.get_or_insert_with(|| {
+                Box::pin(read_next(
+                    this.conn.clone(),
+                    Arc::clone(&this.name),
+                    this.last_id,
+                    this.timeout,
+                    this.decode_mode,
+                ))
+            });
+
+            let res = match pending.as_mut().poll(cx) {
+                Poll::Ready(res) => res,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            this.pending = None;
+
+            if let Ok(Some(msg)) = &res {
+                this.last_id = msg.get_id();
+            }
+
+            // `None` means timeout, so the next read is started
+            if let Some(res) = res.transpose() {
+                return Poll::Ready(Some(res));
+            }
+        }
+    }
+}
+
+/// Reads first message after `last_id` or first new message, when `last_id` is
+/// [`StreamId::ZERO`].
+async fn read_next<MessageContent: DeserializeOwned>(
+    mut conn: AsyncRedisConnection,
+    name: Arc<String>,
+    last_id: StreamId,
+    timeout: Timeout,
+    decode_mode: DecodeMode,
+) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+    let id = if last_id == StreamId::ZERO {
+        // "$" is redis symbol, for first message after xread()
+        String::from("$")
+    } else {
+        stringify_id(&last_id)
+    };
+
+    let timeout = usize::try_from(timeout.as_millis()).unwrap_or(usize::MAX);
+
+    let opts = StreamReadOptions::default().count(1).block(timeout);
+
+    let res = conn
+        .xread_options::<&str, &str, StreamReadReply>(&[name.as_str()], &[&id], &opts)
+        .await?;
+
+    parse_first_read_reply(&res, decode_mode)
+}
+
+/// Async variant of [`WriteStream`](super::WriteStream).
+#[derive(Clone)]
+pub struct AsyncWriteStream<MessageContent: Serialize> {
+    /// redis [`MultiplexedConnection`](redis::aio::MultiplexedConnection)
+    conn: AsyncRedisConnection,
+    /// Stream name, used in redis stream
+    name: Arc<String>,
+    /// Retention policy, stream is trimmed according to it on publish
+    retention: Retention,
+    /// Phantom for message content type
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: Serialize> AsyncWriteStream<MessageContent> {
+    pub fn new(conn: AsyncRedisConnection, name: &str, max_size: u32) -> Self {
+        Self {
+            conn,
+            name: Arc::new(name.to_string()),
+            retention: Retention::MaxLen(max_size as usize),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds [`AsyncWriteStream`] for given [`Channel`]. Channel name is used as stream name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        conn: AsyncRedisConnection,
+        max_size: u32,
+    ) -> Self {
+        Self::new(conn, C::NAME, max_size)
+    }
+
+    /// See [`WriteStream::with_retention()`](super::WriteStream::with_retention).
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// See [`WriteStream::publish()`](super::WriteStream::publish).
+    pub async fn publish(&mut self, message: &MessageContent) -> Result<StreamId, IpcError> {
+        let json = serde_json::to_string(message)?;
+
+        self.add(&[(CONTENT_FIELD, &json)]).await
+    }
+
+    /// See [`WriteStream::forward()`](super::WriteStream::forward).
+    pub async fn forward(
+        &mut self,
+        message: &StreamMessage<MessageContent>,
+    ) -> Result<StreamId, IpcError> {
+        let fields = encode_message(message)?;
+
+        let fields: Vec<(&str, &str)> = fields
+            .iter()
+            .map(|(field, value)| (field.as_str(), value.as_str()))
+            .collect();
+
+        self.add(&fields).await
+    }
+
+    /// Adds entry with given fields to the stream and returns its id.
+    async fn add(&mut self, fields: &[(&str, &str)]) -> Result<StreamId, IpcError> {
+        // "*" lets redis generate id
+        let res = self
+            .conn
+            .xadd_options::<&str, &str, &[(&str, &str)], String>(
+                &self.name,
+                "*",
+                fields,
+                &self.retention.add_options(true),
+            )
+            .await?;
+
+        Ok(parse_id(&res)?)
+    }
+}
@@ -0,0 +1,134 @@
+use super::range::{id_bound, DEFAULT_PAGE_SIZE};
+use super::{entry_fields, ReadStream, StreamId, WriteStream};
+use crate::codec::CONTENT_FIELD;
+use crate::error::IpcError;
+use crate::redact::Redactor;
+use redis::streams::StreamRangeReply;
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::ops::RangeBounds;
+
+/// Single exported stream entry, stored as one JSON line.
+#[derive(Serialize, Deserialize)]
+struct ArchiveEntry {
+    /// Entry id in redis format
+    id: String,
+    /// All entry fields, including content and metadata
+    fields: HashMap<String, String>,
+}
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Sets redaction applied to content of exported messages, see
+    /// [`ReadStream::export()`](ReadStream::export). Read messages aren't redacted.
+    pub fn with_redaction(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Writes messages with ids in given `range` to `writer` as NDJSON (one JSON line per
+    /// message), e.g. to archive old messages before stream is trimmed. Ids and all entry fields
+    /// are written, so messages can be restored with
+    /// [`WriteStream::import()`](WriteStream::import). Returns number of exported messages.
+    ///
+    /// Messages are fetched in pages and reader position isn't changed. Their content is
+    /// redacted, if redaction was set with
+    /// [`ReadStream::with_redaction()`](ReadStream::with_redaction).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or when `writer` fails.
+    pub fn export<W: Write>(
+        &self,
+        range: impl RangeBounds<StreamId>,
+        mut writer: W,
+    ) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        // "-" and "+" are redis symbols for the first and the last id
+        let mut start = id_bound(range.start_bound(), "-");
+        let end = id_bound(range.end_bound(), "+");
+        let mut exported = 0;
+
+        loop {
+            let page = conn.xrange_count::<&str, &str, &str, usize, StreamRangeReply>(
+                &self.name,
+                &start,
+                &end,
+                DEFAULT_PAGE_SIZE,
+            )?;
+
+            for message in &page.ids {
+                let mut fields = entry_fields(message);
+
+                if let (Some(redactor), Some(content)) =
+                    (&self.redactor, fields.get_mut(CONTENT_FIELD))
+                {
+                    let mut value = serde_json::from_str::<Value>(content)?;
+                    redactor.redact_value(&mut value);
+                    *content = value.to_string();
+                }
+
+                let entry = ArchiveEntry {
+                    id: message.id.clone(),
+                    fields,
+                };
+                writeln!(writer, "{}", serde_json::to_string(&entry)?)?;
+            }
+            exported += page.ids.len();
+
+            match page.ids.last() {
+                Some(last) if page.ids.len() == DEFAULT_PAGE_SIZE => {
+                    start = format!("({}", last.id);
+                }
+                _ => break,
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(exported)
+    }
+}
+
+impl<MessageContent: Serialize> WriteStream<MessageContent> {
+    /// Adds messages exported with [`ReadStream::export()`](ReadStream::export) to the stream,
+    /// keeping their ids and fields. Returns number of imported messages.
+    ///
+    /// Ids have to be greater than id of the last message in stream, so messages are usually
+    /// imported to a new stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure, when `reader` fails or line isn't
+    /// exported message. Returns error with [`Conflict`](crate::error::IpcErrorKind::Conflict)
+    /// kind when id isn't greater than the last one. Messages imported before error are kept in
+    /// stream.
+    pub fn import<R: BufRead>(&self, reader: R) -> Result<usize, IpcError> {
+        let mut imported = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str::<ArchiveEntry>(&line)?;
+
+            let fields: Vec<(&str, &str)> = entry
+                .fields
+                .iter()
+                .map(|(field, value)| (field.as_str(), value.as_str()))
+                .collect();
+
+            self.add_with_id(&entry.id, &fields)?;
+            imported += 1;
+        }
+
+        Ok(imported)
+    }
+}
@@ -0,0 +1,68 @@
+use super::{ReadStream, StreamMessage};
+use crate::error::IpcError;
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Number of messages read ahead by the bridge thread.
+const BRIDGE_CAPACITY: usize = 64;
+/// Max time the bridge thread blocks on redis, after which it checks if bridge was dropped.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Pause after failed read, so unreachable redis doesn't cause busy loop.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// [`Stream`] of messages read by [`ReadStream::b_next()`](ReadStream::b_next) on a background
+/// thread. Built with [`ReadStream::into_async_bridge()`](ReadStream::into_async_bridge).
+///
+/// Read errors are yielded as items and reading continues. The thread stops shortly after the
+/// bridge is dropped.
+pub struct ReadStreamBridge<MessageContent> {
+    /// Messages sent by the bridge thread
+    receiver: mpsc::Receiver<Result<StreamMessage<MessageContent>, IpcError>>,
+}
+
+impl<MessageContent> Stream for ReadStreamBridge<MessageContent> {
+    type Item = Result<StreamMessage<MessageContent>, IpcError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl<MessageContent: DeserializeOwned + Send + 'static> ReadStream<MessageContent> {
+    /// Moves stream to a background thread, which reads messages with blocking reads and
+    /// exposes them as async [`Stream`]. It lets mostly sync code hand a stream to async
+    /// consumers. Stream timeout is ignored, reads never time out.
+    pub fn into_async_bridge(mut self) -> ReadStreamBridge<MessageContent> {
+        let (sender, receiver) = mpsc::channel(BRIDGE_CAPACITY);
+
+        self.timeout = POLL_INTERVAL;
+
+        thread::spawn(move || {
+            while !sender.is_closed() {
+                let res = match self.b_next() {
+                    Ok(Some(msg)) => Ok(msg),
+                    // timeout
+                    Ok(None) => continue,
+                    Err(error) => Err(error),
+                };
+
+                let failed = res.is_err();
+
+                if sender.blocking_send(res).is_err() {
+                    break;
+                }
+
+                if failed {
+                    thread::sleep(RETRY_INTERVAL);
+                }
+            }
+        });
+
+        ReadStreamBridge { receiver }
+    }
+}
@@ -0,0 +1,325 @@
+use super::{stream_not_found, Retention, WriteStream};
+use crate::codec::{encode_stream_content, parse_id, FieldEncoding, StreamId};
+use crate::error::IpcError;
+use crate::settings::{resolve, Settings};
+use crate::RedisPool;
+use serde::Serialize;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Messages waiting for flush.
+#[derive(Default)]
+struct Batch {
+    /// Stream entry fields of encoded messages
+    entries: Vec<Vec<(String, String)>>,
+    /// Time when the first message of the batch was published
+    since: Option<Instant>,
+    /// Set when stream is dropped, flusher flushes remaining messages and stops
+    closed: bool,
+}
+
+/// State shared with the flusher thread.
+struct Shared {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Stream name
+    name: Arc<String>,
+    /// Retention policy of stream
+    retention: Retention,
+    /// Encoding of message content into stream entry fields
+    encoding: FieldEncoding,
+    /// Creates stream, if it doesn't exist
+    auto_create: bool,
+    /// Max time message waits in the batch
+    linger: Duration,
+    /// Batch size, which triggers flush
+    max_batch: usize,
+    /// Settings tuned at runtime, they override retention, linger and batch size
+    settings: Option<Settings>,
+    batch: Mutex<Batch>,
+    /// Notified on publish and close
+    changed: Condvar,
+    /// Held while batch is written, so batches are written in publish order
+    writing: Mutex<()>,
+    /// Error of the last background flush, returned by next publish
+    error: Mutex<Option<IpcError>>,
+}
+
+/// Buffered producer mode of [`WriteStream`](super::WriteStream). Published messages are
+/// coalesced into batches, which are written with pipelined `XADD`. Batch is flushed when it
+/// reaches `max_batch` messages or its first message waits for `linger`, whichever comes first.
+///
+/// Messages, which weren't written because of failure, are kept and retried with the next
+/// flush. Remaining messages are flushed on drop, but they are lost if that fails. Use
+/// [`BufferedWriteStream::close()`] to get the error instead and
+/// [`BufferedWriteStream::flush()`] to flush explicitly.
+///
+/// Built with [`WriteStream::buffered()`](super::WriteStream::buffered).
+pub struct BufferedWriteStream<MessageContent: Serialize> {
+    shared: Arc<Shared>,
+    /// Background thread, which flushes batches after linger time
+    flusher: Option<JoinHandle<()>>,
+    /// Phantom for message content type
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: Serialize> BufferedWriteStream<MessageContent> {
+    /// Builds buffered producer, which publishes to given stream with its configuration.
+    pub(super) fn new(
+        stream: WriteStream<MessageContent>,
+        linger: Duration,
+        max_batch: usize,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            pool: stream.pool,
+            name: stream.name,
+            retention: stream.retention,
+            encoding: stream.encoding,
+            auto_create: stream.auto_create,
+            linger,
+            max_batch: max_batch.max(1),
+            settings: stream.settings,
+            batch: Mutex::new(Batch::default()),
+            changed: Condvar::new(),
+            writing: Mutex::new(()),
+            error: Mutex::new(None),
+        });
+
+        let flusher = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || run_flusher(&shared))
+        };
+
+        Self {
+            shared,
+            flusher: Some(flusher),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Adds message to the current batch. Message id isn't known until batch is flushed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when message can't be serialized or previous background
+    /// flush failed, message isn't added then. Messages of the failed batch are kept and
+    /// retried.
+    pub fn publish(&self, message: &MessageContent) -> Result<(), IpcError> {
+        if let Some(error) = self.shared.error.lock()?.take() {
+            return Err(error);
+        }
+
+        let fields = encode_stream_content(message, self.shared.encoding)?;
+
+        let mut batch = self.shared.batch.lock()?;
+        batch.entries.push(fields);
+        batch.since.get_or_insert_with(Instant::now);
+
+        self.shared.changed.notify_one();
+
+        Ok(())
+    }
+
+    /// Writes current batch (including messages kept after failed flush) immediately and
+    /// returns ids of written messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure, messages are kept in the batch.
+    pub fn flush(&self) -> Result<Vec<StreamId>, IpcError> {
+        flush_batch(&self.shared)
+    }
+
+    /// Stops background flusher and writes remaining messages. Unlike drop, it returns error
+    /// when they can't be written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure, remaining messages are lost.
+    pub fn close(mut self) -> Result<(), IpcError> {
+        self.stop_flusher();
+
+        flush_batch(&self.shared).map(|_| ())
+    }
+
+    /// Stops flusher thread, which flushes remaining messages once before it stops.
+    fn stop_flusher(&mut self) {
+        if let Ok(mut batch) = self.shared.batch.lock() {
+            batch.closed = true;
+        }
+        self.shared.changed.notify_one();
+
+        if let Some(flusher) = self.flusher.take() {
+            let _ = flusher.join();
+        }
+    }
+}
+
+impl<MessageContent: Serialize> Drop for BufferedWriteStream<MessageContent> {
+    fn drop(&mut self) {
+        self.stop_flusher();
+    }
+}
+
+/// Flushes batches after linger time or when they are full, until stream is closed.
+fn run_flusher(shared: &Shared) {
+    let Ok(mut batch) = shared.batch.lock() else {
+        return;
+    };
+    // set after failed flush, so full batch isn't retried before linger time
+    let mut failed = false;
+
+    loop {
+        while batch.entries.is_empty() && !batch.closed {
+            batch = match shared.changed.wait(batch) {
+                Ok(batch) => batch,
+                Err(_) => return,
+            };
+        }
+
+        if batch.entries.is_empty() {
+            // closed and nothing left to flush
+            return;
+        }
+
+        let settings = shared.settings.as_ref();
+        let linger = resolve(settings, Settings::get_linger, shared.linger);
+        let max_batch = resolve(settings, Settings::get_max_batch, shared.max_batch).max(1);
+
+        let deadline = batch.since.unwrap_or_else(Instant::now) + linger;
+
+        while !batch.closed && (failed || batch.entries.len() < max_batch) {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+
+            batch = match shared.changed.wait_timeout(batch, deadline - now) {
+                Ok((batch, _)) => batch,
+                Err(_) => return,
+            };
+        }
+
+        let closed = batch.closed;
+        drop(batch);
+
+        let result = flush_batch(shared);
+        failed = result.is_err();
+
+        if let Err(error) = result {
+            if let Ok(mut last_error) = shared.error.lock() {
+                *last_error = Some(error);
+            }
+
+            if closed {
+                // failed messages aren't retried after close
+                return;
+            }
+        }
+
+        batch = match shared.batch.lock() {
+            Ok(batch) => batch,
+            Err(_) => return,
+        };
+    }
+}
+
+/// Takes messages from the batch and resets its start time.
+fn take_entries(batch: &mut Batch) -> Vec<Vec<(String, String)>> {
+    batch.since = None;
+    mem::take(&mut batch.entries)
+}
+
+/// Writes messages of the batch with pipelined `XADD` and returns their ids. Writes are
+/// serialized, so messages are added in publish order. Messages, which weren't written, are put
+/// back in front of the batch and retried after linger time.
+fn flush_batch(shared: &Shared) -> Result<Vec<StreamId>, IpcError> {
+    let _writing = shared.writing.lock()?;
+
+    let entries = take_entries(&mut *shared.batch.lock()?);
+
+    let retention = resolve(shared.settings.as_ref(), Settings::get_retention, shared.retention);
+
+    let results =
+        match add_entries(&shared.pool, &shared.name, retention, shared.auto_create, &entries) {
+            Ok(results) => results,
+            Err(error) => {
+                // result is unknown, so the whole batch is kept, even if some messages were added
+                restore_entries(shared, entries)?;
+                return Err(error);
+            }
+        };
+
+    let mut ids = Vec::with_capacity(entries.len());
+    let mut failed = Vec::new();
+    let mut first_error = None;
+
+    for (fields, result) in entries.into_iter().zip(results) {
+        match result {
+            Ok(id) => ids.push(id),
+            Err(error) => {
+                failed.push(fields);
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+
+    if let Some(error) = first_error {
+        restore_entries(shared, failed)?;
+        return Err(error);
+    }
+
+    // kept messages of the failed background flush are written now
+    shared.error.lock()?.take();
+
+    Ok(ids)
+}
+
+/// Puts messages, which weren't written, back in front of the batch.
+fn restore_entries(shared: &Shared, entries: Vec<Vec<(String, String)>>) -> Result<(), IpcError> {
+    let mut batch = shared.batch.lock()?;
+
+    batch.entries.splice(0..0, entries);
+    // retried after linger time
+    batch.since = Some(Instant::now());
+
+    Ok(())
+}
+
+/// Writes stream entries with given fields with pipelined `XADD` and returns result of every
+/// entry. Missing stream is created only when `auto_create` is set. Error is returned only when
+/// the whole request failed.
+pub(super) fn add_entries(
+    pool: &RedisPool,
+    name: &str,
+    retention: Retention,
+    auto_create: bool,
+    entries: &[Vec<(String, String)>],
+) -> Result<Vec<Result<StreamId, IpcError>>, IpcError> {
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut pipe = redis::pipe();
+
+    let options = retention.add_options(auto_create);
+
+    for fields in entries {
+        // "*" lets redis generate id
+        pipe.xadd_options(name, "*", fields, &options);
+    }
+
+    let mut conn = pool.get()?;
+    let ids = pipe.query::<Vec<Option<String>>>(&mut *conn)?;
+
+    Ok(ids
+        .iter()
+        .map(|id| match id {
+            Some(id) => Ok(parse_id(id)?),
+            None => Err(stream_not_found()),
+        })
+        .collect())
+}
@@ -0,0 +1,50 @@
+use super::{stringify_id, ReadStream, StreamId};
+use crate::error::IpcError;
+use crate::RedisConnection;
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Stores reader position under given redis key, so reader restarted with the same key
+    /// resumes after the last handled message instead of reading only new ones. Position set with
+    /// [`ReadStream::read_from()`](ReadStream::read_from) takes precedence over stored one.
+    ///
+    /// Position is saved with the next read, because message is assumed handled once the next
+    /// one is requested. Message handled just before crash may be read again.
+    pub fn with_checkpoint(mut self, key: &str) -> Self {
+        self.checkpoint_key = Some(Arc::new(key.to_string()));
+        self
+    }
+
+    /// Saves current reader position under checkpoint key immediately, e.g. on graceful
+    /// shutdown. Does nothing if checkpoint isn't set or nothing was read yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn save_checkpoint(&self) -> Result<(), IpcError> {
+        let Some(key) = &self.checkpoint_key else {
+            return Ok(());
+        };
+
+        let Some(last_id) = *self.last_id.lock()? else {
+            return Ok(());
+        };
+
+        let mut conn = self.pool.get()?;
+        conn.set::<&str, String, ()>(key, stringify_id(&last_id))?;
+
+        Ok(())
+    }
+}
+
+/// Returns position stored under checkpoint key or [`None`] if it wasn't stored yet.
+pub(super) fn load_checkpoint(
+    conn: &mut RedisConnection,
+    key: &str,
+) -> Result<Option<StreamId>, IpcError> {
+    let stored = conn.get::<&str, Option<String>>(key)?;
+
+    Ok(stored.map(|id| id.parse()).transpose()?)
+}
@@ -0,0 +1,471 @@
+use super::lag::lag_after;
+use super::{
+    parse_first_read_reply, parse_id, parse_redis_stream_single_message, stringify_id,
+    ConsumerLag, DecodeMode, StreamId, StreamMessage,
+};
+use crate::channel::Channel;
+use crate::error::IpcError;
+use crate::reconnect::Reconnect;
+use crate::settings::{resolve, Settings};
+use crate::watchdog::Watchdog;
+use crate::{OptionalTimeout, RedisPool, Timeout};
+use redis::streams::{
+    StreamAutoClaimOptions, StreamAutoClaimReply, StreamInfoGroupsReply, StreamPendingCountReply,
+    StreamPendingReply, StreamReadOptions, StreamReadReply,
+};
+use redis::{Commands, RedisResult};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time;
+
+/// Stream reader, which belongs to redis consumer group. Every message is delivered to only one
+/// consumer of the group, so multiple worker processes may share a stream without re-reading
+/// each other's messages. Messages stay pending until they are acknowledged with
+/// [`GroupReadStream::ack()`](GroupReadStream::ack).
+///
+/// Group is created on first read if it doesn't exist. New group starts with messages added
+/// after its creation.
+#[derive(Clone)]
+pub struct GroupReadStream<MessageContent: DeserializeOwned> {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Stream name, used in redis stream
+    name: Arc<String>,
+    /// Consumer group name
+    group: Arc<String>,
+    /// Consumer name, unique within the group
+    consumer: Arc<String>,
+    /// Timeout duration, 0 if no timeout
+    timeout: Timeout,
+    /// Watchdog, which tracks blocking reads
+    watchdog: Option<Watchdog>,
+    /// Reconnection policy of blocking reads
+    reconnect: Option<Reconnect>,
+    /// Handling of unknown content fields
+    decode_mode: DecodeMode,
+    /// Settings tuned at runtime
+    settings: Option<Settings>,
+    /// Phantom for message type
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: DeserializeOwned> GroupReadStream<MessageContent> {
+    /// Creates reader of stream `name` as `consumer` of `group`. Consumer name should be unique
+    /// and stable for worker process, because pending messages are assigned to it.
+    pub fn new(
+        pool: RedisPool,
+        name: &str,
+        group: &str,
+        consumer: &str,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self {
+            pool,
+            name: Arc::new(name.to_string()),
+            group: Arc::new(group.to_string()),
+            consumer: Arc::new(consumer.to_string()),
+            timeout: timeout.unwrap_or(time::Duration::ZERO),
+            watchdog: None,
+            reconnect: None,
+            decode_mode: DecodeMode::default(),
+            settings: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builds [`GroupReadStream`] for given [`Channel`]. Channel name is used as stream name.
+    pub fn for_channel<C: Channel<Message = MessageContent>>(
+        pool: RedisPool,
+        group: &str,
+        consumer: &str,
+        timeout: OptionalTimeout,
+    ) -> Self {
+        Self::new(pool, C::NAME, group, consumer, timeout)
+    }
+
+    /// Sets watchdog, which reports blocking reads stuck far beyond the timeout.
+    pub fn with_watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Sets reconnection policy, so [`GroupReadStream::b_next()`](GroupReadStream::b_next) waits
+    /// until redis is reachable again (and re-creates the group if it was lost) instead of
+    /// returning connection error.
+    pub fn with_reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = Some(reconnect);
+        self
+    }
+
+    /// Sets handling of message content fields unknown to `MessageContent`. They are ignored by
+    /// default.
+    pub fn with_decode_mode(mut self, decode_mode: DecodeMode) -> Self {
+        self.decode_mode = decode_mode;
+        self
+    }
+
+    /// Attaches [`Settings`], whose timeout overrides timeout given to
+    /// [`GroupReadStream::new()`](GroupReadStream::new) from the next read.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// Returns consumer group name.
+    pub fn get_group(&self) -> &str {
+        &self.group
+    }
+
+    /// Returns consumer name.
+    pub fn get_consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    /// Reads next message not delivered to any consumer of the group. Blocks thread if not
+    /// available. Waits indefinitely or returns [`None`] after timeout if it was set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or message decoding error.
+    pub fn b_next(&self) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+        let timeout = resolve(self.settings.as_ref(), Settings::get_timeout, self.timeout);
+
+        let opts = StreamReadOptions::default()
+            .group(self.group.as_str(), self.consumer.as_str())
+            .count(1)
+            .block(usize::try_from(timeout.as_millis()).unwrap_or(usize::MAX));
+
+        // call is tracked until guard is dropped
+        let _watch = self
+            .watchdog
+            .as_ref()
+            .map(|watchdog| watchdog.watch(&self.name, timeout));
+
+        match &self.reconnect {
+            Some(reconnect) => reconnect.run(&self.name, None, || self.read_once(&opts)),
+            None => self.read_once(&opts),
+        }
+    }
+
+    /// Reads next message of the group, creates the group if it doesn't exist.
+    fn read_once(
+        &self,
+        opts: &StreamReadOptions,
+    ) -> Result<Option<StreamMessage<MessageContent>>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let res = match self.read_group(&mut conn, opts) {
+            Err(error) if error.code() == Some("NOGROUP") => {
+                self.create_group(&mut conn)?;
+                self.read_group(&mut conn, opts)?
+            }
+            res => res?,
+        };
+
+        parse_first_read_reply(&res, self.decode_mode)
+    }
+
+    /// Acknowledges message with given id, so it's removed from pending messages of the group.
+    ///
+    /// Returns `false` if message wasn't pending, e.g. it was already acknowledged.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn ack(&self, id: StreamId) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let acked =
+            conn.xack::<&str, &str, String, u32>(&self.name, &self.group, &[stringify_id(&id)])?;
+
+        Ok(acked > 0)
+    }
+
+    /// Acknowledges message with given id and deletes it from stream in one atomic request, for
+    /// workflows where processed messages must be removed, not only trimmed later. Message is
+    /// deleted for all groups of the stream.
+    ///
+    /// Returns `false` if message wasn't pending, e.g. it was already acknowledged. It is deleted
+    /// anyway.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn ack_and_delete(&self, id: StreamId) -> Result<bool, IpcError> {
+        let mut conn = self.pool.get()?;
+        let id = stringify_id(&id);
+
+        let (acked, _) = redis::pipe()
+            .atomic()
+            .xack(self.name.as_str(), self.group.as_str(), &[&id])
+            .xdel(self.name.as_str(), &[&id])
+            .query::<(u32, u32)>(&mut *conn)?;
+
+        Ok(acked > 0)
+    }
+
+    /// Claims up to `count` messages, which are pending (delivered, but not acknowledged) for at
+    /// least `min_idle`, e.g. because consumer, which read them, crashed. Claimed messages are
+    /// assigned to this consumer and have to be acknowledged with
+    /// [`GroupReadStream::ack()`](GroupReadStream::ack).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or message decoding error.
+    pub fn claim_stale(
+        &self,
+        min_idle: time::Duration,
+        count: usize,
+    ) -> Result<Vec<StreamMessage<MessageContent>>, IpcError> {
+        let min_idle = u64::try_from(min_idle.as_millis()).unwrap_or(u64::MAX);
+
+        let mut conn = self.pool.get()?;
+
+        let mut claimed = Vec::new();
+        // "0-0" is both the first and the last cursor of XAUTOCLAIM
+        let mut cursor = String::from("0-0");
+
+        while claimed.len() < count {
+            let opts = StreamAutoClaimOptions::default().count(count - claimed.len());
+
+            let res = conn.xautoclaim_options::<_, _, _, _, _, StreamAutoClaimReply>(
+                self.name.as_str(),
+                self.group.as_str(),
+                self.consumer.as_str(),
+                min_idle,
+                &cursor,
+                opts,
+            );
+
+            let res = match res {
+                // group doesn't exist yet, so nothing is pending
+                Err(error) if error.code() == Some("NOGROUP") => break,
+                res => res?,
+            };
+
+            for message in &res.claimed {
+                claimed.push(parse_redis_stream_single_message(message, self.decode_mode)?);
+            }
+
+            if res.next_stream_id == "0-0" {
+                break;
+            }
+
+            cursor = res.next_stream_id;
+        }
+
+        Ok(claimed)
+    }
+
+    /// Returns summary of messages pending (delivered, but not acknowledged) in the group.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or invalid message id.
+    pub fn pending_summary(&self) -> Result<PendingSummary, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let res = match conn.xpending::<&str, &str, StreamPendingReply>(&self.name, &self.group) {
+            // group doesn't exist yet, so nothing is pending
+            Err(error) if error.code() == Some("NOGROUP") => return Ok(PendingSummary::default()),
+            res => res?,
+        };
+
+        let StreamPendingReply::Data(data) = res else {
+            return Ok(PendingSummary::default());
+        };
+
+        Ok(PendingSummary {
+            count: data.count,
+            oldest_id: Some(parse_id(&data.start_id)?),
+            newest_id: Some(parse_id(&data.end_id)?),
+            consumers: data
+                .consumers
+                .into_iter()
+                .map(|consumer| (consumer.name, consumer.pending))
+                .collect(),
+        })
+    }
+
+    /// Returns how far the group is behind the stream head, i.e. messages not delivered to any
+    /// consumer of the group yet. Pending messages aren't counted. Group, which doesn't exist
+    /// yet, isn't behind.
+    ///
+    /// Undelivered messages are counted by redis, which takes time proportional to their number.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or when stream doesn't exist.
+    pub fn lag(&self) -> Result<ConsumerLag, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let groups = conn.xinfo_groups::<&str, StreamInfoGroupsReply>(&self.name)?.groups;
+
+        let Some(group) = groups.into_iter().find(|group| group.name == *self.group) else {
+            return Ok(ConsumerLag::default());
+        };
+
+        lag_after(&mut conn, &self.name, parse_id(&group.last_delivered_id)?)
+    }
+
+    /// Returns up to `count` pending messages of the group, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or invalid message id.
+    pub fn pending(&self, count: usize) -> Result<Vec<PendingMessage>, IpcError> {
+        self.query_pending(count, None)
+    }
+
+    /// Same as [`GroupReadStream::pending()`](GroupReadStream::pending), but returns only
+    /// messages pending for given consumer.
+    pub fn pending_of(
+        &self,
+        consumer: &str,
+        count: usize,
+    ) -> Result<Vec<PendingMessage>, IpcError> {
+        self.query_pending(count, Some(consumer))
+    }
+
+    fn query_pending(
+        &self,
+        count: usize,
+        consumer: Option<&str>,
+    ) -> Result<Vec<PendingMessage>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        // "-" and "+" are redis symbols for the first and the last id
+        let res = match consumer {
+            Some(consumer) => conn
+                .xpending_consumer_count::<_, _, _, _, _, _, StreamPendingCountReply>(
+                    self.name.as_str(),
+                    self.group.as_str(),
+                    "-",
+                    "+",
+                    count,
+                    consumer,
+                ),
+            None => conn.xpending_count::<_, _, _, _, _, StreamPendingCountReply>(
+                self.name.as_str(),
+                self.group.as_str(),
+                "-",
+                "+",
+                count,
+            ),
+        };
+
+        let res = match res {
+            // group doesn't exist yet, so nothing is pending
+            Err(error) if error.code() == Some("NOGROUP") => return Ok(Vec::new()),
+            res => res?,
+        };
+
+        res.ids
+            .into_iter()
+            .map(|pending| {
+                Ok(PendingMessage {
+                    id: parse_id(&pending.id)?,
+                    consumer: pending.consumer,
+                    idle: time::Duration::from_millis(pending.last_delivered_ms as u64),
+                    deliveries: pending.times_delivered,
+                })
+            })
+            .collect()
+    }
+
+    fn read_group(
+        &self,
+        conn: &mut redis::Connection,
+        opts: &StreamReadOptions,
+    ) -> RedisResult<StreamReadReply> {
+        // ">" is redis symbol for messages never delivered to other consumers
+        conn.xread_options::<&str, &str, StreamReadReply>(&[&self.name], &[">"], opts)
+    }
+
+    /// Creates consumer group (and stream if it doesn't exist). Group created concurrently by
+    /// another consumer isn't an error.
+    fn create_group(&self, conn: &mut redis::Connection) -> Result<(), IpcError> {
+        // "$" - group starts with messages added after its creation
+        let res: RedisResult<()> = conn.xgroup_create_mkstream(&*self.name, &*self.group, "$");
+
+        match res {
+            Err(error) if error.code() != Some("BUSYGROUP") => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Summary of messages pending in consumer group, see
+/// [`GroupReadStream::pending_summary()`](GroupReadStream::pending_summary).
+#[derive(Debug, Clone, Default)]
+pub struct PendingSummary {
+    /// Number of pending messages
+    count: usize,
+    /// Id of the oldest pending message
+    oldest_id: Option<StreamId>,
+    /// Id of the newest pending message
+    newest_id: Option<StreamId>,
+    /// Number of pending messages by consumer name
+    consumers: HashMap<String, usize>,
+}
+
+impl PendingSummary {
+    /// Returns number of pending messages.
+    pub fn get_count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns id of the oldest pending message or [`None`] if nothing is pending.
+    pub fn get_oldest_id(&self) -> Option<StreamId> {
+        self.oldest_id
+    }
+
+    /// Returns id of the newest pending message or [`None`] if nothing is pending.
+    pub fn get_newest_id(&self) -> Option<StreamId> {
+        self.newest_id
+    }
+
+    /// Returns number of pending messages by consumer name. Consumers with no pending messages
+    /// are skipped.
+    pub fn get_consumers(&self) -> &HashMap<String, usize> {
+        &self.consumers
+    }
+}
+
+/// Message pending in consumer group, see [`GroupReadStream::pending()`](GroupReadStream::pending).
+#[derive(Debug, Clone)]
+pub struct PendingMessage {
+    /// Message id
+    id: StreamId,
+    /// Consumer, which message was delivered to
+    consumer: String,
+    /// Time since the last delivery
+    idle: time::Duration,
+    /// Number of deliveries
+    deliveries: usize,
+}
+
+impl PendingMessage {
+    /// Returns message id.
+    pub fn get_id(&self) -> StreamId {
+        self.id
+    }
+
+    /// Returns name of the consumer, which message was delivered to.
+    pub fn get_consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    /// Returns time since the last delivery of the message.
+    pub fn get_idle(&self) -> time::Duration {
+        self.idle
+    }
+
+    /// Returns how many times message was delivered, e.g. claimed messages are delivered more
+    /// than once.
+    pub fn get_deliveries(&self) -> usize {
+        self.deliveries
+    }
+}
@@ -0,0 +1,202 @@
+use super::{ReadStream, StreamId};
+use crate::error::IpcError;
+use crate::RedisConnection;
+use redis::streams::{StreamInfoConsumersReply, StreamInfoGroupsReply};
+use redis::{Commands, Value};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::time;
+
+/// State of stream and its consumer groups, see
+/// [`ReadStream::stream_info()`](ReadStream::stream_info).
+#[derive(Debug, Clone)]
+pub struct StreamInfo {
+    /// Number of messages in stream
+    length: usize,
+    /// Id of the first message, [`None`] if stream is empty
+    first_id: Option<StreamId>,
+    /// Id of the last message, [`None`] if stream is empty
+    last_id: Option<StreamId>,
+    /// Id of the last message ever added, also deleted or trimmed one
+    last_generated_id: StreamId,
+    /// Consumer groups of the stream
+    groups: Vec<GroupInfo>,
+}
+
+impl StreamInfo {
+    /// Returns number of messages in stream.
+    pub fn get_length(&self) -> usize {
+        self.length
+    }
+
+    /// Returns id of the first message or [`None`] if stream is empty.
+    pub fn get_first_id(&self) -> Option<StreamId> {
+        self.first_id
+    }
+
+    /// Returns id of the last message or [`None`] if stream is empty.
+    pub fn get_last_id(&self) -> Option<StreamId> {
+        self.last_id
+    }
+
+    /// Returns id of the last message ever added to stream, even if it was deleted or trimmed.
+    pub fn get_last_generated_id(&self) -> StreamId {
+        self.last_generated_id
+    }
+
+    /// Returns consumer groups of the stream.
+    pub fn get_groups(&self) -> &[GroupInfo] {
+        &self.groups
+    }
+}
+
+/// State of consumer group, see [`StreamInfo`].
+#[derive(Debug, Clone)]
+pub struct GroupInfo {
+    /// Group name
+    name: String,
+    /// Number of messages delivered, but not acknowledged
+    pending: usize,
+    /// Id of the last message delivered to the group
+    last_delivered_id: StreamId,
+    /// Number of messages not delivered yet, [`None`] if redis can't tell
+    lag: Option<usize>,
+    /// Consumers of the group
+    consumers: Vec<ConsumerInfo>,
+}
+
+impl GroupInfo {
+    /// Returns group name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns number of messages delivered to group consumers, but not acknowledged.
+    pub fn get_pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Returns id of the last message delivered to the group.
+    pub fn get_last_delivered_id(&self) -> StreamId {
+        self.last_delivered_id
+    }
+
+    /// Returns number of messages not delivered to the group yet or [`None`] if redis can't tell
+    /// it (e.g. after messages were deleted). Requires redis 7.0 or newer.
+    pub fn get_lag(&self) -> Option<usize> {
+        self.lag
+    }
+
+    /// Returns consumers of the group.
+    pub fn get_consumers(&self) -> &[ConsumerInfo] {
+        &self.consumers
+    }
+}
+
+/// State of consumer, see [`GroupInfo`].
+#[derive(Debug, Clone)]
+pub struct ConsumerInfo {
+    /// Consumer name
+    name: String,
+    /// Number of messages delivered to consumer, but not acknowledged
+    pending: usize,
+    /// Time since the last interaction of consumer
+    idle: time::Duration,
+}
+
+impl ConsumerInfo {
+    /// Returns consumer name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns number of messages delivered to consumer, but not acknowledged.
+    pub fn get_pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Returns time since the last interaction of consumer, e.g. read.
+    pub fn get_idle(&self) -> time::Duration {
+        self.idle
+    }
+}
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Returns length, first and last ids and consumer groups of the stream with their pending
+    /// messages, e.g. for dashboards. Stream isn't read atomically, so groups may be slightly
+    /// newer than stream state.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or when stream doesn't exist.
+    pub fn stream_info(&self) -> Result<StreamInfo, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let stream = conn.xinfo_stream::<&str, HashMap<String, Value>>(&self.name)?;
+
+        let length = match stream.get("length") {
+            Some(length) => redis::from_redis_value(length)?,
+            None => 0,
+        };
+
+        let last_generated_id = match stream.get("last-generated-id") {
+            Some(id) => redis::from_redis_value::<String>(id)?.parse()?,
+            None => StreamId::ZERO,
+        };
+
+        let groups = conn
+            .xinfo_groups::<&str, StreamInfoGroupsReply>(&self.name)?
+            .groups
+            .into_iter()
+            .map(|group| {
+                Ok(GroupInfo {
+                    consumers: self.consumers(&mut conn, &group.name)?,
+                    last_delivered_id: group.last_delivered_id.parse()?,
+                    name: group.name,
+                    pending: group.pending,
+                    lag: group.lag,
+                })
+            })
+            .collect::<Result<Vec<GroupInfo>, IpcError>>()?;
+
+        Ok(StreamInfo {
+            length,
+            first_id: entry_id(stream.get("first-entry"))?,
+            last_id: entry_id(stream.get("last-entry"))?,
+            last_generated_id,
+            groups,
+        })
+    }
+
+    /// Returns consumers of given group.
+    fn consumers(
+        &self,
+        conn: &mut RedisConnection,
+        group: &str,
+    ) -> Result<Vec<ConsumerInfo>, IpcError> {
+        let reply =
+            conn.xinfo_consumers::<&str, &str, StreamInfoConsumersReply>(&self.name, group)?;
+
+        Ok(reply
+            .consumers
+            .into_iter()
+            .map(|consumer| ConsumerInfo {
+                name: consumer.name,
+                pending: consumer.pending,
+                idle: time::Duration::from_millis(consumer.idle as u64),
+            })
+            .collect())
+    }
+}
+
+/// Returns id of stream entry returned by `XINFO STREAM` or [`None`] if there is no entry.
+fn entry_id(entry: Option<&Value>) -> Result<Option<StreamId>, IpcError> {
+    let Some(entry) = entry else {
+        return Ok(None);
+    };
+
+    match redis::from_redis_value::<Option<(String, Value)>>(entry)? {
+        Some((id, _)) => Ok(Some(id.parse()?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,83 @@
+use super::{stringify_id, ReadStream, StreamId};
+use crate::error::IpcError;
+use crate::RedisConnection;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Counts entries after id `ARGV[1]` (exclusive) and returns their count with id of the last one.
+const LAG_SCRIPT: &str = r"
+local entries = redis.call('XRANGE', KEYS[1], '(' .. ARGV[1], '+')
+local head = entries[#entries]
+return {#entries, head and head[1] or false}
+";
+
+/// How far consumer is behind the stream head, see [`ReadStream::lag()`](ReadStream::lag) and
+/// [`GroupReadStream::lag()`](super::GroupReadStream::lag).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConsumerLag {
+    /// Number of messages not read yet
+    messages: usize,
+    /// Time between the last read message and the newest one
+    time: Duration,
+}
+
+impl ConsumerLag {
+    /// Returns number of messages in stream, which weren't read yet.
+    pub fn get_messages(&self) -> usize {
+        self.messages
+    }
+
+    /// Returns time between publishing of the last read message and the newest message in
+    /// stream (based on message ids). It's zero when consumer is caught up.
+    pub fn get_time(&self) -> Duration {
+        self.time
+    }
+
+    /// Checks if consumer has read all messages.
+    pub fn is_caught_up(&self) -> bool {
+        self.messages == 0
+    }
+}
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Returns how far reader is behind the stream head, e.g. for autoscaling or alerting.
+    /// Reader of new messages, which didn't read anything yet, isn't behind.
+    ///
+    /// Messages after the last read one are counted by redis, which takes time proportional to
+    /// their number.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn lag(&self) -> Result<ConsumerLag, IpcError> {
+        let Some(last_id) = *self.last_id.lock()? else {
+            return Ok(ConsumerLag::default());
+        };
+
+        let mut conn = self.pool.get()?;
+
+        lag_after(&mut conn, &self.name, last_id)
+    }
+}
+
+/// Returns lag of consumer, which has read stream up to given id.
+pub(super) fn lag_after(
+    conn: &mut RedisConnection,
+    name: &str,
+    last_id: StreamId,
+) -> Result<ConsumerLag, IpcError> {
+    let (messages, head) = redis::Script::new(LAG_SCRIPT)
+        .key(name)
+        .arg(stringify_id(&last_id))
+        .invoke::<(usize, Option<String>)>(&mut **conn)?;
+
+    let time = match head {
+        Some(head) => {
+            let head: StreamId = head.parse()?;
+            Duration::from_millis(head.get_timestamp().saturating_sub(last_id.get_timestamp()))
+        }
+        None => Duration::ZERO,
+    };
+
+    Ok(ConsumerLag { messages, time })
+}
@@ -0,0 +1,322 @@
+use super::{parse_redis_stream_single_message, stringify_id, DecodeMode, StreamId, StreamMessage};
+use crate::error::IpcError;
+use crate::{OptionalTimeout, RedisConnection, RedisPool, Timeout};
+use redis::streams::{StreamRangeReply, StreamReadOptions, StreamReadReply};
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Message read by [`MultiReadStream`] together with name of the stream it comes from.
+pub struct SourcedMessage<MessageContent> {
+    /// Name of the source stream
+    stream: Arc<String>,
+    /// Read message
+    message: StreamMessage<MessageContent>,
+}
+
+impl<MessageContent> SourcedMessage<MessageContent> {
+    /// Returns name of the stream, which message comes from.
+    pub fn get_stream(&self) -> &str {
+        &self.stream
+    }
+
+    /// Returns read message.
+    pub fn get_message(&self) -> &StreamMessage<MessageContent> {
+        &self.message
+    }
+
+    /// Returns message without source stream name.
+    pub fn into_message(self) -> StreamMessage<MessageContent> {
+        self.message
+    }
+}
+
+/// Read state shared by clones of [`MultiReadStream`].
+struct MultiReadState<MessageContent> {
+    /// Id of the last read message of each stream, [`None`] until the first read
+    last_ids: Vec<Option<StreamId>>,
+    /// Messages read with one request, which weren't returned yet
+    pending: VecDeque<SourcedMessage<MessageContent>>,
+}
+
+/// Reads new messages from several streams with one blocking request, so service listening to
+/// many streams doesn't need a thread per stream. Streams are read with one `XREAD`, messages
+/// are returned in order of streams given to [`MultiReadStream::new()`](MultiReadStream::new)
+/// and tagged with their source stream.
+///
+/// Only messages added after the first read are returned. Position of every stream is kept
+/// since then, so no message added between reads is missed.
+///
+/// Streams may be given weights with
+/// [`MultiReadStream::with_weights()`](MultiReadStream::with_weights), so important stream's
+/// backlog is drained faster.
+pub struct MultiReadStream<MessageContent: DeserializeOwned> {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Names of read streams
+    names: Arc<Vec<Arc<String>>>,
+    /// Timeout duration, 0 if no timeout
+    timeout: Timeout,
+    /// Read state shared by clones
+    state: Arc<Mutex<MultiReadState<MessageContent>>>,
+    /// Handling of unknown content fields
+    decode_mode: DecodeMode,
+    /// Weights of streams in order of names, [`None`] if all streams are equal
+    weights: Option<Arc<Vec<usize>>>,
+    /// Phantom for message type
+    phantom: PhantomData<MessageContent>,
+}
+
+// Implemented manually, because derive would require `MessageContent: Clone`.
+impl<MessageContent: DeserializeOwned> Clone for MultiReadStream<MessageContent> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            names: Arc::clone(&self.names),
+            timeout: self.timeout,
+            state: Arc::clone(&self.state),
+            decode_mode: self.decode_mode,
+            weights: self.weights.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<MessageContent: DeserializeOwned> MultiReadStream<MessageContent> {
+    /// Builds reader of streams with given names.
+    pub fn new(pool: RedisPool, names: &[&str], timeout: OptionalTimeout) -> Self {
+        let names: Vec<Arc<String>> = names.iter().map(|name| Arc::new(name.to_string())).collect();
+        let state = MultiReadState {
+            last_ids: vec![None; names.len()],
+            pending: VecDeque::new(),
+        };
+
+        Self {
+            pool,
+            names: Arc::new(names),
+            timeout: timeout.unwrap_or(Duration::ZERO),
+            state: Arc::new(Mutex::new(state)),
+            decode_mode: DecodeMode::default(),
+            weights: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// See [`ReadStream::with_decode_mode()`](super::ReadStream::with_decode_mode).
+    pub fn with_decode_mode(mut self, decode_mode: DecodeMode) -> Self {
+        self.decode_mode = decode_mode;
+        self
+    }
+
+    /// Sets weights of streams given in order of their names (missing weights and `0` mean `1`).
+    /// When several streams have backlog, up to weight messages of each stream are read with one
+    /// request and returned interleaved, so stream with weight `3` is drained three times faster
+    /// than stream with weight `1`.
+    ///
+    /// Backlog is checked with pipelined non-blocking reads before every blocking read, so weights
+    /// cost one more request, when there is no backlog.
+    pub fn with_weights(mut self, weights: &[usize]) -> Self {
+        let weights = (0..self.names.len())
+            .map(|index| weights.get(index).copied().unwrap_or(1).max(1))
+            .collect();
+
+        self.weights = Some(Arc::new(weights));
+        self
+    }
+
+    /// Returns names of read streams.
+    pub fn get_names(&self) -> Vec<&str> {
+        self.names.iter().map(|name| name.as_str()).collect()
+    }
+
+    /// Reads next message from any of the streams. Blocks thread if not available. Waits
+    /// indefinitely or returns [`None`] after timeout if it was set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure or message decoding error.
+    pub fn b_next(&self) -> Result<Option<SourcedMessage<MessageContent>>, IpcError> {
+        let mut state = self.state.lock()?;
+
+        if state.pending.is_empty() {
+            self.read(&mut state)?;
+        }
+
+        Ok(state.pending.pop_front())
+    }
+
+    /// Reads the next message of every stream, which has one, into pending messages.
+    fn read(&self, state: &mut MultiReadState<MessageContent>) -> Result<(), IpcError> {
+        if self.names.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.pool.get()?;
+
+        if state.last_ids.iter().any(Option::is_none) {
+            self.start_positions(&mut conn, state)?;
+        }
+
+        if let Some(weights) = &self.weights {
+            if self.read_weighted(&mut conn, state, weights)? {
+                return Ok(());
+            }
+        }
+
+        let ids: Vec<String> = state
+            .last_ids
+            .iter()
+            .map(|id| stringify_id(&id.unwrap_or_default()))
+            .collect();
+        let names: Vec<&str> = self.names.iter().map(|name| name.as_str()).collect();
+
+        let timeout = usize::try_from(self.timeout.as_millis()).unwrap_or(usize::MAX);
+        let opts = StreamReadOptions::default().count(1).block(timeout);
+
+        let res = conn.xread_options::<&str, String, StreamReadReply>(&names, &ids, &opts)?;
+
+        // reply keys may come in any order
+        for (index, name) in self.names.iter().enumerate() {
+            let Some(key) = res.keys.iter().find(|key| key.key == name.as_str()) else {
+                continue;
+            };
+
+            for message in &key.ids {
+                let message = parse_redis_stream_single_message(message, self.decode_mode)?;
+
+                state.last_ids[index] = Some(message.get_id());
+                state.pending.push_back(SourcedMessage {
+                    stream: Arc::clone(name),
+                    message,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads up to weight messages of every stream without blocking and adds them to pending
+    /// messages interleaved by weights. Returns `false` if no stream has new messages.
+    fn read_weighted(
+        &self,
+        conn: &mut RedisConnection,
+        state: &mut MultiReadState<MessageContent>,
+        weights: &[usize],
+    ) -> Result<bool, IpcError> {
+        let mut pipe = redis::pipe();
+
+        for ((name, last_id), weight) in self.names.iter().zip(&state.last_ids).zip(weights) {
+            let id = stringify_id(&last_id.unwrap_or_default());
+            let opts = StreamReadOptions::default().count(*weight);
+
+            pipe.xread_options(&[name.as_str()], &[id], &opts);
+        }
+
+        let replies = pipe.query::<Vec<Option<StreamReadReply>>>(&mut **conn)?;
+
+        let mut batches = Vec::with_capacity(replies.len());
+
+        for (index, reply) in replies.iter().enumerate() {
+            let mut batch = VecDeque::new();
+
+            for message in reply.iter().flat_map(|reply| &reply.keys).flat_map(|key| &key.ids) {
+                let message = parse_redis_stream_single_message(message, self.decode_mode)?;
+
+                state.last_ids[index] = Some(message.get_id());
+                batch.push_back(SourcedMessage {
+                    stream: Arc::clone(&self.names[index]),
+                    message,
+                });
+            }
+
+            batches.push(batch);
+        }
+
+        let read = batches.iter().any(|batch| !batch.is_empty());
+
+        interleave(batches, weights, &mut state.pending);
+
+        Ok(read)
+    }
+
+    /// Sets position of streams, which weren't read yet, to their last message, so only new
+    /// messages are read.
+    fn start_positions(
+        &self,
+        conn: &mut RedisConnection,
+        state: &mut MultiReadState<MessageContent>,
+    ) -> Result<(), IpcError> {
+        for (name, last_id) in self.names.iter().zip(state.last_ids.iter_mut()) {
+            if last_id.is_some() {
+                continue;
+            }
+
+            // "+" and "-" are redis symbols for the last and the first id
+            let tail = conn.xrevrange_count::<&str, &str, &str, u8, StreamRangeReply>(
+                name, "+", "-", 1,
+            )?;
+
+            // missing or empty stream is read from the beginning
+            *last_id = match tail.ids.first() {
+                Some(tail) => Some(tail.id.parse::<StreamId>()?),
+                None => Some(StreamId::ZERO),
+            };
+        }
+
+        Ok(())
+    }
+}
+
+/// Moves messages from batches to `pending` with smooth weighted round robin, so streams with
+/// higher weights are picked more often, but not all at once.
+fn interleave<T>(mut batches: Vec<VecDeque<T>>, weights: &[usize], pending: &mut VecDeque<T>) {
+    let mut current = vec![0_i64; batches.len()];
+
+    loop {
+        let candidates: Vec<usize> =
+            (0..batches.len()).filter(|index| !batches[*index].is_empty()).collect();
+
+        let total: i64 = candidates.iter().map(|index| weights[*index] as i64).sum();
+
+        for index in &candidates {
+            current[*index] += weights[*index] as i64;
+        }
+
+        // ties are resolved in order of streams
+        let Some(picked) = candidates
+            .iter()
+            .copied()
+            .min_by_key(|index| (Reverse(current[*index]), *index))
+        else {
+            return;
+        };
+
+        current[picked] -= total;
+
+        if let Some(message) = batches[picked].pop_front() {
+            pending.push_back(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleaves_by_weights() {
+        let batches = vec![
+            VecDeque::from(["a1", "a2", "a3", "a4"]),
+            VecDeque::from(["b1", "b2"]),
+        ];
+
+        let mut pending = VecDeque::new();
+        interleave(batches, &[3, 1], &mut pending);
+
+        assert_eq!(pending, ["a1", "a2", "b1", "a3", "a4", "b2"]);
+    }
+}
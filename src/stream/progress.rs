@@ -0,0 +1,152 @@
+use super::{stringify_id, ReadStream, StreamId};
+use crate::error::IpcError;
+use crate::RedisConnection;
+use redis::streams::StreamRangeReply;
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Counts entries after id `ARGV[1]` (exclusive).
+const REMAINING_SCRIPT: &str = r"
+return #redis.call('XRANGE', KEYS[1], '(' .. ARGV[1], '+')
+";
+
+/// Hook called once, when [`ReadStream`] has read all messages, which were in stream before.
+pub type CaughtUpHook = Arc<dyn Fn() + Send + Sync>;
+
+/// Catch-up state shared by clones of [`ReadStream`].
+#[derive(Default)]
+pub(super) struct CatchUp {
+    /// Time of the first read
+    started: Option<Instant>,
+    /// Number of messages read since the first read
+    reads: u64,
+    /// Set when all messages, which were in stream, are read
+    caught_up: bool,
+}
+
+/// Progress of reading stream backlog, see [`ReadStream::progress()`](ReadStream::progress).
+#[derive(Debug, Clone, Copy)]
+pub struct CatchUpProgress {
+    /// Number of messages after the last read one
+    entries_remaining: usize,
+    /// Estimated time of reading remaining messages
+    estimated_time: Option<Duration>,
+}
+
+impl CatchUpProgress {
+    /// Returns number of messages in stream after the last read one.
+    pub fn get_entries_remaining(&self) -> usize {
+        self.entries_remaining
+    }
+
+    /// Checks if all messages in stream are read.
+    pub fn is_caught_up(&self) -> bool {
+        self.entries_remaining == 0
+    }
+
+    /// Returns estimated time of reading remaining messages based on read rate so far. It's
+    /// [`None`] when nothing was read yet or reader is caught up.
+    pub fn get_estimated_time(&self) -> Option<Duration> {
+        self.estimated_time
+    }
+}
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Sets hook called once, when all messages, which were in stream before, are read (e.g.
+    /// when backlog is replayed), so service may delay marking itself ready until then. Reader,
+    /// which reads only new messages, is caught up on its first read.
+    pub fn with_caught_up_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.caught_up_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Returns progress of reading messages, which are in stream after the last read one.
+    ///
+    /// Remaining messages are counted by redis, which takes time proportional to their number.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn progress(&self) -> Result<CatchUpProgress, IpcError> {
+        // reader of new messages has no backlog
+        let Some(last_id) = *self.last_id.lock()? else {
+            return Ok(CatchUpProgress {
+                entries_remaining: 0,
+                estimated_time: None,
+            });
+        };
+
+        let mut conn = self.pool.get()?;
+
+        let entries_remaining = redis::Script::new(REMAINING_SCRIPT)
+            .key(self.name.as_str())
+            .arg(stringify_id(&last_id))
+            .invoke::<usize>(&mut *conn)?;
+
+        let catch_up = self.catch_up.lock()?;
+
+        let estimated_time = match catch_up.started {
+            Some(started) if catch_up.reads > 0 && entries_remaining > 0 => {
+                let per_read = started.elapsed().as_secs_f64() / catch_up.reads as f64;
+                Duration::try_from_secs_f64(per_read * entries_remaining as f64).ok()
+            }
+            _ => None,
+        };
+
+        Ok(CatchUpProgress {
+            entries_remaining,
+            estimated_time,
+        })
+    }
+
+    /// Records read of `count` messages, which last one has given id. Hook is called, when it's
+    /// the last message in stream. Failures are ignored, because messages are already read.
+    pub(super) fn track_read(&self, conn: &mut RedisConnection, id: StreamId, count: usize) {
+        let Ok(mut catch_up) = self.catch_up.lock() else {
+            return;
+        };
+
+        catch_up.started.get_or_insert_with(Instant::now);
+        catch_up.reads += count as u64;
+
+        if catch_up.caught_up || self.caught_up_hook.is_none() {
+            return;
+        }
+
+        // "+" and "-" are redis symbols for the last and the first id
+        let Ok(tail) = conn.xrevrange_count::<&str, &str, &str, u8, StreamRangeReply>(
+            &self.name, "+", "-", 1,
+        ) else {
+            return;
+        };
+
+        let tail = tail.ids.first().and_then(|tail| tail.id.parse::<StreamId>().ok());
+
+        if tail.is_none_or(|tail| id >= tail) {
+            drop(catch_up);
+            self.mark_caught_up();
+        }
+    }
+
+    /// Marks reader as caught up and calls hook, if it wasn't caught up before.
+    pub(super) fn mark_caught_up(&self) {
+        let Ok(mut catch_up) = self.catch_up.lock() else {
+            return;
+        };
+
+        if catch_up.caught_up {
+            return;
+        }
+        catch_up.caught_up = true;
+        drop(catch_up);
+
+        if let Some(hook) = &self.caught_up_hook {
+            hook();
+        }
+    }
+}
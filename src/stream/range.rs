@@ -0,0 +1,235 @@
+use super::{
+    decode_message_with, entry_fields, parse_redis_stream_single_message, stringify_id,
+    DecodeMode, ReadStream, StreamId, StreamMessage,
+};
+use crate::codec::{AMENDS_FIELD, TOMBSTONE_FIELD};
+use crate::error::IpcError;
+use crate::RedisPool;
+use redis::streams::{StreamId as RedisStreamMessage, StreamRangeReply};
+use redis::Commands;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, VecDeque};
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Default number of messages fetched with one `XRANGE` request.
+pub(super) const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Iterator over messages kept in stream in given range, oldest first. Messages are fetched in
+/// pages with `XRANGE`, so large ranges aren't loaded at once. It doesn't block and doesn't
+/// change position of [`ReadStream`], which created it.
+///
+/// Iteration ends after the first error.
+pub struct StreamRange<MessageContent: DeserializeOwned> {
+    /// configured [`Pool`](r2d2::Pool) with redis [`Client`](redis::Client)
+    pool: RedisPool,
+    /// Stream name
+    name: Arc<String>,
+    /// Start of the next page in redis format, exclusive when prefixed with `(`
+    next: String,
+    /// End of the range in redis format, exclusive when prefixed with `(`
+    end: String,
+    /// Max number of messages fetched with one request
+    page_size: usize,
+    /// Handling of unknown content fields
+    decode_mode: DecodeMode,
+    /// Fetched messages, which weren't returned yet
+    page: VecDeque<StreamMessage<MessageContent>>,
+    /// Set when range is exhausted or error was returned
+    done: bool,
+    /// Phantom for message type
+    phantom: PhantomData<MessageContent>,
+}
+
+impl<MessageContent: DeserializeOwned> StreamRange<MessageContent> {
+    /// Creates iterator over messages with ids from `start` to `end`, given as `XRANGE` bounds.
+    pub(super) fn new(pool: RedisPool, name: Arc<String>, start: String, end: String) -> Self {
+        Self {
+            pool,
+            name,
+            next: start,
+            end,
+            page_size: DEFAULT_PAGE_SIZE,
+            decode_mode: DecodeMode::default(),
+            page: VecDeque::new(),
+            done: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates iterator, which returns nothing.
+    pub(super) fn empty(pool: RedisPool, name: Arc<String>) -> Self {
+        let mut range = Self::new(pool, name, String::from("-"), String::from("+"));
+        range.done = true;
+        range
+    }
+
+    /// Sets number of messages fetched with one request (at least one).
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Reads the rest of the range and folds amendments published with
+    /// [`WriteStream::publish_correction()`](super::WriteStream::publish_correction) and
+    /// [`WriteStream::publish_tombstone()`](super::WriteStream::publish_tombstone) into messages
+    /// they amend. Corrected messages keep their id and get content of the latest correction,
+    /// deleted messages are removed. Amendments themselves aren't returned.
+    ///
+    /// Only amendments in the range are applied, amendments of messages outside the range are
+    /// dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection or decoding failure.
+    pub fn resolve_amendments(mut self) -> Result<Vec<StreamMessage<MessageContent>>, IpcError> {
+        // fetched messages, which weren't returned yet, are fetched again with their fields
+        if let Some(first) = self.page.front() {
+            self.next = stringify_id(&first.get_id());
+            self.page.clear();
+            self.done = false;
+        }
+
+        let mut messages = BTreeMap::new();
+
+        while !self.done {
+            for entry in self.fetch_entries()? {
+                let mut fields = entry_fields(&entry);
+
+                let Some(amended) = fields.remove(AMENDS_FIELD) else {
+                    let message = decode_message_with(&entry.id, &fields, self.decode_mode)?;
+                    messages.insert(message.get_id(), message);
+                    continue;
+                };
+                let amended: StreamId = amended.parse()?;
+
+                if fields.contains_key(TOMBSTONE_FIELD) {
+                    messages.remove(&amended);
+                    continue;
+                }
+
+                if let Some(message) = messages.get_mut(&amended) {
+                    let correction = decode_message_with(&entry.id, &fields, self.decode_mode)?;
+
+                    *message = StreamMessage::new(amended, correction.into_content())
+                        .with_extra(message.get_extra().clone());
+                }
+            }
+        }
+
+        Ok(messages.into_values().collect())
+    }
+
+    /// Fetches the next page. Marks range as done, when it's the last one.
+    fn fetch_page(&mut self) -> Result<(), IpcError> {
+        for message in &self.fetch_entries()? {
+            self.page.push_back(parse_redis_stream_single_message(message, self.decode_mode)?);
+        }
+
+        Ok(())
+    }
+
+    /// Fetches raw entries of the next page and moves start of the range after them. Marks range
+    /// as done, when it's the last page.
+    fn fetch_entries(&mut self) -> Result<Vec<RedisStreamMessage>, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let res = conn.xrange_count::<&str, &str, &str, usize, StreamRangeReply>(
+            &self.name,
+            &self.next,
+            &self.end,
+            self.page_size,
+        )?;
+
+        if res.ids.len() < self.page_size {
+            self.done = true;
+        }
+
+        match res.ids.last() {
+            // "(" makes start exclusive
+            Some(last) => self.next = format!("({}", last.id),
+            None => self.done = true,
+        }
+
+        Ok(res.ids)
+    }
+}
+
+impl<MessageContent: DeserializeOwned> Iterator for StreamRange<MessageContent> {
+    type Item = Result<StreamMessage<MessageContent>, IpcError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page.is_empty() && !self.done {
+            if let Err(error) = self.fetch_page() {
+                self.done = true;
+                self.page.clear();
+                return Some(Err(error));
+            }
+        }
+
+        self.page.pop_front().map(Ok)
+    }
+}
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Returns iterator over messages with ids in given `range`. Times may be used as bounds
+    /// after conversion to [`StreamId`], e.g. `StreamId::from(from)..StreamId::from(to)`
+    /// returns messages added at or after `from` and before `to`.
+    pub fn range(&self, range: impl RangeBounds<StreamId>) -> StreamRange<MessageContent> {
+        let last = StreamId::new(u64::MAX, u64::MAX);
+
+        // redis rejects exclusive bounds, which can't contain any id
+        let is_empty = matches!(range.start_bound(), Bound::Excluded(id) if *id == last)
+            || matches!(range.end_bound(), Bound::Excluded(id) if *id == StreamId::ZERO);
+
+        if is_empty {
+            return StreamRange::empty(self.pool.clone(), Arc::clone(&self.name));
+        }
+
+        // "-" and "+" are redis symbols for the first and the last id
+        let mut messages = StreamRange::new(
+            self.pool.clone(),
+            Arc::clone(&self.name),
+            id_bound(range.start_bound(), "-"),
+            id_bound(range.end_bound(), "+"),
+        );
+        messages.decode_mode = self.decode_mode;
+        messages
+    }
+
+    /// Returns iterator over messages added to stream at or after given time.
+    pub fn read_since(&self, since: SystemTime) -> StreamRange<MessageContent> {
+        self.range(StreamId::from(since)..)
+    }
+
+    /// Returns iterator over messages added to stream at or after `from` and before `to`.
+    pub fn read_between(&self, from: SystemTime, to: SystemTime) -> StreamRange<MessageContent> {
+        self.range(StreamId::from(from)..StreamId::from(to))
+    }
+}
+
+/// Converts id range bound to `XRANGE` bound.
+pub(super) fn id_bound(bound: Bound<&StreamId>, unbounded: &str) -> String {
+    match bound {
+        Bound::Included(id) => stringify_id(id),
+        // "(" makes bound exclusive
+        Bound::Excluded(id) => format!("({}", stringify_id(id)),
+        Bound::Unbounded => unbounded.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_id_bounds() {
+        let id = StreamId::new(1500, 2);
+
+        assert_eq!(id_bound(Bound::Included(&id), "-"), "1500-2");
+        assert_eq!(id_bound(Bound::Excluded(&id), "-"), "(1500-2");
+        assert_eq!(id_bound(Bound::Unbounded, "+"), "+");
+    }
+}
@@ -0,0 +1,145 @@
+use super::{ReadStream, StreamId, StreamMessage, StreamRange};
+use crate::error::IpcError;
+use serde::de::DeserializeOwned;
+use std::ops::{Bound, RangeBounds};
+
+/// Iterator, which replays messages kept in stream in given range and then optionally tails new
+/// messages, e.g. to rebuild state from event stream and keep it up to date. Created with
+/// [`ReadStream::replay()`](ReadStream::replay).
+///
+/// History is read without blocking with paginated `XRANGE`, see [`StreamRange`]. Live tailing
+/// starts after the last replayed message (or range start, if nothing was replayed), so no
+/// message is skipped or repeated between the two. Messages after range end, which are already
+/// in stream, are tailed too. Tailing reads with blocking `XREAD` same as
+/// [`ReadStream::b_next()`](ReadStream::b_next) and ends when the reader timeout elapses without
+/// new message.
+///
+/// Position of [`ReadStream`], which created the iterator, isn't changed. Iteration ends after
+/// the first error.
+pub struct StreamReplay<MessageContent: DeserializeOwned> {
+    /// Messages kept in stream
+    history: StreamRange<MessageContent>,
+    /// Reader, which live reader is made from
+    reader: ReadStream<MessageContent>,
+    /// Whether new messages are read once history is replayed
+    live_tail: bool,
+    /// Reader of new messages, set when history is replayed
+    live: Option<ReadStream<MessageContent>>,
+    /// Id of the last replayed message or position before range start
+    position: StreamId,
+    /// Set when error was returned
+    failed: bool,
+}
+
+impl<MessageContent: DeserializeOwned> StreamReplay<MessageContent> {
+    /// Sets number of history messages fetched with one request (at least one).
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.history = self.history.with_page_size(page_size);
+        self
+    }
+
+    /// Enables reading new messages with blocking reads once history is replayed. It's disabled
+    /// by default, so iteration ends with the last message in range.
+    pub fn with_live_tail(mut self, enabled: bool) -> Self {
+        self.live_tail = enabled;
+        self
+    }
+}
+
+impl<MessageContent: DeserializeOwned> Iterator for StreamReplay<MessageContent> {
+    type Item = Result<StreamMessage<MessageContent>, IpcError>;
+
+    ///  **This method blocks in live tailing mode!** Returns next message, error or [`None`]
+    /// when history is replayed (or reader timeout elapsed while tailing).
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let message = match &self.live {
+            Some(live) => live.b_next().transpose(),
+            None => match self.history.next() {
+                Some(message) => Some(message),
+                None if self.live_tail => {
+                    let live = self.reader.clone().read_from(self.position);
+                    let message = live.b_next().transpose();
+                    self.live = Some(live);
+                    message
+                }
+                None => None,
+            },
+        };
+
+        match &message {
+            Some(Ok(message)) => self.position = message.get_id(),
+            Some(Err(_)) => self.failed = true,
+            None => {}
+        }
+
+        message
+    }
+}
+
+impl<MessageContent: DeserializeOwned> ReadStream<MessageContent> {
+    /// Returns iterator, which replays messages with ids in given `range` without blocking. Live
+    /// tailing of new messages may be enabled with
+    /// [`StreamReplay::with_live_tail()`](StreamReplay::with_live_tail).
+    ///
+    /// # Examples
+    ///
+    /// ```ignored
+    /// for event in stream.replay(..).with_live_tail(true) {
+    ///     state.apply(event?.get_content());
+    /// }
+    /// ```
+    pub fn replay(&self, range: impl RangeBounds<StreamId>) -> StreamReplay<MessageContent> {
+        let position = before_start(range.start_bound());
+
+        let mut reader = self.clone();
+        // replay doesn't move reader position saved by this stream nor reports its progress
+        reader.checkpoint_key = None;
+        reader.caught_up_hook = None;
+
+        StreamReplay {
+            history: self.range(range),
+            reader,
+            live_tail: false,
+            live: None,
+            position,
+            failed: false,
+        }
+    }
+}
+
+/// Returns the last id before range start, reads after it return messages from range start.
+fn before_start(start: Bound<&StreamId>) -> StreamId {
+    match start {
+        Bound::Included(id) if id.get_sequence() > 0 => {
+            StreamId::new(id.get_timestamp(), id.get_sequence() - 1)
+        }
+        Bound::Included(id) => match id.get_timestamp().checked_sub(1) {
+            Some(timestamp) => StreamId::new(timestamp, u64::MAX),
+            None => StreamId::ZERO,
+        },
+        Bound::Excluded(id) => *id,
+        Bound::Unbounded => StreamId::ZERO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_before_range_start() {
+        let id = StreamId::new(1500, 2);
+
+        assert_eq!(before_start(Bound::Included(&id)), StreamId::new(1500, 1));
+        assert_eq!(
+            before_start(Bound::Included(&StreamId::new(1500, 0))),
+            StreamId::new(1499, u64::MAX)
+        );
+        assert_eq!(before_start(Bound::Excluded(&id)), id);
+        assert_eq!(before_start(Bound::Unbounded), StreamId::ZERO);
+    }
+}
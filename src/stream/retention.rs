@@ -0,0 +1,89 @@
+use super::{stringify_id, StreamId, WriteStream};
+use crate::error::IpcError;
+use redis::streams::{StreamAddOptions, StreamTrimStrategy, StreamTrimmingMode};
+use serde::Serialize;
+use std::time::{Duration, SystemTime};
+
+/// Retention policy of stream, which is applied by trimming stream on every publish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    /// Keeps about given number of the newest messages. Redis may keep a few more, because it
+    /// trims only whole internal nodes, which is much cheaper. Used by default.
+    MaxLen(usize),
+    /// Keeps exactly given number of the newest messages.
+    ExactMaxLen(usize),
+    /// Keeps messages added within given time. Older messages are trimmed approximately, same
+    /// as with [`Retention::MaxLen`].
+    MaxAge(Duration),
+}
+
+impl Retention {
+    /// Returns trimming arguments of `XADD` and `XTRIM`.
+    pub(super) fn strategy(&self) -> StreamTrimStrategy {
+        match *self {
+            Self::MaxLen(max_len) => {
+                StreamTrimStrategy::maxlen(StreamTrimmingMode::Approx, max_len)
+            }
+            Self::ExactMaxLen(max_len) => {
+                StreamTrimStrategy::maxlen(StreamTrimmingMode::Exact, max_len)
+            }
+            Self::MaxAge(max_age) => {
+                let oldest = SystemTime::now()
+                    .checked_sub(max_age)
+                    .map(StreamId::from_system_time)
+                    .unwrap_or(StreamId::ZERO);
+
+                StreamTrimStrategy::minid(StreamTrimmingMode::Approx, stringify_id(&oldest))
+            }
+        }
+    }
+
+    /// Returns `XADD` options, which trim stream and create it only when `auto_create` is set.
+    pub(super) fn add_options(&self, auto_create: bool) -> StreamAddOptions {
+        let options = StreamAddOptions::default().trim(self.strategy());
+
+        match auto_create {
+            true => options,
+            false => options.nomkstream(),
+        }
+    }
+}
+
+impl<MessageContent: Serialize> WriteStream<MessageContent> {
+    /// Sets retention policy applied on every publish. By default stream keeps about `max_size`
+    /// messages given to [`WriteStream::new()`](WriteStream::new).
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Trims stream according to its retention policy, e.g. when messages aren't published for
+    /// a while, so age based retention isn't applied. Returns number of removed messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn trim(&self) -> Result<usize, IpcError> {
+        self.xtrim(self.current_retention().strategy())
+    }
+
+    /// Removes all messages with ids lower than given one. Returns number of removed messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) on connection failure.
+    pub fn trim_before(&self, id: StreamId) -> Result<usize, IpcError> {
+        self.xtrim(StreamTrimStrategy::minid(StreamTrimmingMode::Exact, stringify_id(&id)))
+    }
+
+    fn xtrim(&self, strategy: StreamTrimStrategy) -> Result<usize, IpcError> {
+        let mut conn = self.pool.get()?;
+
+        let removed = redis::cmd("XTRIM")
+            .arg(self.name.as_str())
+            .arg(strategy)
+            .query::<usize>(&mut *conn)?;
+
+        Ok(removed)
+    }
+}
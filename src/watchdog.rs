@@ -0,0 +1,230 @@
+//! Watchdog of blocking reads. Blocking call waiting on half-dead connection may hang far beyond
+//! its timeout, which is usually noticed only by missing throughput. [`Watchdog`] tracks
+//! blocking calls of consumers it was passed to and calls a hook, when any of them is stuck.
+//!
+//! # Examples
+//! ```ignored
+//! let watchdog = Watchdog::new(Duration::from_secs(30), |call| {
+//!     eprintln!("{} stuck for {:?}", call.get_consumer(), call.get_elapsed());
+//! });
+//!
+//! let mut queue = ReadQueue::new(pool, "tasks", Some(Duration::from_secs(5)))
+//!     .with_watchdog(watchdog.clone());
+//! ```
+
+use crate::error::IpcError;
+use crate::Timeout;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Max time between checks of blocking calls.
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Hook called with blocking call, which is stuck.
+pub type StuckHook = Arc<dyn Fn(&StuckCall) + Send + Sync>;
+
+/// Blocking call, which takes longer than its timeout and watchdog tolerance.
+#[derive(Debug, Clone)]
+pub struct StuckCall {
+    /// Name of the structure, which does the call
+    consumer: Arc<String>,
+    /// Configured timeout of the call
+    timeout: Timeout,
+    /// Time since the call started
+    elapsed: Duration,
+}
+
+impl StuckCall {
+    /// Returns name of the structure (e.g. queue or stream), which does the call.
+    pub fn get_consumer(&self) -> &str {
+        &self.consumer
+    }
+
+    /// Returns configured timeout of the call.
+    pub fn get_timeout(&self) -> Timeout {
+        self.timeout
+    }
+
+    /// Returns time since the call started.
+    pub fn get_elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Blocking call in progress.
+struct Call {
+    consumer: Arc<String>,
+    timeout: Timeout,
+    started: Instant,
+    /// Hook is called only once per call
+    reported: bool,
+}
+
+/// State shared with the checker thread.
+struct Shared {
+    /// Extra time, which call may take over its timeout
+    tolerance: Duration,
+    hook: StuckHook,
+    /// Calls in progress by their id
+    calls: Mutex<HashMap<u64, Call>>,
+    /// Time of the last completed call by consumer name
+    completed: Mutex<HashMap<Arc<String>, Instant>>,
+    next_id: AtomicU64,
+}
+
+/// Watchdog of blocking calls. See [module documentation](self).
+///
+/// Calls with no timeout are tracked, but never reported as stuck. Clones share the same state,
+/// checks are stopped when all clones are dropped.
+#[derive(Clone)]
+pub struct Watchdog {
+    shared: Arc<Shared>,
+}
+
+impl Watchdog {
+    /// Creates watchdog, which calls `hook` when blocking call takes longer than its timeout
+    /// and `tolerance`. Calls are checked in a background thread.
+    pub fn new<F>(tolerance: Duration, hook: F) -> Self
+    where
+        F: Fn(&StuckCall) + Send + Sync + 'static,
+    {
+        let shared = Arc::new(Shared {
+            tolerance,
+            hook: Arc::new(hook),
+            calls: Mutex::new(HashMap::new()),
+            completed: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        });
+
+        let interval = tolerance.clamp(Duration::from_millis(10), MAX_CHECK_INTERVAL);
+        let weak = Arc::downgrade(&shared);
+        thread::spawn(move || run_checker(&weak, interval));
+
+        Self { shared }
+    }
+
+    /// Returns time since consumer with given name completed its last blocking call or
+    /// [`None`] if it hasn't completed any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`IpcError`](IpcError) when state lock is poisoned.
+    pub fn get_idle(&self, consumer: &str) -> Result<Option<Duration>, IpcError> {
+        let completed = self.shared.completed.lock()?;
+
+        Ok(completed.get(&consumer.to_string()).map(Instant::elapsed))
+    }
+
+    /// Starts tracking of blocking call. Call is completed when returned guard is dropped.
+    pub(crate) fn watch(&self, consumer: &Arc<String>, timeout: Timeout) -> WatchGuard {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+
+        if let Ok(mut calls) = self.shared.calls.lock() {
+            calls.insert(
+                id,
+                Call {
+                    consumer: Arc::clone(consumer),
+                    timeout,
+                    started: Instant::now(),
+                    reported: false,
+                },
+            );
+        }
+
+        WatchGuard {
+            shared: Arc::clone(&self.shared),
+            id,
+        }
+    }
+}
+
+/// Tracked blocking call, which is completed on drop.
+pub(crate) struct WatchGuard {
+    shared: Arc<Shared>,
+    id: u64,
+}
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        let call = match self.shared.calls.lock() {
+            Ok(mut calls) => calls.remove(&self.id),
+            Err(_) => return,
+        };
+
+        if let (Some(call), Ok(mut completed)) = (call, self.shared.completed.lock()) {
+            completed.insert(call.consumer, Instant::now());
+        }
+    }
+}
+
+/// Reports stuck calls every `interval`, until watchdog is dropped.
+fn run_checker(shared: &Weak<Shared>, interval: Duration) {
+    loop {
+        thread::sleep(interval);
+
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+
+        let stuck: Vec<StuckCall> = match shared.calls.lock() {
+            Ok(mut calls) => calls
+                .values_mut()
+                .filter(|call| !call.reported && !call.timeout.is_zero())
+                .filter(|call| call.started.elapsed() > call.timeout + shared.tolerance)
+                .map(|call| {
+                    call.reported = true;
+
+                    StuckCall {
+                        consumer: Arc::clone(&call.consumer),
+                        timeout: call.timeout,
+                        elapsed: call.started.elapsed(),
+                    }
+                })
+                .collect(),
+            Err(_) => return,
+        };
+
+        // hook is called without lock, so it may use watchdog
+        for call in &stuck {
+            (shared.hook)(call);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn reports_stuck_call_once() {
+        let reported = Arc::new(AtomicUsize::new(0));
+
+        let watchdog = {
+            let reported = Arc::clone(&reported);
+            Watchdog::new(Duration::from_millis(20), move |call| {
+                assert_eq!(call.get_consumer(), "queue");
+                reported.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        let consumer = Arc::new(String::from("queue"));
+
+        let guard = watchdog.watch(&consumer, Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(200));
+        drop(guard);
+
+        assert_eq!(reported.load(Ordering::SeqCst), 1);
+        assert!(watchdog.get_idle("queue").unwrap().is_some());
+
+        // call without timeout is never stuck
+        let guard = watchdog.watch(&consumer, Duration::ZERO);
+        thread::sleep(Duration::from_millis(100));
+        drop(guard);
+
+        assert_eq!(reported.load(Ordering::SeqCst), 1);
+    }
+}
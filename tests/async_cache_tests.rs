@@ -0,0 +1,41 @@
+#![cfg(feature = "tokio")]
+
+use redis_ipc::cache::{AsyncCache, Cache};
+use redis_ipc::error::IpcErrorKind;
+use std::time::Duration;
+
+mod common;
+
+use common::TestMessage;
+
+#[tokio::test]
+async fn async_cache_sets_and_gets() {
+    let name = common::random_string(10);
+    let field = common::random_string(5);
+
+    let mut cache = AsyncCache::<TestMessage>::new(
+        common::build_async_connection().await,
+        &name,
+        Some(Duration::from_secs(15)),
+        Some(Duration::from_millis(200)),
+    );
+
+    assert!(!cache.exists(&field).await.unwrap());
+
+    let msg = common::build_test_message();
+    cache.set(&field, &msg).await.expect("Cannot set value");
+
+    assert!(cache.exists(&field).await.unwrap());
+    assert_eq!(cache.b_get(&field).await.unwrap().get_content(), &msg);
+
+    // same layout as sync cache
+    let sync_cache = Cache::<TestMessage>::new(common::build_pool(), &name, None, None);
+    assert_eq!(sync_cache.get(&field).unwrap().unwrap().get_content(), &msg);
+
+    cache.delete(&field).await.unwrap();
+
+    match cache.b_get(&field).await {
+        Err(error) => assert!(matches!(error.kind(), IpcErrorKind::Timeout)),
+        Ok(_) => panic!("Deleted element should not be returned"),
+    }
+}
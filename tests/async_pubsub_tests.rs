@@ -0,0 +1,38 @@
+#![cfg(feature = "tokio")]
+
+use futures::StreamExt;
+use redis_ipc::pubsub::{AsyncPublisher, AsyncSubscriber};
+use std::env;
+use std::time::Duration;
+
+mod common;
+
+use common::TestMessage;
+
+#[tokio::test]
+async fn subscriber_receives_published_messages() {
+    let _ = dotenvy::dotenv();
+    let url = env::var("REDIS_URL").expect("Env REDIS_URL not found");
+    let client = redis::Client::open(url).expect("Invalid redis url");
+
+    let name = common::random_string(10);
+
+    let mut subscriber = AsyncSubscriber::<TestMessage>::new(&client, &[&name])
+        .await
+        .expect("Cannot subscribe");
+    let mut publisher =
+        AsyncPublisher::<TestMessage>::new(common::build_async_connection().await, &name);
+
+    let msg = common::build_test_message();
+    let receivers = publisher.publish(&msg).await.expect("Cannot publish");
+    assert_eq!(receivers, 1);
+
+    let received = tokio::time::timeout(Duration::from_secs(5), subscriber.next())
+        .await
+        .expect("Subscriber timed out")
+        .expect("Subscriber ended")
+        .expect("Response error");
+
+    assert_eq!(received.get_channel(), name);
+    assert_eq!(received.get_content(), &msg);
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "tokio")]
+
+use futures::StreamExt;
+use redis_ipc::queue::{AsyncReadQueue, AsyncWriteQueue};
+use std::time::Duration;
+
+mod common;
+
+use common::TestMessage;
+
+#[tokio::test]
+async fn async_queue_communicates() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue =
+        AsyncWriteQueue::<TestMessage>::new(common::build_async_connection().await, &queue_name);
+    let mut read_queue = AsyncReadQueue::<TestMessage>::new(
+        common::build_async_connection().await,
+        &queue_name,
+        Some(Duration::from_secs(5)),
+    );
+
+    let msg = common::build_test_message();
+
+    write_queue.publish(&msg).await.expect("Cannot publish");
+
+    let response = read_queue.b_next().await.expect("Response error").expect("No message");
+
+    assert_eq!(response.get_content(), &msg);
+    assert!(read_queue.next().await.expect("Cannot read queue").is_none());
+}
+
+#[tokio::test]
+async fn async_read_queue_timeouts() {
+    let queue_name = common::random_string(10);
+
+    let mut queue = AsyncReadQueue::<TestMessage>::new(
+        common::build_async_connection().await,
+        &queue_name,
+        Some(Duration::from_secs(1)),
+    );
+
+    assert!(queue.b_next().await.expect("Response error").is_none());
+}
+
+#[tokio::test]
+async fn async_queue_is_stream() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue =
+        AsyncWriteQueue::<TestMessage>::new(common::build_async_connection().await, &queue_name);
+    let read_queue = AsyncReadQueue::<TestMessage>::new(
+        common::build_async_connection().await,
+        &queue_name,
+        Some(Duration::from_secs(5)),
+    );
+
+    let msg = common::build_test_message();
+
+    for _ in 0..3 {
+        write_queue.publish(&msg).await.expect("Cannot publish");
+    }
+
+    let received: Vec<_> = read_queue.take(3).collect().await;
+
+    assert_eq!(received.len(), 3);
+    for res in received {
+        assert_eq!(res.expect("Response error").get_content(), &msg);
+    }
+}
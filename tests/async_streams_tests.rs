@@ -0,0 +1,66 @@
+#![cfg(feature = "tokio")]
+
+use futures::StreamExt;
+use redis_ipc::stream::{AsyncReadStream, AsyncWriteStream, ReadStream, WriteStream};
+use std::time::Duration;
+
+mod common;
+
+use common::TestMessage;
+
+#[tokio::test]
+async fn async_stream_yields_published_messages() {
+    let name = common::random_string(10);
+
+    let mut write_stream =
+        AsyncWriteStream::<TestMessage>::new(common::build_async_connection().await, &name, 100);
+    let mut read_stream = AsyncReadStream::<TestMessage>::new(
+        common::build_async_connection().await,
+        &name,
+        Some(Duration::from_secs(5)),
+    );
+
+    let first = write_stream
+        .publish(&common::build_test_message())
+        .await
+        .expect("Cannot publish");
+
+    let last = read_stream.last().await.expect("Cannot read stream").expect("No messages");
+    assert_eq!(last.get_id(), first);
+
+    let reader = tokio::spawn(async move {
+        let msg = read_stream.next().await.expect("Stream ended");
+        msg.expect("Response error")
+    });
+
+    // give reader time to start blocking read
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).await.expect("Cannot publish");
+
+    assert_eq!(reader.await.unwrap().get_content(), &msg);
+}
+
+#[tokio::test]
+async fn sync_stream_bridge_yields_published_messages() {
+    let name = common::random_string(10);
+
+    let pool = common::build_pool();
+    let write_stream = WriteStream::<TestMessage>::new(pool.clone(), &name, 100);
+    let mut bridge = ReadStream::<TestMessage>::new(pool, &name, None).into_async_bridge();
+
+    // give bridge thread time to start blocking read
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    let res = tokio::time::timeout(Duration::from_secs(5), bridge.next())
+        .await
+        .expect("Bridge timed out")
+        .expect("Bridge ended")
+        .expect("Response error");
+
+    assert_eq!(res.get_content(), &msg);
+}
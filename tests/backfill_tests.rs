@@ -0,0 +1,47 @@
+#![cfg(feature = "redis")]
+
+use redis_ipc::backfill::Backfill;
+use redis_ipc::stream::{ReadStream, WriteStream};
+use std::time::{Duration, Instant};
+
+mod common;
+
+use common::TestMessage;
+
+/// Checks if all source messages are published and position of the last one is saved.
+#[test]
+fn publishes_source_and_saves_checkpoint() {
+    let name = common::random_string(10);
+    let backfill = Backfill::new(common::build_pool(), &common::random_string(10))
+        .with_checkpoint_every(2);
+
+    assert_eq!(backfill.checkpoint().expect("Cannot read checkpoint"), None);
+
+    let mut stream = WriteStream::<TestMessage>::new(common::build_pool(), &name, 1024);
+    let source = (1..=5).map(|id| (id.to_string(), common::build_test_message()));
+
+    assert_eq!(backfill.run(source, &mut stream).expect("Cannot backfill"), 5);
+    assert_eq!(backfill.checkpoint().expect("Cannot read checkpoint"), Some(String::from("5")));
+
+    let read_stream = ReadStream::<TestMessage>::new(common::build_pool(), &name, None);
+    assert_eq!(read_stream.len().expect("Cannot read length"), 5);
+
+    backfill.reset().expect("Cannot reset");
+    assert_eq!(backfill.checkpoint().expect("Cannot read checkpoint"), None);
+}
+
+/// Checks if publishing is slowed down to the rate limit.
+#[test]
+fn respects_rate_limit() {
+    let backfill = Backfill::new(common::build_pool(), &common::random_string(10))
+        .with_rate_limit(10);
+
+    let mut stream =
+        WriteStream::<TestMessage>::new(common::build_pool(), &common::random_string(10), 1024);
+    let source = (0..5).map(|id| (id.to_string(), common::build_test_message()));
+
+    let started = Instant::now();
+    backfill.run(source, &mut stream).expect("Cannot backfill");
+
+    assert!(started.elapsed() >= Duration::from_millis(400));
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "http-bridge")]
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use redis_ipc::bridge::HttpBridge;
+use serde_json::Value;
+use tower::ServiceExt;
+
+mod common;
+
+use common::TestMessage;
+
+/// Checks if task published through the bridge can be consumed through the bridge.
+#[tokio::test]
+async fn queue_publish_and_next() {
+    let name = common::random_string(10);
+    let router = HttpBridge::new(common::build_pool()).router();
+
+    let msg = common::build_test_message();
+
+    let publish = Request::post(format!("/queues/{name}"))
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_string(&msg).unwrap()))
+        .unwrap();
+
+    let response = router.clone().oneshot(publish).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    let next = Request::get(format!("/queues/{name}?timeout_ms=1000"))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = router.oneshot(next).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let envelope: Value = serde_json::from_slice(&body).unwrap();
+
+    let content: TestMessage = serde_json::from_value(envelope["content"].clone()).unwrap();
+
+    assert_eq!(content, msg);
+}
+
+/// Checks if long-poll returns no content when queue is empty.
+#[tokio::test]
+async fn queue_next_times_out() {
+    let name = common::random_string(10);
+    let router = HttpBridge::new(common::build_pool()).router();
+
+    let next = Request::get(format!("/queues/{name}?timeout_ms=1000"))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = router.oneshot(next).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
@@ -1,8 +1,14 @@
+#![cfg(feature = "redis")]
+#![allow(clippy::clone_on_copy)]
+
 mod common;
-use redis_ipc::cache::Cache;
+use redis::Commands;
+use redis_ipc::cache::{Cache, ConditionalGet, GuardedMap, ReadOnlyCache, ReadRepair};
+use redis_ipc::error::IpcErrorKind;
 use redis_ipc::{Ttl, Timeout};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::thread;
 use crate::common::TestMessage;
@@ -136,6 +142,273 @@ fn element_delete() {
 	assert!(!exists, "Field ${field} should not exist");
 }
 
+#[test]
+fn element_version_bumps() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let cache: Cache<TestMessage> = build_cache(&name, ttl, timeout);
+
+	let field = common::random_string(5);
+	let value = common::build_test_message();
+
+	assert_eq!(cache.get_version(&field).expect("Cannot read version"), None);
+
+	cache.set(&field, &value).expect("Cannot set value");
+	let first = cache.get(&field).unwrap().unwrap().get_version();
+
+	cache.set(&field, &value).expect("Cannot set value");
+	let second = cache.get(&field).unwrap().unwrap().get_version();
+
+	assert!(second > first);
+	assert_eq!(cache.get_version(&field).expect("Cannot read version"), Some(second));
+}
+
+#[test]
+fn element_get_if_modified() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let cache: Cache<TestMessage> = build_cache(&name, ttl, timeout);
+
+	let field = common::random_string(5);
+	let value = common::build_test_message();
+
+	let res = cache.get_if_modified(&field, 0).expect("Conditional get failed");
+	assert!(matches!(res, ConditionalGet::NotFound));
+
+	cache.set(&field, &value).expect("Cannot set value");
+
+	let version = match cache.get_if_modified(&field, 0).expect("Conditional get failed") {
+		ConditionalGet::Modified(element) => {
+			assert_eq!(element.get_content(), &value);
+			element.get_version()
+		}
+		_ => panic!("Element should be modified"),
+	};
+
+	let res = cache.get_if_modified(&field, version).expect("Conditional get failed");
+	assert!(matches!(res, ConditionalGet::NotModified));
+}
+
+#[test]
+fn sharded_set_get_all() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let cache: Cache<TestMessage> = build_cache(&name, ttl, timeout).with_shards(4);
+
+	let value = common::build_test_message();
+	let fields: Vec<String> = (0..10).map(|i| format!("field-{i}")).collect();
+
+	for field in &fields {
+		cache.set(field, &value).expect("Cannot set value");
+	}
+
+	for field in &fields {
+		let element = cache.get(field).unwrap().expect("Element should exist");
+		assert_eq!(element.get_content(), &value);
+	}
+
+	let all = cache.get_all().expect("Cannot read all elements");
+	assert_eq!(all.len(), fields.len());
+
+	let scanned = cache.scan("field-1*").expect("Cannot scan elements");
+	assert_eq!(scanned.len(), 1);
+}
+
+#[test]
+fn read_only_cache_reads_written_element() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let cache: Cache<TestMessage> = build_cache(&name, ttl, timeout);
+	let read_only: ReadOnlyCache<TestMessage> =
+		ReadOnlyCache::new(common::build_pool(), &name, Some(timeout));
+
+	let field = common::random_string(5);
+	let value = common::build_test_message();
+
+	cache.set(&field, &value).expect("Cannot set value");
+
+	let element = read_only.get(&field).unwrap().expect("Element should exist");
+	assert_eq!(element.get_content(), &value);
+	assert!(cache.read_only().exists(&field).expect("Cannot check value existence"));
+}
+
+#[test]
+fn split_cache_communicates() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let cache: Cache<TestMessage> = build_cache(&name, ttl, timeout);
+	let (read_cache, write_cache) = cache.split();
+
+	let field = common::random_string(5);
+	let value = common::build_test_message();
+
+	write_cache.set(&field, &value).expect("Cannot set value");
+
+	let element = read_cache.get(&field).unwrap().expect("Element should exist");
+	assert_eq!(element.get_content(), &value);
+
+	write_cache.delete(&field).expect("Cannot delete value");
+	assert!(!read_cache.exists(&field).expect("Cannot check value existence"));
+}
+
+#[test]
+fn corrupted_element_is_quarantined() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let reported = Arc::new(Mutex::new(Vec::new()));
+	let hook_reported = Arc::clone(&reported);
+
+	let cache: Cache<TestMessage> = build_cache(&name, ttl, timeout)
+		.with_read_repair(ReadRepair::Quarantine)
+		.with_corruption_hook(move |field, raw| {
+			hook_reported.lock().unwrap().push((field.to_string(), raw.to_string()));
+		});
+
+	let field = common::random_string(5);
+
+	let mut conn = common::build_pool().get().unwrap();
+	conn.hset::<&str, &str, &str, ()>(&name, &field, "not json").unwrap();
+
+	assert!(cache.get(&field).expect("Corrupted element should be repaired").is_none());
+	assert!(!cache.exists(&field).expect("Cannot check value existence"));
+	assert_eq!(*reported.lock().unwrap(), vec![(field.clone(), "not json".to_string())]);
+
+	let quarantined = conn
+		.hget::<String, &str, Option<String>>(format!("{name}:quarantine"), &field)
+		.unwrap();
+	assert_eq!(quarantined.as_deref(), Some("not json"));
+}
+
+#[test]
+fn set_if_version_detects_conflict() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let cache: Cache<TestMessage> = build_cache(&name, ttl, timeout);
+
+	let field = common::random_string(5);
+	let value = common::build_test_message();
+
+	let version = cache.set_if_version(&field, 0, &value).expect("Cannot set value");
+	assert_eq!(version, 1);
+
+	// other writer
+	cache.set(&field, &value).expect("Cannot set value");
+
+	let res = cache.set_if_version(&field, version, &value);
+	assert!(matches!(res.unwrap_err().kind(), IpcErrorKind::Conflict));
+
+	let current = cache.get_version(&field).unwrap().expect("Version should exist");
+	assert_eq!(cache.set_if_version(&field, current, &value).unwrap(), current + 1);
+}
+#[test]
+fn auto_refresh_is_done_by_single_leader() {
+	let name = common::random_string(10);
+
+	let ttl = Duration::from_secs(15);
+	let timeout = ttl;
+
+	let cache: Cache<u32> = build_cache(&name, ttl, timeout);
+	let field = common::random_string(5);
+
+	let calls = Arc::new(Mutex::new(0));
+	let interval = Duration::from_millis(100);
+
+	let handles: Vec<_> = (0..2)
+		.map(|_| {
+			let calls = Arc::clone(&calls);
+			cache.auto_refresh(&field, interval, move || {
+				let mut calls = calls.lock().unwrap();
+				*calls += 1;
+				Ok(*calls)
+			})
+		})
+		.collect();
+
+	thread::sleep(Duration::from_millis(450));
+	drop(handles);
+
+	let calls = *calls.lock().unwrap();
+	// only leader refreshes, once per interval
+	assert!((1..=6).contains(&calls));
+	assert_eq!(*cache.get(&field).unwrap().unwrap().get_content(), calls);
+}
+
+
+#[test]
+fn guarded_map_locks_entry() {
+	let name = common::random_string(10);
+	let key = common::random_string(10);
+
+	let cache: Cache<u32> = build_cache(&name, Duration::from_secs(15), Duration::from_millis(300));
+	let map = GuardedMap::new(cache, Duration::from_secs(15));
+
+	let mut guard = map.lock_entry(&key).expect("Cannot lock entry");
+	guard.set(&1).expect("Cannot set entry");
+
+	// entry is locked by the guard
+	match map.lock_entry(&key) {
+		Err(error) => assert!(matches!(error.kind(), IpcErrorKind::Timeout)),
+		Ok(_) => panic!("Entry locked twice"),
+	}
+
+	guard.unlock().expect("Cannot unlock entry");
+
+	let mut guard = map.lock_entry(&key).expect("Cannot lock entry");
+	let value = *guard.get().unwrap().expect("Entry not found").get_content();
+	guard.set(&(value + 1)).expect("Cannot set entry");
+	drop(guard);
+
+	let element = map.get_cache().get(&key).unwrap().expect("Entry not found");
+	assert_eq!(*element.get_content(), 2);
+}
+
+#[test]
+fn deleted_and_expired_elements_are_not_found() {
+	let name = common::random_string(10);
+
+	let cache: Cache<u32> = build_cache(&name, Duration::from_secs(1), Duration::from_secs(1));
+
+	let field = common::random_string(5);
+
+	cache.set(&field, &1).expect("Cannot set value");
+	let version = cache.get_version(&field).unwrap().expect("Version should exist");
+
+	cache.delete(&field).expect("Cannot delete");
+	assert_eq!(cache.get_version(&field).expect("Cannot read version"), None);
+	let res = cache.get_if_modified(&field, version).expect("Conditional get failed");
+	assert!(matches!(res, ConditionalGet::NotFound));
+
+	cache.set(&field, &2).expect("Cannot set value");
+	let version = cache.get_version(&field).unwrap().expect("Version should exist");
+	let res = cache.get_if_modified(&field, version).expect("Conditional get failed");
+	assert!(matches!(res, ConditionalGet::NotModified));
+
+	thread::sleep(Duration::from_millis(2100));
+	assert_eq!(cache.get_version(&field).expect("Cannot read version"), None);
+	let res = cache.get_if_modified(&field, version).expect("Conditional get failed");
+	assert!(matches!(res, ConditionalGet::NotFound));
+}
 
 // ** Helpers **
 fn build_cache<CacheElement: Serialize + DeserializeOwned>(name: &str, ttl: Ttl, timeout: Timeout) -> Cache<CacheElement> {
@@ -1,3 +1,7 @@
+// shared by all test crates, not every crate uses every helper
+#![allow(dead_code)]
+#![cfg(feature = "streams")]
+
 use redis_ipc::{RedisPool, helpers};
 use rand::{distr::Alphanumeric, Rng};
 use std::env;
@@ -12,9 +16,7 @@ pub fn build_pool() -> RedisPool {
     });
 
     let url = env::var("REDIS_URL").expect("Env REDIS_URL not found");
-    let pool = helpers::connect(url).expect("Redis pool cannot be built.");
-
-    pool
+    helpers::connect(url).expect("Redis pool cannot be built.")
 }
 
 pub fn random_string(len: u8) -> String {
@@ -45,4 +47,13 @@ impl PartialEq for TestMessage {
     fn eq(&self, other: &Self) -> bool {
         self.title == other.title
     }
-}
\ No newline at end of file
+}
+#[cfg(feature = "tokio")]
+pub async fn build_async_connection() -> redis_ipc::AsyncRedisConnection {
+    INIT.call_once(|| {
+        let _ = dotenvy::dotenv();
+    });
+
+    let url = env::var("REDIS_URL").expect("Env REDIS_URL not found");
+    helpers::connect_async(&url).await.expect("Redis connection cannot be built.")
+}
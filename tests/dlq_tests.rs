@@ -0,0 +1,162 @@
+#![cfg(feature = "redis")]
+
+use redis::Commands;
+use redis_ipc::dlq::{DeadLetter, DeadLetterQueue, SourceKind};
+use redis_ipc::queue::ReadQueue;
+use redis_ipc::redact::Redactor;
+use serde_json::Value;
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+use common::TestMessage;
+
+/// Checks if pushed dead letters can be listed and inspected.
+#[test]
+fn push_list_inspect() {
+    let dlq = DeadLetterQueue::<TestMessage>::new(common::build_pool(), &common::random_string(10));
+
+    let msg = common::build_test_message();
+    let dead_letter = DeadLetter::new(msg.clone(), "orders", SourceKind::Queue, "handler failed")
+        .expect("Cannot build dead letter")
+        .with_attempts(3)
+        .with_consumer("worker-1");
+
+    dlq.push(&dead_letter).expect("Cannot push dead letter");
+
+    assert_eq!(dlq.len().expect("Cannot read length"), 1);
+
+    let listed = dlq.list(0, 10).expect("Cannot list dead letters");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].get_payload(), &msg);
+    assert_eq!(listed[0].get_attempts(), 3);
+    assert_eq!(listed[0].get_consumer(), Some("worker-1"));
+
+    let inspected = dlq
+        .inspect(dead_letter.get_id())
+        .expect("Cannot inspect dead letter")
+        .expect("Dead letter not found");
+    assert_eq!(inspected.get_error(), "handler failed");
+}
+
+/// Checks if replayed dead letter is published to its original queue and removed from dlq.
+#[test]
+fn replay_entry_to_original_queue() {
+    let queue_name = common::random_string(10);
+    let dlq = DeadLetterQueue::<TestMessage>::new(common::build_pool(), &common::random_string(10));
+
+    let msg = common::build_test_message();
+    let dead_letter = DeadLetter::new(msg.clone(), &queue_name, SourceKind::Queue, "failed")
+        .expect("Cannot build dead letter");
+
+    dlq.push(&dead_letter).expect("Cannot push dead letter");
+
+    assert!(dlq.replay_entry(dead_letter.get_id()).expect("Cannot replay"));
+    assert!(dlq.is_empty().expect("Cannot read length"));
+
+    let timeout = Some(Duration::from_secs(5));
+    let mut queue = ReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, timeout);
+
+    let replayed = queue.b_next().expect("Replayed message not found").expect("No message");
+    assert_eq!(replayed.get_content(), &msg);
+}
+
+/// Checks if unknown envelope fields of dead lettered message survive replay.
+#[test]
+fn replay_keeps_unknown_envelope_fields() {
+    let queue_name = common::random_string(10);
+    let dlq = DeadLetterQueue::<TestMessage>::new(common::build_pool(), &common::random_string(10));
+
+    let mut conn = common::build_pool().get().unwrap();
+    let payload = r#"{"uuid":"1","content":{"title":"a"},"trace_id":"abc"}"#;
+    conn.lpush::<&str, &str, ()>(&queue_name, payload).unwrap();
+
+    let mut queue = ReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, None);
+    let message = queue.next().expect("Cannot read").expect("No message");
+
+    let dead_letter = DeadLetter::from_queue_message(&message, &queue_name, "failed")
+        .expect("Cannot build dead letter");
+    dlq.push(&dead_letter).expect("Cannot push dead letter");
+
+    assert!(dlq.replay_entry(dead_letter.get_id()).expect("Cannot replay"));
+
+    let replayed = queue.next().expect("Cannot read").expect("No message");
+    assert!(replayed.is_redelivered());
+    assert_eq!(replayed.get_extra().get("trace_id"), Some(&Value::from("abc")));
+}
+
+/// Checks if dead letter replayed by many processes at once is published only once.
+#[test]
+fn concurrent_replay_publishes_once() {
+    let queue_name = common::random_string(10);
+    let dlq = DeadLetterQueue::<TestMessage>::new(common::build_pool(), &common::random_string(10));
+
+    let dead_letter =
+        DeadLetter::new(common::build_test_message(), &queue_name, SourceKind::Queue, "failed")
+            .expect("Cannot build dead letter");
+
+    dlq.push(&dead_letter).expect("Cannot push dead letter");
+
+    let replayed: usize = thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| scope.spawn(|| dlq.replay(|_| true, None, None).expect("Cannot replay")))
+            .collect();
+
+        handles.into_iter().map(|handle| handle.join().unwrap()).sum()
+    });
+
+    assert_eq!(replayed, 1);
+    assert!(!dlq.replay_entry(dead_letter.get_id()).expect("Cannot replay"));
+
+    let mut queue = ReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, None);
+    assert!(queue.next().expect("Cannot read").is_some());
+    assert!(queue.next().expect("Cannot read").is_none());
+}
+
+/// Checks if only filtered dead letters are replayed to target queue and marked as redelivered.
+#[test]
+fn replay_filtered_to_target() {
+    let target_name = common::random_string(10);
+    let dlq = DeadLetterQueue::<TestMessage>::new(common::build_pool(), &common::random_string(10));
+
+    let msg = common::build_test_message();
+
+    for error in ["timeout", "invalid", "timeout"] {
+        let dead_letter = DeadLetter::new(msg.clone(), "orders", SourceKind::Queue, error)
+            .expect("Cannot build dead letter");
+        dlq.push(&dead_letter).expect("Cannot push dead letter");
+    }
+
+    let replayed = dlq
+        .replay(|letter| letter.get_error() == "timeout", Some(100), Some(&target_name))
+        .expect("Cannot replay");
+
+    assert_eq!(replayed, 2);
+    assert_eq!(dlq.len().expect("Cannot read length"), 1);
+
+    let timeout = Some(Duration::from_secs(5));
+    let mut queue = ReadQueue::<TestMessage>::new(common::build_pool(), &target_name, timeout);
+
+    let replayed = queue.b_next().expect("Replayed message not found").expect("No message");
+    assert!(replayed.is_redelivered());
+}
+
+/// Checks if payloads of stored dead letters are redacted.
+#[test]
+fn push_redacts_payload() {
+    let dlq = DeadLetterQueue::<TestMessage>::new(common::build_pool(), &common::random_string(10))
+        .with_redaction(Redactor::mask_fields(&["/title"]));
+
+    let msg = common::build_test_message();
+    let dead_letter = DeadLetter::new(msg, "orders", SourceKind::Queue, "failed")
+        .expect("Cannot build dead letter");
+
+    dlq.push(&dead_letter).expect("Cannot push dead letter");
+
+    let inspected = dlq
+        .inspect(dead_letter.get_id())
+        .expect("Cannot inspect dead letter")
+        .expect("Dead letter not found");
+    assert_eq!(inspected.get_payload().title, "[REDACTED]");
+}
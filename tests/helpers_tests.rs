@@ -0,0 +1,81 @@
+#![cfg(feature = "streams")]
+
+use redis_ipc::helpers::{self, ConnectionOptions};
+use std::time::Duration;
+#[cfg(feature = "tokio")]
+use redis_ipc::AsyncWriteQueue;
+
+mod common;
+
+#[test]
+fn warms_up_connections() {
+    let pool = common::build_pool();
+
+    let warmed = helpers::warm_up(&pool, 4).expect("Cannot warm up pool");
+
+    assert_eq!(warmed, 4);
+    assert!(pool.state().idle_connections >= 4);
+}
+
+#[test]
+fn warm_up_is_limited_by_pool_size() {
+    let pool = common::build_pool();
+
+    let warmed = helpers::warm_up(&pool, pool.max_size() + 10).expect("Cannot warm up pool");
+
+    assert_eq!(warmed, pool.max_size());
+}
+
+#[test]
+fn connects_with_options() {
+    let _ = dotenvy::dotenv();
+    let url = std::env::var("REDIS_URL").expect("Env REDIS_URL not found");
+
+    let options = ConnectionOptions::new().with_connect_timeout(Duration::from_secs(5));
+    let pool = helpers::connect_with_options(url, &options).expect("Cannot build pool");
+
+    assert_eq!(helpers::warm_up(&pool, 1).expect("Cannot warm up pool"), 1);
+}
+
+#[test]
+fn sync_pool_rejects_socket_options() {
+    let url = String::from("redis://127.0.0.1/");
+
+    let options = ConnectionOptions::new().with_keepalive(Duration::from_secs(60));
+    assert!(helpers::connect_with_options(url.clone(), &options).is_err());
+
+    let options = ConnectionOptions::new().with_nodelay(true);
+    assert!(helpers::connect_with_options(url, &options).is_err());
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_connection_applies_options() {
+    let _ = dotenvy::dotenv();
+    let url = std::env::var("REDIS_URL").expect("Env REDIS_URL not found");
+
+    let options = ConnectionOptions::new()
+        .with_keepalive(Duration::from_secs(60))
+        .with_nodelay(true)
+        .with_connect_timeout(Duration::from_secs(5));
+    let pool = helpers::connect_async_pool_with_options(&url, 2, &options)
+        .await
+        .expect("Cannot build pool");
+
+    let mut queue = AsyncWriteQueue::<String>::new(pool.get(), &common::random_string(10));
+    queue.publish(&String::from("Hello")).await.expect("Cannot publish");
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_pool_hands_out_connections() {
+    let _ = dotenvy::dotenv();
+    let url = std::env::var("REDIS_URL").expect("Env REDIS_URL not found");
+
+    let pool = helpers::connect_async_pool(&url, 2).await.expect("Cannot build pool");
+
+    assert_eq!(pool.get_size(), 2);
+
+    let mut queue = AsyncWriteQueue::<String>::new(pool.get(), &common::random_string(10));
+    queue.publish(&String::from("Hello")).await.expect("Cannot publish");
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "derive")]
+
+use redis_ipc::message::Message;
+use redis_ipc::IpcMessage;
+
+#[derive(IpcMessage)]
+struct OrderCreated;
+
+#[derive(IpcMessage)]
+#[ipc(name = "user.updated", version = 3, channel = "users")]
+struct UserUpdated;
+
+#[test]
+fn derives_defaults() {
+    assert_eq!(OrderCreated::TYPE_NAME, "OrderCreated");
+    assert_eq!(OrderCreated::SCHEMA_VERSION, 1);
+    assert_eq!(OrderCreated::CHANNEL, "order_created");
+}
+
+#[test]
+fn derives_attributes() {
+    assert_eq!(UserUpdated::TYPE_NAME, "user.updated");
+    assert_eq!(UserUpdated::SCHEMA_VERSION, 3);
+    assert_eq!(UserUpdated::CHANNEL, "users");
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "redis")]
+
+use redis_ipc::error::IpcErrorKind;
+use redis_ipc::notify::Notify;
+use std::thread;
+use std::time::Duration;
+
+mod common;
+
+#[test]
+fn notify_one_stores_permit() {
+    let name = common::random_string(10);
+    let notify = Notify::new(common::build_pool(), &name, Some(Duration::from_secs(1)));
+
+    notify.notify_one().expect("Cannot notify");
+    notify.notify_one().expect("Cannot notify");
+
+    notify.wait().expect("Permit should be stored");
+
+    // only one permit is stored
+    let res = notify.wait();
+    assert!(matches!(res.unwrap_err().kind(), IpcErrorKind::Timeout));
+}
+
+#[test]
+fn notify_all_wakes_all_waiters() {
+    let name = common::random_string(10);
+    let notify = Notify::new(common::build_pool(), &name, Some(Duration::from_secs(5)));
+
+    let waiters: Vec<_> = (0..2)
+        .map(|_| {
+            let notify = notify.clone();
+            thread::spawn(move || notify.wait())
+        })
+        .collect();
+
+    // give waiters time to register
+    thread::sleep(Duration::from_millis(500));
+
+    assert_eq!(notify.notify_all().expect("Cannot notify"), 2);
+
+    for waiter in waiters {
+        waiter.join().unwrap().expect("Waiter should be woken");
+    }
+}
@@ -1,8 +1,21 @@
-use redis_ipc::queue::{WriteQueue, ReadQueue};
+#![cfg(feature = "redis")]
+
+use redis::Commands;
+use redis_ipc::cancel::CancellationToken;
+use redis_ipc::error::IpcErrorKind;
+use redis_ipc::queue::{
+    DecodeMode, FairReadQueue, IdStrategy, MultiReadQueue, QueueReaper, RecurringScheduler,
+    ReliableReadQueue, WriteQueue, ReadQueue,
+};
+use redis_ipc::settings::Settings;
+use redis_ipc::spill::SpillBuffer;
 use redis_ipc::Timeout;
 use serde::{Serialize};
 use serde::de::DeserializeOwned;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use std::thread;
 
 mod common;
@@ -21,11 +34,7 @@ fn publishes_to_write_queue() {
     let _ = queue.publish(&msg);
 }
 
-/// Checks if `ReadQueue::b_next()` returns error when queue is empty and timeout happens.
-/// 
-/// Please be aware that this test should NOT ever panic. It may panic
-/// during queue initialization but that means failure. It should only
-/// end up with queue read error. 
+/// Checks if `ReadQueue::b_next()` returns `None` when queue is empty and timeout happens.
 #[test]
 fn read_queue_timeouts() {
     let queue_name = common::random_string(10);
@@ -33,17 +42,13 @@ fn read_queue_timeouts() {
     // 1s timeout
     let mut queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(1));
 
-    let res = queue.b_next();
+    let res = queue.b_next().expect("Read error");
 
-    assert!(res.is_err())
+    assert!(res.is_none())
 }
 
 
-/// Checks if `ReadQueue::next()` returns error when queue is empty.
-/// 
-/// Please be aware that this test should NOT ever panic. It may panic
-/// during queue initialization but that means failure. It should only
-/// end up with queue read error. 
+/// Checks if `ReadQueue::next()` returns `None` when queue is empty.
 #[test]
 fn read_queue_error_on_empty() {
     let queue_name = common::random_string(10);
@@ -68,9 +73,9 @@ fn write_and_read_queues_communicate() {
 
     let msg = common::build_test_message();
 
-    let _ = write_queue.publish(&msg).expect("Cannot publish");
+    write_queue.publish(&msg).expect("Cannot publish");
 
-    let response = read_queue.b_next().expect("Response error");
+    let response = read_queue.b_next().expect("Response error").expect("No message");
 
     assert_eq!(response.get_content(), &msg);
 }
@@ -85,7 +90,7 @@ fn write_and_read_queues_communicate_non_blocking() {
 
     let msg = common::build_test_message();
 
-    let _ = write_queue.publish(&msg).expect("Cannot publish");
+    write_queue.publish(&msg).expect("Cannot publish");
     
     thread::sleep(Duration::from_secs(1));
     
@@ -94,6 +99,576 @@ fn write_and_read_queues_communicate_non_blocking() {
     assert_eq!(response.get_content(), &msg);
 }
 
+/// Checks if queues built for the same channel communicate with each other.
+#[test]
+fn channel_queues_communicate() {
+    redis_ipc::channel!(TestChannel: TestMessage = "test-channel-queue");
+
+    let mut write_queue = WriteQueue::for_channel::<TestChannel>(common::build_pool());
+    let mut read_queue =
+        ReadQueue::for_channel::<TestChannel>(common::build_pool(), Some(Duration::from_secs(60)));
+
+    let msg = common::build_test_message();
+
+    write_queue.publish(&msg).expect("Cannot publish");
+
+    let response = read_queue.b_next().expect("Response error").expect("No message");
+
+    assert_eq!(response.get_content(), &msg);
+}
+
+#[test]
+fn spills_and_replays_when_redis_is_unreachable() {
+    let queue_name = common::random_string(10);
+    let path = env::temp_dir().join(format!("{}.spill", common::random_string(10)));
+    let spill = SpillBuffer::new(&path);
+
+    // nothing listens on port 1
+    let client = redis::Client::open("redis://127.0.0.1:1").unwrap();
+    let unreachable = r2d2::Pool::builder()
+        .connection_timeout(Duration::from_millis(100))
+        .build_unchecked(client);
+
+    let mut write_queue = WriteQueue::new(unreachable, &queue_name).with_spill(spill.clone());
+    let mut read_queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(5));
+
+    let msg = common::build_test_message();
+
+    write_queue.publish(&msg).expect("Message should be spilled");
+
+    assert_eq!(spill.len().unwrap(), 1);
+
+    let replayed = spill.replay(&common::build_pool()).expect("Cannot replay");
+
+    assert_eq!(replayed, 1);
+    assert!(spill.is_empty().unwrap());
+    let message = read_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(message.get_content(), &msg);
+}
+
+#[test]
+fn reads_with_blocking_pool() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name);
+    let mut read_queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(5))
+        .with_blocking_pool(common::build_pool());
+
+    let msg = common::build_test_message();
+
+    write_queue.publish(&msg).expect("Cannot publish");
+
+    let message = read_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(message.get_content(), &msg);
+}
+
+#[test]
+fn prefetched_messages_are_returned_on_drop() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(5))
+        .with_prefetch(3);
+
+    for i in 0..4 {
+        write_queue.publish(&i).expect("Cannot publish");
+    }
+
+    assert_eq!(*read_queue.b_next().expect("Response error").expect("No message").get_content(), 0);
+    assert_eq!(*read_queue.next().unwrap().unwrap().get_content(), 1);
+
+    drop(read_queue);
+
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(5));
+
+    assert_eq!(*read_queue.next().unwrap().unwrap().get_content(), 2);
+    assert_eq!(*read_queue.next().unwrap().unwrap().get_content(), 3);
+    assert!(read_queue.next().unwrap().is_none());
+}
+
+#[test]
+fn unique_job_is_pending_only_once() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name);
+    let mut read_queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(5));
+
+    let msg = common::build_test_message();
+
+    assert!(write_queue.publish_unique("tenant-1", &msg).unwrap());
+    assert!(!write_queue.publish_unique("tenant-1", &msg).unwrap());
+    assert!(write_queue.publish_unique("tenant-2", &msg).unwrap());
+
+    let job = read_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(job.get_unique_key(), Some("tenant-1"));
+
+    // still in-flight
+    assert!(!write_queue.publish_unique("tenant-1", &msg).unwrap());
+
+    read_queue.release_unique(&job).unwrap();
+
+    assert!(write_queue.publish_unique("tenant-1", &msg).unwrap());
+}
+
+#[test]
+fn unreleased_unique_job_can_be_cleared() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name);
+    let mut read_queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(5));
+    let msg = common::build_test_message();
+
+    assert!(write_queue.publish_unique("tenant-1", &msg).unwrap());
+    // consumer crashes without releasing the key
+    read_queue.b_next().expect("Response error").expect("No message");
+
+    assert_eq!(write_queue.get_unique_keys().unwrap(), vec!["tenant-1".to_string()]);
+    assert!(write_queue.clear_unique("tenant-1").unwrap());
+    assert!(!write_queue.clear_unique("tenant-1").unwrap());
+
+    assert!(write_queue.publish_unique("tenant-1", &msg).unwrap());
+}
+
+#[test]
+fn purge_releases_unique_jobs() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name);
+    let msg = common::build_test_message();
+
+    assert!(write_queue.publish_unique("tenant-1", &msg).unwrap());
+    assert_eq!(write_queue.purge().expect("Cannot purge"), 1);
+
+    assert!(write_queue.publish_unique("tenant-1", &msg).unwrap());
+}
+
+#[test]
+fn cancellation_interrupts_blocking_read() {
+    let queue_name = common::random_string(10);
+
+    let token = CancellationToken::new();
+    let mut read_queue = ReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, None)
+        .with_cancellation(token.clone());
+
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(300));
+        token.cancel();
+    });
+
+    match read_queue.b_next() {
+        Err(error) => assert!(matches!(error.kind(), IpcErrorKind::Cancelled)),
+        Ok(_) => panic!("Read should be cancelled"),
+    }
+
+    // iteration ends after cancellation
+    assert!(Iterator::next(&mut read_queue).is_none());
+
+    canceller.join().unwrap();
+}
+
+#[test]
+fn debounced_publishes_are_collapsed() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(5))
+        .with_scheduled_delivery(true);
+
+    let window = Duration::from_millis(300);
+
+    for i in 0..3 {
+        write_queue.publish_debounced("rebuild", &i, window).expect("Cannot publish");
+    }
+
+    // not due yet
+    assert!(read_queue.next().unwrap().is_none());
+
+    assert_eq!(*read_queue.b_next().expect("Response error").expect("No message").get_content(), 2);
+
+    thread::sleep(window);
+    assert!(read_queue.next().unwrap().is_none());
+}
+
+#[test]
+fn scheduled_messages_are_cancelled_and_rescheduled() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(5))
+        .with_scheduled_delivery(true);
+
+    let cancelled = write_queue.publish_delayed(&1, Duration::from_secs(60)).unwrap();
+    let moved = write_queue.publish_delayed(&2, Duration::from_secs(60)).unwrap();
+
+    assert!(write_queue.cancel_scheduled(&cancelled).unwrap());
+    assert!(!write_queue.cancel_scheduled(&cancelled).unwrap());
+    assert!(write_queue.reschedule(&moved, Duration::from_millis(100)).unwrap());
+
+    let msg = read_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(msg.get_uuid(), moved);
+    assert_eq!(*msg.get_content(), 2);
+
+    // already delivered
+    assert!(!write_queue.reschedule(&moved, Duration::ZERO).unwrap());
+    assert!(read_queue.next().unwrap().is_none());
+}
+
+#[test]
+fn scheduled_backlog_is_visible() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<u32>(&queue_name);
+
+    assert_eq!(write_queue.scheduled_len().unwrap(), 0);
+    assert!(write_queue.next_due().unwrap().is_none());
+
+    let before = SystemTime::now();
+    let soon = write_queue.publish_delayed(&1, Duration::from_secs(10)).unwrap();
+    let later = write_queue.publish_delayed(&2, Duration::from_secs(60)).unwrap();
+
+    assert_eq!(write_queue.scheduled_len().unwrap(), 2);
+
+    let next_due = write_queue.next_due().unwrap().expect("Message should be scheduled");
+    assert!(next_due >= before + Duration::from_secs(9));
+
+    let all = write_queue.list_scheduled(..).unwrap();
+    let keys: Vec<&str> = all.iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(keys, vec![soon.as_str(), later.as_str()]);
+
+    let first = write_queue.list_scheduled(..before + Duration::from_secs(30)).unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].0, soon);
+}
+
+#[test]
+fn messages_published_at_time_are_delivered_when_due() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(5))
+        .with_scheduled_delivery(true);
+
+    let now = SystemTime::now();
+    let late = write_queue.publish_at(&1, now - Duration::from_secs(60)).unwrap();
+    let future = write_queue.publish_at(&2, now + Duration::from_millis(300)).unwrap();
+
+    assert_eq!(read_queue.next().unwrap().expect("Message should be due").get_uuid(), late);
+    assert!(read_queue.next().unwrap().is_none());
+
+    let message = read_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(message.get_uuid(), future);
+}
+
+#[test]
+fn fair_queue_round_robins_tenants() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<String>(&queue_name);
+    let timeout = Some(Duration::from_secs(5));
+    let mut read_queue = FairReadQueue::<String>::new(common::build_pool(), &queue_name, timeout);
+
+    for i in 0..3 {
+        write_queue.publish_for_tenant("a", &format!("a{i}")).expect("Cannot publish");
+    }
+    write_queue.publish_for_tenant("b", &String::from("b0")).expect("Cannot publish");
+
+    let mut order = Vec::new();
+    for _ in 0..4 {
+        let message = read_queue.b_next().expect("Response error").expect("No message");
+        order.push(message.get_content().clone());
+    }
+
+    assert_eq!(order, vec!["a0", "b0", "a1", "a2"]);
+    assert!(read_queue.next().unwrap().is_none());
+}
+
+
+#[test]
+fn messages_get_ids_of_configured_strategy() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name)
+        .with_id_strategy(IdStrategy::custom(|| String::from("order-1")));
+    let mut read_queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(5));
+
+    write_queue.publish(&common::build_test_message()).expect("Cannot publish");
+
+    let response = read_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(response.get_uuid(), "order-1");
+}
+
+
+#[test]
+fn strict_read_queue_rejects_unknown_fields() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<serde_json::Value>(&queue_name);
+    let mut read_queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(5))
+        .with_decode_mode(DecodeMode::Strict);
+
+    let message = serde_json::json!({ "title": "title", "priority": 1 });
+    write_queue.publish(&message).expect("Cannot publish");
+
+    let error = read_queue.b_next().err().expect("Unknown field accepted");
+    assert!(matches!(error.kind(), IpcErrorKind::InvalidData));
+}
+
+
+/// Checks if timeout and prefetch changed on shared settings apply to the next reads.
+#[test]
+fn settings_are_applied_to_next_reads() {
+    let queue_name = common::random_string(10);
+    let settings = Settings::new();
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name);
+    let mut read_queue = build_read_queue::<TestMessage>(&queue_name, Duration::from_secs(60))
+        .with_settings(settings.clone());
+
+    settings.set_timeout(Some(Duration::from_secs(1)));
+    assert!(read_queue.b_next().expect("Response error").is_none());
+
+    settings.set_prefetch(Some(10));
+    for _ in 0..3 {
+        write_queue.publish(&common::build_test_message()).expect("Cannot publish");
+    }
+
+    read_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(read_queue.release_prefetched().expect("Cannot release"), 2);
+}
+
+#[test]
+fn reliable_queue_redelivers_unacked_messages() {
+    let queue_name = common::random_string(10);
+    let timeout = Some(Duration::from_secs(5));
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name);
+    write_queue.publish(&common::build_test_message()).expect("Cannot publish");
+    write_queue.publish(&common::build_test_message()).expect("Cannot publish");
+
+    let mut crashed =
+        ReliableReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, "a", timeout);
+    let lost = crashed.b_next().expect("Response error").expect("No message");
+    assert!(!lost.is_redelivered());
+    drop(crashed);
+
+    let mut restarted =
+        ReliableReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, "a", timeout);
+    let redelivered = restarted.b_next().expect("Response error").expect("No message");
+    assert_eq!(redelivered.get_uuid(), lost.get_uuid());
+    assert!(redelivered.is_redelivered());
+
+    assert!(restarted.ack(redelivered.get_uuid()).expect("Cannot ack"));
+    assert!(!restarted.ack(redelivered.get_uuid()).expect("Cannot ack"));
+
+    let next = restarted.next().expect("Response error").expect("No message");
+    assert!(restarted.ack(next.get_uuid()).expect("Cannot ack"));
+    assert_eq!(restarted.requeue_unacked().expect("Cannot requeue"), 0);
+}
+
+#[test]
+fn reaper_requeues_messages_after_visibility_timeout() {
+    let queue_name = common::random_string(10);
+    let timeout = Some(Duration::from_secs(5));
+    let visibility = Duration::from_millis(200);
+
+    let mut write_queue = build_write_queue::<TestMessage>(&queue_name);
+    write_queue.publish(&common::build_test_message()).expect("Cannot publish");
+    write_queue.publish(&common::build_test_message()).expect("Cannot publish");
+
+    let mut stalled =
+        ReliableReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, "a", timeout)
+            .with_visibility_timeout(visibility);
+    let lost = stalled.b_next().expect("Response error").expect("No message");
+    let touched = stalled.b_next().expect("Response error").expect("No message");
+
+    let reaper = QueueReaper::new(common::build_pool(), &queue_name);
+    assert_eq!(reaper.reap().expect("Cannot reap"), 0);
+
+    thread::sleep(visibility / 2);
+    assert!(stalled.touch(touched.get_uuid()).expect("Cannot touch"));
+    thread::sleep(visibility / 2 + Duration::from_millis(50));
+    assert_eq!(reaper.reap().expect("Cannot reap"), 1);
+
+    let mut other =
+        ReliableReadQueue::<TestMessage>::new(common::build_pool(), &queue_name, "b", timeout);
+    let redelivered = other.b_next().expect("Response error").expect("No message");
+    assert_eq!(redelivered.get_uuid(), lost.get_uuid());
+    assert!(redelivered.is_redelivered());
+
+    assert!(stalled.ack(touched.get_uuid()).expect("Cannot ack"));
+    assert!(!stalled.touch(touched.get_uuid()).expect("Cannot touch"));
+    thread::sleep(visibility);
+    assert_eq!(reaper.reap().expect("Cannot reap"), 0);
+}
+
+#[test]
+fn recurring_jobs_are_published_once_per_interval() {
+    let queue_name = common::random_string(10);
+    let scheduler_name = common::random_string(10);
+    let interval = Duration::from_millis(300);
+
+    let write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(1));
+
+    let scheduler = RecurringScheduler::new(common::build_pool(), &scheduler_name);
+    scheduler.register("report", &write_queue, &7, interval).expect("Cannot register");
+    assert!(scheduler.next_run("report").unwrap().is_some());
+
+    // not due yet
+    assert_eq!(scheduler.tick().expect("Cannot tick"), 0);
+
+    thread::sleep(interval);
+    let other = RecurringScheduler::new(common::build_pool(), &scheduler_name);
+    assert_eq!(scheduler.tick().expect("Cannot tick") + other.tick().expect("Cannot tick"), 1);
+
+    let message = read_queue.next().unwrap().expect("Job message not published");
+    assert_eq!(*message.get_content(), 7);
+    assert!(read_queue.next().unwrap().is_none());
+
+    let handles = [scheduler.clone(), other]
+        .map(|scheduler| scheduler.start(Duration::from_millis(50)));
+    thread::sleep(interval * 2 + Duration::from_millis(100));
+    drop(handles);
+
+    let mut fired = 0;
+    while read_queue.next().unwrap().is_some() {
+        fired += 1;
+    }
+    assert!((1..=2).contains(&fired));
+
+    assert!(scheduler.unregister("report").unwrap());
+    assert!(scheduler.next_run("report").unwrap().is_none());
+}
+
+#[test]
+fn invalid_recurring_jobs_are_skipped_and_reported() {
+    let queue_name = common::random_string(10);
+    let scheduler_name = common::random_string(10);
+    let interval = Duration::from_millis(100);
+
+    let write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(1));
+
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let hook_reported = Arc::clone(&reported);
+    let scheduler = RecurringScheduler::new(common::build_pool(), &scheduler_name)
+        .with_invalid_job_hook(move |job, raw| {
+            hook_reported.lock().unwrap().push((job.to_string(), raw.to_string()));
+        });
+    scheduler.register("broken", &write_queue, &1, interval).expect("Cannot register");
+    scheduler.register("report", &write_queue, &7, interval).expect("Cannot register");
+
+    let mut conn = common::build_pool().get().unwrap();
+    conn.hset::<String, &str, &str, ()>(format!("{scheduler_name}:jobs"), "broken", "{")
+        .unwrap();
+
+    thread::sleep(interval);
+    assert_eq!(scheduler.tick().expect("Cannot tick"), 1);
+    assert_eq!(*reported.lock().unwrap(), vec![("broken".to_string(), "{".to_string())]);
+
+    let message = read_queue.next().unwrap().expect("Job message not published");
+    assert_eq!(*message.get_content(), 7);
+    assert!(read_queue.next().unwrap().is_none());
+}
+
+
+#[test]
+fn queue_can_be_inspected_and_purged() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(1));
+
+    assert!(write_queue.is_empty().unwrap());
+
+    for i in 1..=3 {
+        write_queue.publish(&i).expect("Cannot publish");
+    }
+    assert_eq!(write_queue.len().unwrap(), 3);
+
+    let peeked = read_queue.peek(2).expect("Cannot peek");
+    let contents: Vec<u32> = peeked.iter().map(|message| *message.get_content()).collect();
+    assert_eq!(contents, vec![1, 2]);
+    assert_eq!(read_queue.len().unwrap(), 3);
+
+    let message = read_queue.next().unwrap().expect("No message");
+    assert_eq!(*message.get_content(), 1);
+
+    assert_eq!(read_queue.purge().expect("Cannot purge"), 2);
+    assert!(read_queue.is_empty().unwrap());
+    assert!(read_queue.peek(10).unwrap().is_empty());
+}
+
+
+#[test]
+fn publish_many_pushes_messages_in_order() {
+    let queue_name = common::random_string(10);
+
+    let mut write_queue = build_write_queue::<u32>(&queue_name);
+    let mut read_queue = build_read_queue::<u32>(&queue_name, Duration::from_secs(1));
+
+    assert!(write_queue.publish_many(&[]).expect("Cannot publish").is_empty());
+
+    let result = write_queue.publish_many(&[1, 2, 3]).expect("Cannot publish");
+    let uuids = result.into_result().expect("Message not published");
+    assert_eq!(uuids.len(), 3);
+    assert_eq!(write_queue.len().unwrap(), 3);
+
+    for (uuid, content) in uuids.iter().zip(1..=3) {
+        let message = read_queue.next().unwrap().expect("No message");
+        assert_eq!(message.get_uuid(), uuid);
+        assert_eq!(*message.get_content(), content);
+    }
+}
+
+#[test]
+fn publish_many_reports_messages_which_cant_be_encoded() {
+    let queue_name = common::random_string(10);
+
+    // JSON object keys have to be strings, so only empty map can be encoded
+    let mut write_queue = build_write_queue::<HashMap<Vec<u32>, u32>>(&queue_name);
+    let messages = vec![HashMap::from([(vec![1], 1)]), HashMap::new()];
+
+    let result = write_queue.publish_many(&messages).expect("Cannot publish");
+    assert_eq!(result.failed_indices(), vec![0]);
+    assert!(matches!(result.get_failures()[0].1.kind(), IpcErrorKind::InvalidData));
+    assert_eq!(result.get_successes().len(), 1);
+    assert_eq!(result.get_successes()[0].0, 1);
+    assert_eq!(write_queue.len().unwrap(), 1);
+}
+
+
+#[test]
+fn multi_read_queue_reads_all_queues() {
+    let high_name = common::random_string(10);
+    let low_name = common::random_string(10);
+
+    let mut high = build_write_queue::<u32>(&high_name);
+    let mut low = build_write_queue::<u32>(&low_name);
+
+    let mut multi_queue = MultiReadQueue::<u32>::new(
+        common::build_pool(),
+        &[&high_name, &low_name],
+        Some(Duration::from_secs(1)),
+    );
+
+    assert!(multi_queue.b_next().expect("Response error").is_none());
+
+    low.publish(&2).expect("Cannot publish");
+    high.publish(&1).expect("Cannot publish");
+
+    // earlier queue has priority
+    let message = multi_queue.b_next().expect("Response error").expect("No message");
+    assert_eq!(message.get_queue(), high_name);
+    assert_eq!(*message.get_message().get_content(), 1);
+
+    let message = multi_queue.next().expect("Response error").expect("No message");
+    assert_eq!(message.get_queue(), low_name);
+    assert_eq!(*message.into_message().get_content(), 2);
+
+    assert!(multi_queue.next().expect("Response error").is_none());
+}
+
 
 // *Test helpers*
 
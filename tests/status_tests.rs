@@ -0,0 +1,46 @@
+#![cfg(feature = "streams")]
+
+use redis_ipc::status::{FleetStatus, StatusPublisher};
+use std::time::Duration;
+
+mod common;
+
+/// Checks if only the latest report of every instance is returned.
+#[test]
+fn aggregates_latest_reports() {
+    let namespace = common::random_string(10);
+
+    let first = StatusPublisher::<u32>::new(common::build_pool(), &namespace, "billing", "a");
+    let second = StatusPublisher::<u32>::new(common::build_pool(), &namespace, "billing", "b");
+
+    first.publish(true, 1).expect("Cannot publish status");
+    second.publish(true, 5).expect("Cannot publish status");
+    first.publish(false, 2).expect("Cannot publish status");
+
+    let fleet = FleetStatus::<u32>::new(common::build_pool(), &namespace, Duration::from_secs(60));
+    let reports = fleet.current().expect("Cannot read status");
+
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].get_instance(), "a");
+    assert!(!reports[0].is_healthy());
+    assert_eq!(reports[0].get_details(), &2);
+    assert_eq!(reports[1].get_details(), &5);
+    assert!(reports[1].sent_at().is_some());
+}
+
+/// Checks if started publisher reports status until it's stopped.
+#[test]
+fn started_publisher_reports_periodically() {
+    let namespace = common::random_string(10);
+
+    let publisher = StatusPublisher::new(common::build_pool(), &namespace, "billing", "a");
+    let handle = publisher.start(Duration::from_secs(60), || (true, String::from("ok")));
+    handle.stop();
+
+    let stale_after = Duration::from_secs(60);
+    let fleet = FleetStatus::<String>::new(common::build_pool(), &namespace, stale_after);
+    let reports = fleet.current().expect("Cannot read status");
+
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].get_service(), "billing");
+}
@@ -1,10 +1,21 @@
+#![cfg(feature = "streams")]
+
 mod common;
 
 use common::TestMessage;
+use redis::streams::StreamRangeReply;
+use redis::Commands;
+use redis_ipc::codec::stringify_id;
+use redis_ipc::error::IpcErrorKind;
 use redis_ipc::{Timeout};
-use redis_ipc::stream::{WriteStream, ReadStream};
+use redis_ipc::stream::{
+    ConsumerLag, FieldEncoding, GroupReadStream, MultiReadStream, ReadStream, Retention, StreamId,
+    StreamMessage, WriteStream,
+};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
@@ -27,9 +38,9 @@ fn timeout_on_empty() {
     // 1s timeout
     let stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
 
-    let res = stream.b_next();
+    let res = stream.b_next().expect("Read error");
 
-    assert!(res.is_err())
+    assert!(res.is_none())
 }
 
 #[test]
@@ -79,24 +90,845 @@ fn publishes_and_b_next_communicate() {
         write_stream.publish(&msg_clone).expect("Message can't be published");
     });
 
-    let res = read_stream.b_next().expect("Cannot read stream message.");
+    let res = read_stream.b_next().expect("Cannot read stream message.").expect("No message");
 
     handler.join().unwrap();
 
     assert_eq!(res.get_content(), &msg);
 }
 
+#[test]
+fn forward_preserves_extra_fields() {
+    let name = common::random_string(10);
+    let target_name = common::random_string(10);
+
+    let msg = common::build_test_message();
+    let json = serde_json::to_string(&msg).unwrap();
+
+    // entry published by newer producer, with additional field
+    let mut conn = common::build_pool().get().unwrap();
+    redis::cmd("XADD")
+        .arg(&name)
+        .arg("*")
+        .arg("trace_id")
+        .arg("abc")
+        .arg("content")
+        .arg(&json)
+        .exec(&mut *conn)
+        .expect("Cannot add entry");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(15));
+    let received = read_stream.last().unwrap().expect("No messages on stream");
+
+    let target = build_write_stream::<TestMessage>(&target_name);
+    target.forward(&received).expect("Cannot forward message");
+
+    let target_reader = build_read_stream::<TestMessage>(&target_name, Duration::from_secs(15));
+    let forwarded = target_reader.last().unwrap().expect("No messages on stream");
+
+    assert_eq!(forwarded.get_content(), &msg);
+    assert_eq!(forwarded.get_extra().get("trace_id").map(String::as_str), Some("abc"));
+}
+
+#[test]
+fn buffered_stream_flushes_batches() {
+    let name = common::random_string(10);
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(15));
+    let write_stream =
+        build_write_stream::<TestMessage>(&name).buffered(Duration::from_secs(60), 100);
+
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    let ids = write_stream.flush().expect("Cannot flush");
+
+    assert_eq!(ids.len(), 2);
+    assert_eq!(read_stream.len().expect("Cannot read length"), 2);
+
+    // remaining messages are flushed on drop
+    write_stream.publish(&msg).expect("Cannot publish");
+    drop(write_stream);
+
+    assert_eq!(read_stream.len().expect("Cannot read length"), 3);
+}
+
+#[test]
+fn group_consumers_share_messages() {
+    let name = common::random_string(10);
+    let group = common::random_string(10);
+
+    let first = build_group_stream::<TestMessage>(&name, &group, "first");
+    let second = build_group_stream::<TestMessage>(&name, &group, "second");
+
+    // creates the group, nothing published yet
+    assert!(first.b_next().expect("Response error").is_none());
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    let first_msg = first.b_next().expect("Cannot read stream message.").expect("No message");
+    let second_msg = second.b_next().expect("Cannot read stream message.").expect("No message");
+
+    assert_ne!(first_msg.get_id(), second_msg.get_id());
+    assert!(second.b_next().expect("Response error").is_none());
+
+    assert!(first.ack(first_msg.get_id()).expect("Cannot ack"));
+    assert!(!first.ack(first_msg.get_id()).expect("Cannot ack"));
+}
+
+#[test]
+fn group_consumer_claims_stale_messages() {
+    let name = common::random_string(10);
+    let group = common::random_string(10);
+
+    let crashed = build_group_stream::<TestMessage>(&name, &group, "crashed");
+    let survivor = build_group_stream::<TestMessage>(&name, &group, "survivor");
+
+    assert!(survivor.claim_stale(Duration::ZERO, 10).expect("Cannot claim").is_empty());
+    assert!(crashed.b_next().expect("Response error").is_none());
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    let pending = crashed.b_next().expect("Cannot read stream message.").expect("No message");
+
+    thread::sleep(Duration::from_millis(200));
+
+    assert!(survivor.claim_stale(Duration::from_secs(60), 10).expect("Cannot claim").is_empty());
+
+    let claimed = survivor.claim_stale(Duration::from_millis(100), 10).expect("Cannot claim");
+
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].get_id(), pending.get_id());
+    assert!(survivor.ack(pending.get_id()).expect("Cannot ack"));
+}
+
+#[test]
+fn group_pending_messages_are_inspected() {
+    let name = common::random_string(10);
+    let group = common::random_string(10);
+
+    let consumer = build_group_stream::<TestMessage>(&name, &group, "worker");
+
+    assert_eq!(consumer.pending_summary().expect("Cannot read pending").get_count(), 0);
+    assert!(consumer.b_next().expect("Response error").is_none());
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    let first = consumer.b_next().expect("Cannot read stream message.").expect("No message");
+    let second = consumer.b_next().expect("Cannot read stream message.").expect("No message");
+
+    let summary = consumer.pending_summary().expect("Cannot read pending");
+    assert_eq!(summary.get_count(), 2);
+    assert_eq!(summary.get_oldest_id(), Some(first.get_id()));
+    assert_eq!(summary.get_consumers().get("worker"), Some(&2));
+
+    consumer.ack(first.get_id()).expect("Cannot ack");
+
+    let pending = consumer.pending_of("worker", 10).expect("Cannot read pending");
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].get_id(), second.get_id());
+    assert_eq!(pending[0].get_deliveries(), 1);
+}
+
+#[test]
+fn progress_counts_remaining_messages() {
+    let name = common::random_string(10);
+
+    let caught_up = Arc::new(AtomicUsize::new(0));
+    let read_stream = {
+        let caught_up = Arc::clone(&caught_up);
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(15))
+            .with_caught_up_hook(move || {
+                caught_up.fetch_add(1, Ordering::SeqCst);
+            })
+    };
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    assert!(read_stream.progress().expect("Cannot read progress").is_caught_up());
+
+    let handler = {
+        let msg = msg.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(1));
+            write_stream.publish(&msg).expect("Cannot publish");
+            write_stream.publish(&msg).expect("Cannot publish");
+            write_stream.publish(&msg).expect("Cannot publish");
+        })
+    };
+
+    read_stream.b_next().expect("Cannot read stream message.").expect("No message");
+    handler.join().unwrap();
+
+    let progress = read_stream.progress().expect("Cannot read progress");
+    assert_eq!(progress.get_entries_remaining(), 2);
+    assert!(!progress.is_caught_up());
+
+    read_stream.b_next().expect("Cannot read stream message.").expect("No message");
+    read_stream.b_next().expect("Cannot read stream message.").expect("No message");
+
+    assert!(read_stream.progress().expect("Cannot read progress").is_caught_up());
+    assert_eq!(caught_up.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn read_stream_replays_history() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    let second = write_stream.publish(&msg).expect("Cannot publish");
+
+    let from_beginning =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).from_beginning();
+    assert_eq!(from_beginning.b_next().expect("Cannot read").expect("No message").get_id(), first);
+    assert_eq!(from_beginning.b_next().expect("Cannot read").expect("No message").get_id(), second);
+
+    let from_checkpoint =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).read_from(first);
+    let message = from_checkpoint.b_next().expect("Cannot read").expect("No message");
+    assert_eq!(message.get_id(), second);
+
+    let from_time = build_read_stream::<TestMessage>(&name, Duration::from_secs(1))
+        .from_time(first.to_system_time());
+    assert_eq!(from_time.b_next().expect("Cannot read").expect("No message").get_id(), first);
+}
+
+#[test]
+fn b_next_many_reads_batch() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.publish(&msg).expect("Cannot publish");
+    let last = write_stream.publish(&msg).expect("Cannot publish");
+
+    let read_stream =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).from_beginning();
+
+    let batch = read_stream.b_next_many(2).expect("Cannot read batch");
+    assert_eq!(batch.len(), 2);
+    assert_eq!(batch[0].get_id(), first);
+
+    let batch = read_stream.b_next_many(10).expect("Cannot read batch");
+    assert_eq!(batch.len(), 1);
+    assert_eq!(batch[0].get_id(), last);
+
+    assert!(read_stream.b_next_many(10).expect("Cannot read batch").is_empty());
+}
+
+
+#[test]
+fn read_between_pages_through_messages() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    for _ in 0..4 {
+        write_stream.publish(&msg).expect("Cannot publish");
+    }
+    thread::sleep(Duration::from_millis(5));
+    let after = write_stream.publish(&msg).expect("Cannot publish");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+
+    let since = read_stream
+        .read_since(first.to_system_time())
+        .with_page_size(2)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Cannot read range");
+    assert_eq!(since.len(), 6);
+    assert_eq!(since[0].get_id(), first);
+
+    let between = read_stream
+        .read_between(first.to_system_time(), after.to_system_time())
+        .with_page_size(2)
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Cannot read range");
+    assert_eq!(between.len(), 5);
+    assert!(between.iter().all(|message| message.get_id() < after));
+}
+
+
+#[test]
+fn exported_messages_are_imported() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    let last = write_stream.publish(&msg).expect("Cannot publish");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+
+    let mut archive = Vec::new();
+    let exported = read_stream.export(first..=last, &mut archive).expect("Cannot export");
+    assert_eq!(exported, 2);
+
+    let restored_name = common::random_string(10);
+    let restored = build_write_stream::<TestMessage>(&restored_name);
+    let imported = restored.import(archive.as_slice()).expect("Cannot import");
+    assert_eq!(imported, 2);
+
+    let read_restored =
+        build_read_stream::<TestMessage>(&restored_name, Duration::from_secs(1)).from_beginning();
+    let message = read_restored.b_next().expect("Cannot read").expect("No message");
+    assert_eq!(message.get_id(), first);
+    assert_eq!(message.get_content().title, msg.title);
+}
+
+
+#[test]
+fn range_reads_messages_between_ids() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    let second = write_stream.publish(&msg).expect("Cannot publish");
+    let last = write_stream.publish(&msg).expect("Cannot publish");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+
+    let ids = |range: Vec<Result<StreamMessage<TestMessage>, _>>| {
+        range
+            .into_iter()
+            .map(|message| message.expect("Cannot read range").get_id())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(ids(read_stream.range(first..last).collect()), vec![first, second]);
+    assert_eq!(ids(read_stream.range(..).with_page_size(1).collect()), vec![first, second, last]);
+    assert!(read_stream.range(..StreamId::ZERO).next().is_none());
+
+    let since_first = read_stream.range(StreamId::from(first.to_system_time())..);
+    assert_eq!(ids(since_first.collect()).len(), 3);
+}
+
+
+#[test]
+fn iter_yields_messages_until_timeout() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    let read_stream =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).from_beginning();
+
+    let messages = read_stream
+        .iter()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Cannot read");
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].get_id(), first);
+}
+
+
+#[test]
+fn multi_read_stream_reads_several_streams() {
+    let orders = common::random_string(10);
+    let payments = common::random_string(10);
+
+    let multi_stream = MultiReadStream::<TestMessage>::new(
+        common::build_pool(),
+        &[&orders, &payments],
+        Some(Duration::from_secs(1)),
+    );
+
+    // the first read sets positions of streams
+    assert!(multi_stream.b_next().expect("Response error").is_none());
+
+    let msg = common::build_test_message();
+    let payment = build_write_stream::<TestMessage>(&payments)
+        .publish(&msg)
+        .expect("Cannot publish");
+    let order = build_write_stream::<TestMessage>(&orders)
+        .publish(&msg)
+        .expect("Cannot publish");
+
+    let first = multi_stream.b_next().expect("Cannot read").expect("No message");
+    let second = multi_stream.b_next().expect("Cannot read").expect("No message");
+
+    assert_eq!(first.get_stream(), orders);
+    assert_eq!(first.get_message().get_id(), order);
+    assert_eq!(second.get_stream(), payments);
+    assert_eq!(second.into_message().get_id(), payment);
+}
+
+
+#[test]
+fn publish_many_returns_ids_in_order() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let messages = vec![common::build_test_message(); 3];
+
+    let result = write_stream.publish_many(&messages).expect("Cannot publish");
+    assert!(result.is_complete());
+    let ids = result.into_result().expect("Message not published");
+    assert_eq!(ids.len(), 3);
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let read_stream =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).from_beginning();
+    assert_eq!(read_stream.b_next().expect("Cannot read").expect("No message").get_id(), ids[0]);
+
+    assert!(write_stream.publish_many(&[]).expect("Cannot publish").is_empty());
+}
+
+
+#[test]
+fn publish_many_reports_failed_messages() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name).with_auto_create(false);
+    let messages = vec![common::build_test_message(); 2];
+
+    let result = write_stream.publish_many(&messages).expect("Cannot publish");
+    assert!(!result.is_complete());
+    assert!(result.get_successes().is_empty());
+    assert_eq!(result.failed_indices(), vec![0, 1]);
+    assert!(matches!(result.get_failures()[0].1.kind(), IpcErrorKind::NotFound));
+}
+
+
+#[test]
+fn write_stream_trims_by_retention_and_id() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name)
+        .with_retention(Retention::ExactMaxLen(3));
+    let msg = common::build_test_message();
+
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        ids.push(write_stream.publish(&msg).expect("Cannot publish"));
+    }
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+    assert_eq!(read_stream.len().expect("Cannot read length"), 3);
+
+    assert_eq!(write_stream.trim_before(ids[4]).expect("Cannot trim"), 2);
+    assert_eq!(read_stream.len().expect("Cannot read length"), 1);
+    assert_eq!(write_stream.trim().expect("Cannot trim"), 0);
+}
+
+
+#[test]
+fn publish_with_id_rejects_duplicates() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+
+    let id = StreamId::new(1_000, 1);
+    assert_eq!(write_stream.publish_with_id(id, &msg).expect("Cannot publish"), id);
+
+    let error = write_stream.publish_with_id(id, &msg).unwrap_err();
+    assert!(matches!(error.kind(), IpcErrorKind::Conflict));
+}
+
+
+#[test]
+fn delete_and_ack_delete_remove_messages() {
+    let name = common::random_string(10);
+    let group = common::random_string(10);
+
+    let consumer = build_group_stream::<TestMessage>(&name, &group, "worker");
+
+    // creates the group, nothing published yet
+    assert!(consumer.b_next().expect("Response error").is_none());
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    assert!(write_stream.delete(first).expect("Cannot delete"));
+    assert!(!write_stream.delete(first).expect("Cannot delete"));
+
+    let read = consumer.b_next().expect("Cannot read stream message.").expect("No message");
+    assert!(consumer.ack_and_delete(read.get_id()).expect("Cannot ack"));
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+    assert!(read_stream.is_empty().expect("Cannot read length"));
+}
+
+
+#[test]
+fn stream_info_reports_groups_and_pending() {
+    let name = common::random_string(10);
+    let group = common::random_string(10);
+
+    let consumer = build_group_stream::<TestMessage>(&name, &group, "worker");
+
+    // creates the group, nothing published yet
+    assert!(consumer.b_next().expect("Response error").is_none());
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    let first = write_stream.publish(&msg).expect("Cannot publish");
+    let last = write_stream.publish(&msg).expect("Cannot publish");
+
+    consumer.b_next().expect("Cannot read stream message.").expect("No message");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+    let info = read_stream.stream_info().expect("Cannot read stream info");
+
+    assert_eq!(info.get_length(), 2);
+    assert_eq!(info.get_first_id(), Some(first));
+    assert_eq!(info.get_last_id(), Some(last));
+
+    let groups = info.get_groups();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].get_name(), group);
+    assert_eq!(groups[0].get_pending(), 1);
+    assert_eq!(groups[0].get_consumers()[0].get_name(), "worker");
+}
+
+
+#[test]
+fn flat_encoded_messages_are_read_back() {
+    let name = common::random_string(10);
+
+    let write_stream =
+        build_write_stream::<TestMessage>(&name).with_field_encoding(FieldEncoding::Flat);
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+    let read = read_stream.last().expect("Cannot read").expect("Message not found");
+
+    assert_eq!(read.get_content(), &msg);
+    assert_eq!(read.get_extra().get("title"), Some(&msg.title));
+}
+
+#[test]
+fn buffered_stream_keeps_field_encoding() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name)
+        .with_field_encoding(FieldEncoding::Flat)
+        .buffered(Duration::from_secs(60), 100);
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.flush().expect("Cannot flush");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+    let read = read_stream.last().expect("Cannot read").expect("Message not found");
+
+    assert_eq!(read.get_content(), &msg);
+    assert_eq!(read.get_extra().get("title"), Some(&msg.title));
+}
+
+
+#[test]
+fn publish_without_auto_create_requires_existing_stream() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name).with_auto_create(false);
+    let msg = common::build_test_message();
+
+    assert!(!write_stream.exists().expect("Cannot check stream"));
+    let error = write_stream.publish(&msg).unwrap_err();
+    assert!(matches!(error.kind(), IpcErrorKind::NotFound));
+
+    assert!(write_stream.create().expect("Cannot create stream"));
+    assert!(!write_stream.create().expect("Cannot create stream"));
+    assert!(write_stream.exists().expect("Cannot check stream"));
+
+    write_stream.publish(&msg).expect("Cannot publish");
+}
+
+#[test]
+fn buffered_stream_without_auto_create_requires_existing_stream() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name)
+        .with_auto_create(false)
+        .buffered(Duration::from_secs(60), 100);
+    let msg = common::build_test_message();
+
+    write_stream.publish(&msg).expect("Cannot publish");
+    let error = write_stream.flush().unwrap_err();
+    assert!(matches!(error.kind(), IpcErrorKind::NotFound));
+
+    let stream = build_write_stream::<TestMessage>(&name);
+    assert!(!stream.exists().expect("Cannot check stream"));
+}
+
+#[test]
+fn buffered_stream_keeps_messages_of_failed_flush() {
+    let name = common::random_string(10);
+
+    let stream = build_write_stream::<TestMessage>(&name).with_auto_create(false);
+    let write_stream = stream.clone().buffered(Duration::from_secs(60), 100);
+    let msg = common::build_test_message();
+
+    write_stream.publish(&msg).expect("Cannot publish");
+    assert!(write_stream.flush().is_err());
+
+    // kept message is written once stream exists
+    assert!(stream.create().expect("Cannot create stream"));
+    write_stream.publish(&msg).expect("Cannot publish");
+    assert_eq!(write_stream.flush().expect("Cannot flush").len(), 2);
+
+    write_stream.publish(&msg).expect("Cannot publish");
+    write_stream.close().expect("Cannot close");
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+    assert_eq!(read_stream.len().expect("Cannot read length"), 3);
+}
+
+
+#[test]
+fn weighted_multi_read_stream_drains_important_stream_faster() {
+    let urgent = common::random_string(10);
+    let bulk = common::random_string(10);
+
+    let multi_stream = MultiReadStream::<TestMessage>::new(
+        common::build_pool(),
+        &[&bulk, &urgent],
+        Some(Duration::from_secs(1)),
+    )
+    .with_weights(&[1, 3]);
+
+    // the first read sets positions of streams
+    assert!(multi_stream.b_next().expect("Response error").is_none());
+
+    let msg = common::build_test_message();
+    for name in [&urgent, &bulk] {
+        let write_stream = build_write_stream::<TestMessage>(name);
+        for _ in 0..4 {
+            write_stream.publish(&msg).expect("Cannot publish");
+        }
+    }
+
+    let sources: Vec<String> = (0..4)
+        .map(|_| {
+            let message = multi_stream.b_next().expect("Cannot read").expect("No message");
+            message.get_stream().to_string()
+        })
+        .collect();
+
+    assert_eq!(sources.iter().filter(|source| **source == urgent).count(), 3);
+}
+
+
+#[test]
+fn read_stream_resumes_from_checkpoint() {
+    let name = common::random_string(10);
+    let key = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    let ids: Vec<StreamId> = (0..3)
+        .map(|_| write_stream.publish(&msg).expect("Cannot publish"))
+        .collect();
+
+    let first = build_read_stream::<TestMessage>(&name, Duration::from_secs(1))
+        .from_beginning()
+        .with_checkpoint(&key);
+    first.b_next().expect("Cannot read").expect("No message");
+    // saves the first message as handled
+    first.b_next().expect("Cannot read").expect("No message");
+
+    let restarted =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).with_checkpoint(&key);
+    assert_eq!(restarted.b_next().expect("Cannot read").expect("No message").get_id(), ids[1]);
+    restarted.save_checkpoint().expect("Cannot save checkpoint");
+
+    let restarted =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).with_checkpoint(&key);
+    assert_eq!(restarted.b_next().expect("Cannot read").expect("No message").get_id(), ids[2]);
+}
+
+
+#[test]
+fn lag_reports_unread_messages_and_time() {
+    let name = common::random_string(10);
+    let group = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    for timestamp in [1000, 3000, 6000] {
+        write_stream
+            .publish_with_id(StreamId::new(timestamp, 0), &msg)
+            .expect("Cannot publish");
+    }
+
+    let read_stream =
+        build_read_stream::<TestMessage>(&name, Duration::from_secs(1)).read_from(StreamId::ZERO);
+    read_stream.b_next().expect("Cannot read").expect("No message");
+
+    let lag = read_stream.lag().expect("Cannot read lag");
+    assert_eq!(lag.get_messages(), 2);
+    assert_eq!(lag.get_time(), Duration::from_secs(5));
+
+    // group, which doesn't exist yet, isn't behind
+    let group_stream = build_group_stream::<TestMessage>(&name, &group, "consumer");
+    assert_eq!(group_stream.lag().expect("Cannot read lag"), ConsumerLag::default());
+
+    read_stream.b_next().expect("Cannot read").expect("No message");
+    read_stream.b_next().expect("Cannot read").expect("No message");
+    assert!(read_stream.lag().expect("Cannot read lag").is_caught_up());
+}
+
+#[test]
+fn replay_reads_history_then_tails_new_messages() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    let ids: Vec<StreamId> = (0..3)
+        .map(|_| write_stream.publish(&msg).expect("Cannot publish"))
+        .collect();
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+
+    let history: Vec<StreamId> = read_stream
+        .replay(ids[1]..)
+        .with_page_size(1)
+        .map(|message| message.expect("Cannot replay").get_id())
+        .collect();
+    assert_eq!(history, ids[1..]);
+
+    let mut replay = read_stream.replay(..).with_live_tail(true);
+    for id in &ids {
+        assert_eq!(replay.next().unwrap().expect("Cannot replay").get_id(), *id);
+    }
+
+    let live = write_stream.publish(&msg).expect("Cannot publish");
+    assert_eq!(replay.next().unwrap().expect("Cannot tail").get_id(), live);
+    // reader timeout ends tailing
+    assert!(replay.next().is_none());
+}
+
+#[test]
+fn try_next_returns_none_without_blocking() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<TestMessage>(&name);
+    let msg = common::build_test_message();
+    write_stream.publish(&msg).expect("Cannot publish");
+
+    // reader of new messages skips messages published before its first read
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(60));
+    assert!(read_stream.try_next().expect("Cannot read").is_none());
+
+    let id = write_stream.publish(&msg).expect("Cannot publish");
+    let message = read_stream.try_next().expect("Cannot read").expect("No message");
+    assert_eq!(message.get_id(), id);
+
+    assert!(read_stream.try_next().expect("Cannot read").is_none());
+}
+
+#[test]
+fn amendments_are_folded_into_range() {
+    let name = common::random_string(10);
+
+    let write_stream = build_write_stream::<u32>(&name);
+
+    let first = write_stream.publish(&1).expect("Cannot publish");
+    let second = write_stream.publish(&2).expect("Cannot publish");
+    let third = write_stream.publish(&3).expect("Cannot publish");
+
+    write_stream.publish_correction(first, &10).expect("Cannot correct");
+    write_stream.publish_tombstone(second).expect("Cannot delete");
+    write_stream.publish_correction(first, &11).expect("Cannot correct");
+
+    let read_stream = build_read_stream::<u32>(&name, Duration::from_secs(1));
+    let messages = read_stream
+        .range(..)
+        .with_page_size(2)
+        .resolve_amendments()
+        .expect("Cannot resolve amendments");
+
+    let resolved: Vec<(StreamId, u32)> = messages
+        .iter()
+        .map(|message| (message.get_id(), *message.get_content()))
+        .collect();
+    assert_eq!(resolved, vec![(first, 11), (third, 3)]);
+
+    // readers, which don't resolve amendments, see them as regular messages
+    let read_stream = build_read_stream::<Option<u32>>(&name, Duration::from_secs(1));
+    let amendments: Vec<StreamMessage<Option<u32>>> = read_stream
+        .range(third..)
+        .skip(1)
+        .collect::<Result<_, _>>()
+        .expect("Cannot read range");
+
+    assert_eq!(amendments[0].get_amended_id(), Some(first));
+    assert!(!amendments[0].is_tombstone());
+    assert_eq!(amendments[1].get_amended_id(), Some(second));
+    assert!(amendments[1].is_tombstone());
+    assert!(amendments[1].get_content().is_none());
+}
+
+#[test]
+fn tombstones_keep_field_encoding() {
+    let name = common::random_string(10);
+
+    let write_stream =
+        build_write_stream::<TestMessage>(&name).with_field_encoding(FieldEncoding::Flat);
+
+    let first = write_stream.publish(&common::build_test_message()).expect("Cannot publish");
+    let second = write_stream.publish(&common::build_test_message()).expect("Cannot publish");
+    let tombstone = write_stream.publish_tombstone(first).expect("Cannot delete");
+
+    let mut conn = common::build_pool().get().unwrap();
+    let entries: StreamRangeReply = conn.xrange(&name, stringify_id(&tombstone), "+").unwrap();
+    assert!(!entries.ids[0].map.contains_key("content"));
+
+    let read_stream = build_read_stream::<TestMessage>(&name, Duration::from_secs(1));
+    let messages = read_stream.range(..).resolve_amendments().expect("Cannot resolve amendments");
+
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].get_id(), second);
+}
 
 // **helpers**s
-fn build_write_stream<'a, MessageContent: Serialize>(name: &str) -> WriteStream<MessageContent> {
+fn build_write_stream<MessageContent: Serialize>(name: &str) -> WriteStream<MessageContent> {
     let pool = common::build_pool();
     
     WriteStream::new(pool, name, 1024)
 }
 
-fn build_read_stream<'a, MessageContent: DeserializeOwned>(name: &str, timeout: Timeout) -> ReadStream<MessageContent> {
+fn build_read_stream<MessageContent: DeserializeOwned>(name: &str, timeout: Timeout) -> ReadStream<MessageContent> {
     let pool = common::build_pool();
 
     // timeout 60s
     ReadStream::new(pool, name, Some(timeout))
-}
\ No newline at end of file
+}
+
+fn build_group_stream<MessageContent: DeserializeOwned>(
+    name: &str,
+    group: &str,
+    consumer: &str,
+) -> GroupReadStream<MessageContent> {
+    let pool = common::build_pool();
+
+    GroupReadStream::new(pool, name, group, consumer, Some(Duration::from_secs(1)))
+}